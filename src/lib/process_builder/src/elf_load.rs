@@ -32,8 +32,17 @@ pub enum ElfLoadError {
     GetVmoName(zx::Status),
     #[error("Failed to set VMO name: {}", _0)]
     SetVmoName(zx::Status),
+    #[error("PT_LOAD segment vaddr 0x{:x} and offset 0x{:x} are not congruent modulo page size", _0, _1)]
+    MisalignedSegment(usize, usize),
+    #[error("ELF has {} PT_LOAD segments, which exceeds the limit of {}", _0, MAX_LOAD_SEGMENTS)]
+    TooManySegments(usize),
 }
 
+/// Maximum number of PT_LOAD segments that [load_elf] will process. A malicious or corrupt ELF
+/// with an excessive number of load segments could otherwise exhaust VMAR allocations while
+/// `allocate` walks the program headers.
+const MAX_LOAD_SEGMENTS: usize = 64;
+
 impl ElfLoadError {
     /// Returns an appropriate zx::Status code for the given error.
     pub fn as_zx_status(&self) -> zx::Status {
@@ -47,6 +56,8 @@ impl ElfLoadError {
             | ElfLoadError::VmoWrite(s)
             | ElfLoadError::GetVmoName(s)
             | ElfLoadError::SetVmoName(s) => *s,
+            ElfLoadError::MisalignedSegment(_, _) => zx::Status::NOT_FOUND,
+            ElfLoadError::TooManySegments(_) => zx::Status::NOT_FOUND,
         }
     }
 }
@@ -90,9 +101,16 @@ impl ElfVmar {
     /// Allocates a new VMAR within the given root VMAR large enough and with appropriate mapping
     /// permissions for the given ELF file. The kernel chooses where the VMAR is located for ASLR.
     fn allocate(root_vmar: &zx::Vmar, headers: &elf::Elf64Headers) -> Result<Self, ElfLoadError> {
+        let num_segments = headers.program_headers_with_type(elf::SegmentType::Load).count();
+        if num_segments > MAX_LOAD_SEGMENTS {
+            return Err(ElfLoadError::TooManySegments(num_segments));
+        }
+
         let (mut first, mut low, mut high) = (false, 0, 0);
         let mut max_perm = elf::SegmentFlags::empty();
         for hdr in headers.program_headers_with_type(elf::SegmentType::Load) {
+            check_segment_alignment(hdr.vaddr, hdr.offset)?;
+
             // elf_parse already checked that segments are ordered by vaddr and do not overlap.
             if first {
                 low = util::page_start(hdr.vaddr);
@@ -255,6 +273,15 @@ fn vmo_name_with_prefix(name: &CStr, prefix: &[u8]) -> CString {
     CString::new(buf).expect("Unexpected nul byte in prefix")
 }
 
+// The kernel maps whole pages, so a PT_LOAD segment's vaddr and offset must be congruent modulo
+// page size or the file contents would land at the wrong offset within the mapped page.
+fn check_segment_alignment(vaddr: usize, offset: usize) -> Result<(), ElfLoadError> {
+    if vaddr.wrapping_sub(offset) % util::PAGE_SIZE != 0 {
+        return Err(ElfLoadError::MisalignedSegment(vaddr, offset));
+    }
+    Ok(())
+}
+
 fn elf_to_vmar_can_map_flags(elf_flags: &elf::SegmentFlags) -> zx::VmarFlags {
     let mut flags = zx::VmarFlags::empty();
     if elf_flags.contains(elf::SegmentFlags::READ) {
@@ -324,4 +351,95 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_check_segment_alignment() {
+        // vaddr and offset are both page aligned.
+        assert!(check_segment_alignment(0x4000, 0x1000).is_ok());
+        // vaddr and offset differ by a whole number of pages.
+        assert!(check_segment_alignment(0x4123, 0x1123).is_ok());
+
+        match check_segment_alignment(0x4123, 0x1000) {
+            Err(ElfLoadError::MisalignedSegment(vaddr, offset)) => {
+                assert_eq!((vaddr, offset), (0x4123, 0x1000));
+            }
+            other => panic!("Expected ElfLoadError::MisalignedSegment, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_too_many_load_segments_rejected() -> Result<(), Error> {
+        let num_segments = MAX_LOAD_SEGMENTS + 1;
+        let vmo = zx::Vmo::create(crafted_elf_with_load_segments(num_segments).len() as u64)?;
+        vmo.write(&crafted_elf_with_load_segments(num_segments), 0)?;
+
+        let headers = elf::Elf64Headers::from_vmo(&vmo)?;
+        match ElfVmar::allocate(&fuchsia_runtime::vmar_root_self(), &headers) {
+            Err(ElfLoadError::TooManySegments(found)) => assert_eq!(found, num_segments),
+            other => panic!("Expected ElfLoadError::TooManySegments, got {:?}", other),
+        }
+        Ok(())
+    }
+
+    // Builds a minimal, otherwise-valid ELF file header followed by `num_segments` distinct,
+    // non-overlapping PT_LOAD program headers, to exercise the PT_LOAD segment count cap without
+    // needing a real, fully linked executable with that many segments.
+    fn crafted_elf_with_load_segments(num_segments: usize) -> Vec<u8> {
+        use elf::{Elf64FileHeader, Elf64ProgramHeader, ElfArchitecture, ElfDataEncoding, ElfIdent, ElfType, ElfVersion};
+
+        #[cfg(target_arch = "x86_64")]
+        const CURRENT_ARCH: ElfArchitecture = ElfArchitecture::X86_64;
+        #[cfg(target_arch = "aarch64")]
+        const CURRENT_ARCH: ElfArchitecture = ElfArchitecture::AARCH64;
+
+        let file_header = Elf64FileHeader {
+            ident: ElfIdent {
+                magic: *b"\x7fELF",
+                class: 2, // ELFCLASS64
+                data: ElfDataEncoding::LittleEndian as u8,
+                version: ElfVersion::Current as u8,
+                osabi: 0,
+                abiversion: 0,
+                pad: [0; 7],
+            },
+            elf_type: ElfType::SharedObject as u16,
+            machine: CURRENT_ARCH as u16,
+            version: 1,
+            entry: 0,
+            phoff: std::mem::size_of::<Elf64FileHeader>(),
+            shoff: 0,
+            flags: 0,
+            ehsize: std::mem::size_of::<Elf64FileHeader>() as u16,
+            phentsize: std::mem::size_of::<Elf64ProgramHeader>() as u16,
+            phnum: num_segments as u16,
+            shentsize: 0,
+            shnum: 0,
+            shstrndx: 0,
+        };
+
+        // Safe because these are #[repr(C)] plain-old-data structs with no padding-sensitive
+        // invariants, and the returned slice does not outlive the struct it borrows from.
+        fn struct_bytes<T>(s: &T) -> &[u8] {
+            unsafe {
+                std::slice::from_raw_parts(s as *const T as *const u8, std::mem::size_of::<T>())
+            }
+        }
+
+        let mut data = struct_bytes(&file_header).to_vec();
+        for i in 0..num_segments {
+            let vaddr = i * util::PAGE_SIZE;
+            let phdr = Elf64ProgramHeader {
+                segment_type: 1, // PT_LOAD
+                flags: 0b0100,   // READ
+                offset: vaddr,
+                vaddr,
+                paddr: vaddr,
+                filesz: 0,
+                memsz: util::PAGE_SIZE as u64,
+                align: util::PAGE_SIZE as u64,
+            };
+            data.extend_from_slice(struct_bytes(&phdr));
+        }
+        data
+    }
 }
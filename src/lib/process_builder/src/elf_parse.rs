@@ -29,6 +29,12 @@ pub enum ElfParseError {
     InvalidProgramHeader(&'static str),
     #[error("Multiple ELF program headers of type {} present", _0)]
     MultipleHeaders(SegmentType),
+    #[error("ELF file is 32-bit, only 64-bit ELF files are supported")]
+    Not64Bit,
+    #[error("ELF file is for the wrong architecture, expected {:?} but found machine type {:#x}", _0, _1)]
+    WrongArchitecture(ElfArchitecture, u16),
+    #[error("ELF file is too small: must be at least {} bytes to hold a file header", _0)]
+    TooSmall(usize),
 }
 
 impl ElfParseError {
@@ -40,8 +46,11 @@ impl ElfParseError {
             // matches elf_load.
             ElfParseError::ParseError(_)
             | ElfParseError::InvalidFileHeader(_)
-            | ElfParseError::InvalidProgramHeader(_) => zx::Status::NOT_FOUND,
+            | ElfParseError::InvalidProgramHeader(_)
+            | ElfParseError::Not64Bit
+            | ElfParseError::WrongArchitecture(_, _) => zx::Status::NOT_FOUND,
             ElfParseError::MultipleHeaders(_) => zx::Status::NOT_FOUND,
+            ElfParseError::TooSmall(_) => zx::Status::NOT_FOUND,
         }
     }
 }
@@ -174,8 +183,10 @@ impl Validate for Elf64FileHeader {
         if self.ident.magic != ELF_MAGIC {
             return Err(ElfParseError::InvalidFileHeader("Invalid ELF magic"));
         }
-        if self.ident.class() != Ok(ElfClass::Elf64) {
-            return Err(ElfParseError::InvalidFileHeader("Invalid ELF class"));
+        match self.ident.class() {
+            Ok(ElfClass::Elf64) => {}
+            Ok(ElfClass::Elf32) => return Err(ElfParseError::Not64Bit),
+            _ => return Err(ElfParseError::InvalidFileHeader("Invalid ELF class")),
         }
         if self.ident.data() != Ok(NATIVE_ENCODING) {
             return Err(ElfParseError::InvalidFileHeader("Invalid ELF data encoding"));
@@ -191,8 +202,8 @@ impl Validate for Elf64FileHeader {
                 "2^16 or more ELF program headers is unsupported",
             ));
         }
-        if self.machine() != Ok(CURRENT_ARCH) {
-            return Err(ElfParseError::InvalidFileHeader("Invalid ELF architecture"));
+        if self.machine != CURRENT_ARCH as u16 {
+            return Err(ElfParseError::WrongArchitecture(CURRENT_ARCH, self.machine));
         }
         if self.elf_type() != Ok(ElfType::SharedObject) {
             return Err(ElfParseError::InvalidFileHeader(
@@ -226,6 +237,21 @@ pub enum SegmentType {
     GnuStack = 0x6474e551, // PT_GNU_STACK
 }
 
+#[derive(FromBytes, Debug, Eq, PartialEq)]
+#[repr(C)]
+pub struct Elf64SectionHeader {
+    pub name: u32,
+    pub section_type: u32,
+    pub flags: u64,
+    pub addr: usize,
+    pub offset: usize,
+    pub size: u64,
+    pub link: u32,
+    pub info: u32,
+    pub addralign: u64,
+    pub entsize: u64,
+}
+
 bitflags! {
     pub struct SegmentFlags: u32 {
         const EXECUTE = 0b0001;
@@ -273,7 +299,11 @@ impl Validate for [Elf64ProgramHeader] {
                             "Overlap in virtual addresses",
                         ));
                     }
-                    vaddr_high = hdr.vaddr + hdr.memsz as usize;
+                    // Use checked arithmetic since vaddr/memsz come straight from the (possibly
+                    // malicious) file and could otherwise overflow and panic.
+                    vaddr_high = hdr.vaddr.checked_add(hdr.memsz as usize).ok_or(
+                        ElfParseError::InvalidProgramHeader("vaddr + memsz overflows"),
+                    )?;
                 }
                 Ok(SegmentType::GnuStack) => {
                     if hdr.flags().contains(SegmentFlags::EXECUTE) {
@@ -292,6 +322,22 @@ impl Validate for [Elf64ProgramHeader] {
     }
 }
 
+// The Elf64_Dyn structure from the System V ABI, one entry per PT_DYNAMIC slot. Only the few
+// dynamic tags consumed by [Elf64Headers::needed_libraries] and [Elf64Headers::runpaths] are
+// given names below; everything else is read and skipped over.
+#[derive(FromBytes, Debug, Eq, PartialEq)]
+#[repr(C)]
+struct Elf64Dyn {
+    tag: i64,
+    val: u64,
+}
+
+const DT_NEEDED: i64 = 1;
+const DT_STRTAB: i64 = 5;
+const DT_STRSZ: i64 = 10;
+const DT_RPATH: i64 = 15;
+const DT_RUNPATH: i64 = 29;
+
 pub struct Elf64Headers {
     // These headers are read straight out of a VMO and then parsed with zerocopy, so we use
     // OwningRef to keep ownership of the underlying bytes and hold a reference to the parsed
@@ -299,14 +345,17 @@ pub struct Elf64Headers {
     // and hide this detail.
     file_header: OwningRef<Vec<u8>, Elf64FileHeader>,
     program_headers: Option<OwningRef<Vec<u8>, [Elf64ProgramHeader]>>,
-    // Section headers are not parsed currently since they aren't needed for the current use case,
-    // but could be added if needed.
+    section_headers: Option<OwningRef<Vec<u8>, [Elf64SectionHeader]>>,
 }
 
 impl Elf64Headers {
     pub fn from_vmo(vmo: &zx::Vmo) -> Result<Elf64Headers, ElfParseError> {
         // Read and parse the ELF file header from the VMO.
         let file_hdr_len = mem::size_of::<Elf64FileHeader>();
+        let vmo_size = vmo.get_size().map_err(|s| ElfParseError::ReadError(s))? as usize;
+        if vmo_size < file_hdr_len {
+            return Err(ElfParseError::TooSmall(file_hdr_len));
+        }
         let mut data = vec![0u8; file_hdr_len];
         vmo.read(&mut data[..], 0).map_err(|s| ElfParseError::ReadError(s))?;
         let data_oref = OwningRef::new(data);
@@ -333,7 +382,23 @@ impl Elf64Headers {
             program_headers = Some(phdrs);
         }
 
-        Ok(Elf64Headers { file_header, program_headers })
+        // Read and parse the ELF section headers from the VMO, same as program headers above.
+        let mut section_headers = None;
+        let shdrs_size = file_header.shnum as usize * mem::size_of::<Elf64SectionHeader>();
+        if shdrs_size > 0 {
+            let mut shdrs_data = vec![0; shdrs_size];
+            vmo.read(&mut shdrs_data[..], file_header.shoff as u64)
+                .map_err(|s| ElfParseError::ReadError(s))?;
+            let shdrs_data_oref = OwningRef::new(shdrs_data);
+            let shdrs = shdrs_data_oref.try_map(|v| {
+                LayoutVerified::new_slice(v)
+                    .ok_or(ElfParseError::ParseError("Failed to parse ELF64 section headers"))
+                    .map(|lv| lv.into_slice())
+            })?;
+            section_headers = Some(shdrs);
+        }
+
+        Ok(Elf64Headers { file_header, program_headers, section_headers })
     }
 
     pub fn file_header(&self) -> &Elf64FileHeader {
@@ -347,6 +412,15 @@ impl Elf64Headers {
         }
     }
 
+    /// Returns the already-parsed ELF64 section headers, letting callers inspect a large VMO's
+    /// sections without re-reading it. Returns an empty slice if the ELF has no section headers.
+    pub fn section_headers(&self) -> &[Elf64SectionHeader] {
+        match &self.section_headers {
+            Some(own_ref) => &*own_ref,
+            None => &[],
+        }
+    }
+
     /// Returns an iterator that yields all program headers of the given type.
     pub fn program_headers_with_type(
         &self,
@@ -371,6 +445,150 @@ impl Elf64Headers {
         }
         return Ok(header);
     }
+
+    /// Returns the list of `DT_NEEDED` shared library names from this ELF's `PT_DYNAMIC` segment,
+    /// i.e. the libraries the dynamic linker must load for this executable to run. Returns an
+    /// empty list if there is no `PT_DYNAMIC` segment (e.g. a statically linked executable) or it
+    /// has no `DT_NEEDED` entries.
+    ///
+    /// `vmo` must be the same VMO this [Elf64Headers] was parsed from, since the `DT_STRTAB`
+    /// string table is read from it directly rather than cached.
+    ///
+    /// Note: this crate has no `inspect_executable` function or `ExecutableInfo` type to expose
+    /// this list through; this method is the public entry point for now.
+    pub fn needed_libraries(&self, vmo: &zx::Vmo) -> Result<Vec<String>, ElfParseError> {
+        let dynamic_hdr = match self.program_header_with_type(SegmentType::Dynamic)? {
+            Some(hdr) => hdr,
+            None => return Ok(vec![]),
+        };
+
+        let mut dyn_data = vec![0u8; dynamic_hdr.filesz as usize];
+        vmo.read(&mut dyn_data, dynamic_hdr.offset as u64).map_err(ElfParseError::ReadError)?;
+        let dyn_entries = LayoutVerified::<&[u8], [Elf64Dyn]>::new_slice(&dyn_data[..])
+            .ok_or(ElfParseError::ParseError("Failed to parse PT_DYNAMIC entries"))?
+            .into_slice();
+
+        let mut needed_offsets = vec![];
+        let mut strtab_vaddr = None;
+        let mut strtab_size = None;
+        for entry in dyn_entries {
+            match entry.tag {
+                DT_NEEDED => needed_offsets.push(entry.val as usize),
+                DT_STRTAB => strtab_vaddr = Some(entry.val as usize),
+                DT_STRSZ => strtab_size = Some(entry.val as usize),
+                _ => {}
+            }
+        }
+        if needed_offsets.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let strtab_vaddr = strtab_vaddr
+            .ok_or(ElfParseError::ParseError("PT_DYNAMIC is missing a DT_STRTAB entry"))?;
+        let strtab_size = strtab_size
+            .ok_or(ElfParseError::ParseError("PT_DYNAMIC is missing a DT_STRSZ entry"))?;
+
+        // DT_STRTAB gives a virtual address, not a file offset, so translate it via whichever
+        // PT_LOAD segment covers it.
+        let strtab_file_offset = self
+            .program_headers_with_type(SegmentType::Load)
+            .find(|hdr| strtab_vaddr >= hdr.vaddr && strtab_vaddr < hdr.vaddr + hdr.filesz as usize)
+            .map(|hdr| hdr.offset + (strtab_vaddr - hdr.vaddr))
+            .ok_or(ElfParseError::ParseError(
+                "DT_STRTAB address is not covered by any PT_LOAD segment",
+            ))?;
+
+        let mut strtab_data = vec![0u8; strtab_size];
+        vmo.read(&mut strtab_data, strtab_file_offset as u64).map_err(ElfParseError::ReadError)?;
+
+        needed_offsets
+            .into_iter()
+            .map(|off| {
+                let rest = strtab_data.get(off..).ok_or(ElfParseError::ParseError(
+                    "DT_NEEDED string table offset is out of bounds",
+                ))?;
+                let end = rest.iter().position(|&b| b == 0).ok_or(ElfParseError::ParseError(
+                    "DT_NEEDED string table entry is not nul-terminated",
+                ))?;
+                String::from_utf8(rest[..end].to_vec())
+                    .map_err(|_| ElfParseError::ParseError("DT_NEEDED string is not valid UTF-8"))
+            })
+            .collect()
+    }
+
+    /// Returns the list of `DT_RPATH`/`DT_RUNPATH` search path entries from this ELF's
+    /// `PT_DYNAMIC` segment, i.e. the extra directories the dynamic linker would search for
+    /// `DT_NEEDED` libraries. Fuchsia binaries are expected to have none of these, since library
+    /// resolution goes through package namespacing instead; the value of this method is in
+    /// flagging the unexpected case where one is present. Returns an empty list if there is no
+    /// `PT_DYNAMIC` segment, or it has neither a `DT_RPATH` nor a `DT_RUNPATH` entry.
+    ///
+    /// `vmo` must be the same VMO this [Elf64Headers] was parsed from, since the `DT_STRTAB`
+    /// string table is read from it directly rather than cached.
+    ///
+    /// Note: this crate has no `inspect_executable` function or `ExecutableInfo` type to expose
+    /// this list through; this method is the public entry point for now.
+    pub fn runpaths(&self, vmo: &zx::Vmo) -> Result<Vec<String>, ElfParseError> {
+        let dynamic_hdr = match self.program_header_with_type(SegmentType::Dynamic)? {
+            Some(hdr) => hdr,
+            None => return Ok(vec![]),
+        };
+
+        let mut dyn_data = vec![0u8; dynamic_hdr.filesz as usize];
+        vmo.read(&mut dyn_data, dynamic_hdr.offset as u64).map_err(ElfParseError::ReadError)?;
+        let dyn_entries = LayoutVerified::<&[u8], [Elf64Dyn]>::new_slice(&dyn_data[..])
+            .ok_or(ElfParseError::ParseError("Failed to parse PT_DYNAMIC entries"))?
+            .into_slice();
+
+        let mut runpath_offsets = vec![];
+        let mut strtab_vaddr = None;
+        let mut strtab_size = None;
+        for entry in dyn_entries {
+            match entry.tag {
+                DT_RPATH | DT_RUNPATH => runpath_offsets.push(entry.val as usize),
+                DT_STRTAB => strtab_vaddr = Some(entry.val as usize),
+                DT_STRSZ => strtab_size = Some(entry.val as usize),
+                _ => {}
+            }
+        }
+        if runpath_offsets.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let strtab_vaddr = strtab_vaddr
+            .ok_or(ElfParseError::ParseError("PT_DYNAMIC is missing a DT_STRTAB entry"))?;
+        let strtab_size = strtab_size
+            .ok_or(ElfParseError::ParseError("PT_DYNAMIC is missing a DT_STRSZ entry"))?;
+
+        // DT_STRTAB gives a virtual address, not a file offset, so translate it via whichever
+        // PT_LOAD segment covers it.
+        let strtab_file_offset = self
+            .program_headers_with_type(SegmentType::Load)
+            .find(|hdr| strtab_vaddr >= hdr.vaddr && strtab_vaddr < hdr.vaddr + hdr.filesz as usize)
+            .map(|hdr| hdr.offset + (strtab_vaddr - hdr.vaddr))
+            .ok_or(ElfParseError::ParseError(
+                "DT_STRTAB address is not covered by any PT_LOAD segment",
+            ))?;
+
+        let mut strtab_data = vec![0u8; strtab_size];
+        vmo.read(&mut strtab_data, strtab_file_offset as u64).map_err(ElfParseError::ReadError)?;
+
+        // Each DT_RPATH/DT_RUNPATH entry is itself a single colon-separated list of directories,
+        // so split them out into individual paths once decoded.
+        let mut runpaths = vec![];
+        for off in runpath_offsets {
+            let rest = strtab_data
+                .get(off..)
+                .ok_or(ElfParseError::ParseError("DT_RUNPATH string table offset is out of bounds"))?;
+            let end = rest.iter().position(|&b| b == 0).ok_or(ElfParseError::ParseError(
+                "DT_RUNPATH string table entry is not nul-terminated",
+            ))?;
+            let value = String::from_utf8(rest[..end].to_vec())
+                .map_err(|_| ElfParseError::ParseError("DT_RUNPATH string is not valid UTF-8"))?;
+            runpaths.extend(value.split(':').map(|s| s.to_string()));
+        }
+        Ok(runpaths)
+    }
 }
 
 #[cfg(test)]
@@ -428,16 +646,47 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_too_small() -> Result<(), Error> {
+        let vmo = zx::Vmo::create(4)?;
+        vmo.write(&[0u8; 4], 0)?;
+
+        match Elf64Headers::from_vmo(&vmo) {
+            Err(ElfParseError::TooSmall(expected)) => {
+                assert_eq!(expected, mem::size_of::<Elf64FileHeader>());
+            }
+            other => panic!("Expected ElfParseError::TooSmall, got {:?}", other.err()),
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_parse_wrong_arch() -> Result<(), Error> {
         let vmo = zx::Vmo::create(HEADER_DATA_WRONG_ARCH.len() as u64)?;
-        vmo.write(&HEADER_DATA, 0)?;
+        vmo.write(&HEADER_DATA_WRONG_ARCH, 0)?;
 
         match Elf64Headers::from_vmo(&vmo) {
-            Err(ElfParseError::InvalidFileHeader(msg)) => {
-                assert_eq!(msg, "Invalid ELF architecture");
+            Err(ElfParseError::WrongArchitecture(expected, _found)) => {
+                assert_eq!(expected, CURRENT_ARCH);
             }
-            _ => {}
+            other => panic!("Expected ElfParseError::WrongArchitecture, got {:?}", other.err()),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_32bit_rejected() -> Result<(), Error> {
+        // Flip the class field of an otherwise-valid header to ELFCLASS32 and confirm it's
+        // rejected rather than silently parsed as if it were 64-bit.
+        let mut data = HEADER_DATA.to_vec();
+        data[4] = ElfClass::Elf32 as u8;
+
+        let vmo = zx::Vmo::create(data.len() as u64)?;
+        vmo.write(&data, 0)?;
+
+        match Elf64Headers::from_vmo(&vmo) {
+            Err(ElfParseError::Not64Bit) => {}
+            other => panic!("Expected ElfParseError::Not64Bit, got {:?}", other.err()),
         }
         Ok(())
     }
@@ -457,6 +706,56 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_section_headers() -> Result<(), Error> {
+        // Parse ourselves; a normal dynamically linked binary has several sections.
+        let file = File::open("/pkg/bin/process_builder_lib_test")?;
+        let vmo = fdio::get_vmo_copy_from_file(&file)?;
+
+        let headers = Elf64Headers::from_vmo(&vmo)?;
+        assert!(headers.section_headers().len() > 0);
+        // Index 0 is always the reserved, all-zero SHN_UNDEF section header.
+        assert_eq!(headers.section_headers()[0].section_type, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_needed_libraries() -> Result<(), Error> {
+        // Parse ourselves; any dynamically linked test binary needs at least libc.
+        let file = File::open("/pkg/bin/process_builder_lib_test")?;
+        let vmo = fdio::get_vmo_copy_from_file(&file)?;
+
+        let headers = Elf64Headers::from_vmo(&vmo)?;
+        let needed = headers.needed_libraries(&vmo)?;
+        assert!(!needed.is_empty());
+        assert!(needed.iter().any(|lib| lib.contains("libc")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_needed_libraries_static_pie_has_none() -> Result<(), Error> {
+        // A statically linked PIE has no PT_DYNAMIC DT_NEEDED entries (it may still have a
+        // PT_DYNAMIC segment for relocations, but no needed libraries).
+        let file = File::open("/pkg/bin/static_pie_test_util")?;
+        let vmo = fdio::get_vmo_copy_from_file(&file)?;
+
+        let headers = Elf64Headers::from_vmo(&vmo)?;
+        assert_eq!(headers.needed_libraries(&vmo)?, Vec::<String>::new());
+        Ok(())
+    }
+
+    #[test]
+    fn test_runpaths_of_dynamic_test_util_is_empty() -> Result<(), Error> {
+        // Fuchsia binaries are built without RPATH/RUNPATH entries; library resolution goes
+        // through package namespacing instead.
+        let file = File::open("/pkg/bin/process_builder_lib_test")?;
+        let vmo = fdio::get_vmo_copy_from_file(&file)?;
+
+        let headers = Elf64Headers::from_vmo(&vmo)?;
+        assert_eq!(headers.runpaths(&vmo)?, Vec::<String>::new());
+        Ok(())
+    }
+
     #[test]
     fn test_parse_static_pie() -> Result<(), Error> {
         // Parse the statically linked PIE test binary.
@@ -471,4 +770,67 @@ mod tests {
         assert!(headers.program_headers_with_type(SegmentType::Load).count() > 1);
         Ok(())
     }
+
+    #[test]
+    fn test_parse_malformed_never_panics() -> Result<(), Error> {
+        // Regression test for fxbug.dev fuzzer findings: none of these malformed inputs should
+        // cause `from_vmo` to panic, even though several of them are not even well-formed enough
+        // to make it past the file header before failing.
+        let mut malformed_inputs: Vec<Vec<u8>> = vec![
+            // Empty and arbitrarily-sized garbage that's too small to hold a file header.
+            vec![],
+            vec![0u8; 1],
+            vec![0xffu8; 17],
+            // Just the ELF magic, nothing else.
+            ELF_MAGIC.to_vec(),
+        ];
+
+        // A valid file header, but with phnum set high enough that the program headers read past
+        // the end of the (much smaller) VMO.
+        let header_size = mem::size_of::<Elf64FileHeader>();
+        let phnum_offset = header_size - 4 * mem::size_of::<u16>();
+        let mut huge_phnum = HEADER_DATA.to_vec();
+        huge_phnum[phnum_offset..phnum_offset + 2].copy_from_slice(&(u16::MAX - 1).to_ne_bytes());
+        malformed_inputs.push(huge_phnum);
+
+        // A valid file header with exactly one PT_LOAD program header whose vaddr + memsz
+        // overflows usize. Before this file's `checked_add` fix, validating this would panic.
+        let mut overflowing_load = HEADER_DATA.to_vec();
+        // Offset of the `phoff: usize` field within Elf64FileHeader: ident, elf_type, machine,
+        // version, then entry, all of which precede it.
+        let phoff_offset = mem::size_of::<ElfIdent>()
+            + mem::size_of::<u16>() * 2
+            + mem::size_of::<u32>()
+            + mem::size_of::<usize>();
+        overflowing_load[phnum_offset..phnum_offset + 2].copy_from_slice(&1u16.to_ne_bytes());
+        overflowing_load[phoff_offset..phoff_offset + 8]
+            .copy_from_slice(&(header_size as u64).to_ne_bytes());
+        let overflowing_phdr = Elf64ProgramHeader {
+            segment_type: SegmentType::Load as u32,
+            flags: 0,
+            offset: 0,
+            vaddr: 1,
+            paddr: 0,
+            filesz: 0,
+            memsz: u64::MAX,
+            align: 1,
+        };
+        overflowing_load.extend_from_slice(unsafe {
+            std::slice::from_raw_parts(
+                &overflowing_phdr as *const Elf64ProgramHeader as *const u8,
+                mem::size_of::<Elf64ProgramHeader>(),
+            )
+        });
+        malformed_inputs.push(overflowing_load);
+
+        for data in malformed_inputs {
+            let vmo = zx::Vmo::create(data.len().max(1) as u64)?;
+            if !data.is_empty() {
+                vmo.write(&data, 0)?;
+            }
+            // Only the lack of a panic is being tested here; both Ok and Err are acceptable.
+            let _ = Elf64Headers::from_vmo(&vmo);
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,18 @@
+// Copyright 2020 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use {crate::elf_parse::Elf64Headers, fuchsia_zircon as zx};
+
+/// Writes `data` into a VMO and runs [Elf64Headers::from_vmo] on it, discarding the result.
+/// Used as a libFuzzer entry point, so it must never panic on arbitrary input.
+pub fn fuzz_parse_elf(data: &[u8]) {
+    let vmo = match zx::Vmo::create(data.len() as u64) {
+        Ok(vmo) => vmo,
+        Err(_) => return,
+    };
+    if vmo.write(data, 0).is_err() {
+        return;
+    }
+    let _ = Elf64Headers::from_vmo(&vmo);
+}
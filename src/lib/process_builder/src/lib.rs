@@ -72,6 +72,19 @@ mod elf_parse;
 mod processargs;
 mod util;
 
+#[cfg(test)]
+mod test_util;
+
+#[cfg(fuzz)]
+mod fuzzer;
+#[cfg(fuzz)]
+use fuzz::fuzz;
+#[cfg(fuzz)]
+#[fuzz]
+fn fuzz_parse_elf(data: &[u8]) {
+    fuzzer::fuzz_parse_elf(data);
+}
+
 use {
     anyhow::{anyhow, Context},
     fidl::endpoints::{ClientEnd, Proxy},
@@ -82,6 +95,7 @@ use {
     futures::prelude::*,
     lazy_static::lazy_static,
     log::warn,
+    std::collections::{HashMap, HashSet},
     std::convert::TryFrom,
     std::default::Default,
     std::ffi::{CStr, CString},
@@ -104,17 +118,37 @@ pub struct NamespaceEntry {
 ///
 /// See top-level crate documentation for a usage example.
 pub struct ProcessBuilder {
+    /// The name given to the new process, as passed to [ProcessBuilder::new()]. Retained so
+    /// [ProcessBuilder::set_argv0_from_name()] can use it as argv[0] without the caller having to
+    /// pass it again.
+    name: CString,
     /// The ELF binary for the new process.
     executable: zx::Vmo,
     /// The fuchsia.ldsvc.Loader service to use for the new process, if dynamically linked.
     ldsvc: Option<fldsvc::LoaderProxy>,
     /// A non-default vDSO to use for the new process, if any.
     non_default_vdso: Option<zx::Vmo>,
+    /// Whether to map the vDSO into the new process at all. See
+    /// [ProcessBuilder::set_map_system_vdso()].
+    map_system_vdso: bool,
     /// The contents of the main processargs message to be sent to the new process.
     msg_contents: processargs::MessageContents,
     /// Handles that are common to both the linker and main processargs messages, wrapped in an
     /// inner struct for code organization and clarity around borrows.
     common: CommonMessageHandles,
+    /// Whether to expand `${VAR}` references to this builder's own environment variables in
+    /// arguments at [ProcessBuilder::build()] time. See
+    /// [ProcessBuilder::enable_arg_env_expansion()].
+    arg_env_expansion: bool,
+    /// Whether to ensure argv[0] is the process name if no arguments were added. See
+    /// [ProcessBuilder::set_argv0_from_name()].
+    argv0_from_name: bool,
+    /// Number of times to retry a transient failure of `ldsvc.load_object` when loading the
+    /// dynamic linker. See [ProcessBuilder::set_loader_retries()].
+    loader_retries: u32,
+    /// Whether to reject duplicate handles (by koid) at [ProcessBuilder::build()] time. See
+    /// [ProcessBuilder::set_detect_duplicate_handles()].
+    detect_duplicate_handles: bool,
 }
 
 struct CommonMessageHandles {
@@ -153,12 +187,83 @@ pub struct BuiltProcess {
     pub bootstrap: zx::Channel,
 
     /// The base address of the VDSO in the process's VMAR, to be passed to the process on start as
-    /// arg2 in zx_process_start / zx::Process::start.
+    /// arg2 in zx_process_start / zx::Process::start. 0 if [ProcessBuilder::set_map_system_vdso()]
+    /// was used to disable mapping a vDSO.
     pub vdso_base: usize,
 
     /// The base address where the ELF executable, or the dynamic linker if the ELF was dynamically
     /// linked, was loaded in the process's VMAR.
     pub elf_base: usize,
+
+    /// The name given to the initial thread's stack VMO, e.g. "stack: default 0x40000" for a
+    /// statically linked executable with the default stack size, "stack: explicit 0x40000" for
+    /// one whose PT_GNU_STACK header gives a size, or "stack: msg of 0x40000" for a dynamically
+    /// linked executable, whose stack is sized to hold the bootstrap message. Exposed for tests
+    /// and tooling that want to confirm the naming contract.
+    pub stack_vmo_name: String,
+
+    /// For dynamically linked binaries, a handle to the VMAR reserving the lower half of the
+    /// process's address space for sanitizers. `None` for statically linked binaries, which don't
+    /// reserve this region. This reservation is released when the process is started via
+    /// [BuiltProcess::start()]; until then it remains in place, which is useful for e.g. a
+    /// debugger inspecting the address space of a built-but-not-yet-started process.
+    pub reserved_vmar: Option<zx::Vmar>,
+}
+
+/// A reusable snapshot of a [ProcessBuilder]'s arguments, environment variables, and namespace
+/// paths, captured with [ProcessBuilder::template()]. Used to configure multiple similar
+/// processes without re-specifying this configuration each time.
+///
+/// Handles cannot be cloned, so this does not capture any handles (including namespace directory
+/// handles); those must be re-added to each instantiated [ProcessBuilder] by the caller.
+pub struct ProcessBuilderTemplate {
+    /// Arguments to be passed to instantiated processes.
+    pub args: Vec<CString>,
+
+    /// Environment variables to be passed to instantiated processes.
+    pub environment_vars: Vec<CString>,
+
+    /// Namespace paths from the captured builder. These are provided for reference only; the
+    /// caller must still provide a directory handle for each path via
+    /// [ProcessBuilder::add_namespace_entries()].
+    pub namespace_paths: Vec<CString>,
+}
+
+/// A snapshot of a [ProcessBuilder]'s currently staged bootstrap message contents, for debugging
+/// and tooling use. Captured with [ProcessBuilder::debug_dump_contents()].
+///
+/// Handles are summarized by their [HandleType] only; the handles themselves are not exposed, so
+/// that this can be logged or displayed without risking use-after-transfer of the real handles.
+#[derive(Debug)]
+pub struct MessageContentsSummary {
+    /// Arguments currently staged for the process, in order.
+    pub args: Vec<CString>,
+
+    /// Environment variables currently staged for the process, in order.
+    pub environment_vars: Vec<CString>,
+
+    /// Namespace paths currently staged for the process, in order.
+    pub namespace_paths: Vec<CString>,
+
+    /// The [HandleType] of each handle currently staged for the process, in order.
+    pub handle_types: Vec<HandleType>,
+}
+
+impl ProcessBuilderTemplate {
+    /// Creates a new [ProcessBuilder] for the given job and executable, pre-populated with this
+    /// template's arguments and environment variables. Namespace directories and other handles
+    /// must be re-added to the returned builder by the caller.
+    pub fn instantiate(
+        &self,
+        name: &CStr,
+        job: &zx::Job,
+        executable: zx::Vmo,
+    ) -> Result<ProcessBuilder, ProcessBuilderError> {
+        let mut pb = ProcessBuilder::new(name, job, executable)?;
+        pb.add_arguments(self.args.clone());
+        pb.add_environment_variables(self.environment_vars.clone());
+        Ok(pb)
+    }
 }
 
 impl ProcessBuilder {
@@ -173,6 +278,10 @@ impl ProcessBuilder {
     ///
     /// # Errors
     ///
+    /// Returns Err([ProcessBuilderError::ExecutableNotExecutable]) immediately if `executable`
+    /// does not have the [zx::Rights::EXECUTE] right, rather than deferring that failure until
+    /// ELF loading.
+    ///
     /// Returns Err([ProcessBuilderError::CreateProcess]) if process creation fails, such as if the
     /// process using this is disallowed direct process creation rights through job policy. See
     /// top-level crate documentation for more details.
@@ -187,6 +296,13 @@ impl ProcessBuilder {
         if executable.is_invalid_handle() {
             return Err(ProcessBuilderError::BadHandle("Invalid executable handle"));
         }
+        let info = executable
+            .as_handle_ref()
+            .basic_info()
+            .map_err(|s| ProcessBuilderError::GenericStatus("Failed to get VMO basic info", s))?;
+        if !info.rights.contains(zx::Rights::EXECUTE) {
+            return Err(ProcessBuilderError::ExecutableNotExecutable());
+        }
 
         // Creating the process immediately has the benefit that we fail fast if the calling
         // process does not have permission to create processes directly.
@@ -201,16 +317,38 @@ impl ProcessBuilder {
         // Add duplicates of the process, VMAR, and thread handles to the bootstrap message.
         let msg_contents = processargs::MessageContents::default();
         let mut pb = ProcessBuilder {
+            name: name.to_owned(),
             executable,
             ldsvc: None,
             non_default_vdso: None,
+            map_system_vdso: true,
             msg_contents,
             common: CommonMessageHandles { process, thread, root_vmar },
+            arg_env_expansion: false,
+            argv0_from_name: false,
+            loader_retries: 0,
+            detect_duplicate_handles: false,
         };
         pb.common.add_to_message(&mut pb.msg_contents)?;
         Ok(pb)
     }
 
+    /// Captures this builder's arguments, environment variables, and namespace paths into a
+    /// [ProcessBuilderTemplate] that can be used to configure further [ProcessBuilder]s without
+    /// re-specifying them.
+    ///
+    /// Handles (including namespace directory handles) cannot be cloned and are therefore not
+    /// captured; [ProcessBuilderTemplate::instantiate()] produces a builder with no handles added,
+    /// and the caller is responsible for re-adding them, e.g. via
+    /// [ProcessBuilder::add_namespace_entries()].
+    pub fn template(&self) -> ProcessBuilderTemplate {
+        ProcessBuilderTemplate {
+            args: self.msg_contents.args.clone(),
+            environment_vars: self.msg_contents.environment_vars.clone(),
+            namespace_paths: self.msg_contents.namespace_paths.clone(),
+        }
+    }
+
     /// Sets the fuchsia.ldsvc.Loader service for the process.
     ///
     /// The loader service is used to load dynamic libraries if the executable is a dynamically
@@ -249,6 +387,39 @@ impl ProcessBuilder {
         self.non_default_vdso = Some(vdso);
     }
 
+    /// Sets whether to map a vDSO into the new process at all. Defaults to true.
+    ///
+    /// Some specialized processes (e.g. ones that bundle their own vDSO, or that have no syscall
+    /// surface at all) need no vDSO mapped in by the builder. Passing false here skips loading and
+    /// mapping a vDSO -- whether the system default or one set via [ProcessBuilder::set_vdso_vmo()]
+    /// -- and no [HandleType::VdsoVmo] handle is added to the bootstrap message.
+    /// [BuiltProcess::vdso_base] will be 0 in this case.
+    ///
+    /// The resulting process must not rely on the vDSO (e.g. for `zx_*` syscall trampolines) being
+    /// present; doing so will fail unpredictably once it tries to call into it.
+    pub fn set_map_system_vdso(&mut self, enabled: bool) {
+        self.map_system_vdso = enabled;
+    }
+
+    /// Sets the default job handle for the process, i.e. the job a process uses to create
+    /// sub-processes and sub-jobs of its own, by adding it to the bootstrap message as a
+    /// [HandleType::DefaultJob] handle.
+    ///
+    /// This is a convenience wrapper around [ProcessBuilder::add_handles()] with
+    /// [HandleType::DefaultJob] that also verifies `job` is actually a job handle, since passing
+    /// the wrong object type here would otherwise only surface as a confusing failure inside the
+    /// new process once it tries to use its default job.
+    ///
+    /// # Errors
+    ///
+    /// Returns Err([ProcessBuilderError::BadHandle]) if `job` is invalid or is not a job handle.
+    pub fn set_default_job(&mut self, job: zx::Job) -> Result<(), ProcessBuilderError> {
+        self.add_handles(vec![StartupHandle {
+            handle: job.into(),
+            info: HandleInfo::new(HandleType::DefaultJob, 0),
+        }])
+    }
+
     /// Add arguments to the process's bootstrap message. Successive calls append (not replace)
     /// arguments.
     pub fn add_arguments(&mut self, mut args: Vec<CString>) {
@@ -261,6 +432,102 @@ impl ProcessBuilder {
         self.msg_contents.environment_vars.append(&mut vars);
     }
 
+    /// Add an additional, caller-defined string array to the process's bootstrap message, tagged
+    /// with `array_type`, beyond the args/environment_vars/namespace_paths arrays the processargs
+    /// protocol already carries. This generalizes those existing arrays for consumers (e.g. a
+    /// runtime-specific bootstrap reader) that agree out-of-band on the meaning of `array_type`.
+    ///
+    /// Successive calls append a new array, they don't merge with a previously added array of the
+    /// same `array_type`.
+    ///
+    /// # Errors
+    ///
+    /// Returns Err([ProcessBuilderError::InvalidArg]) if `array_type` is one of the
+    /// `processargs::RESERVED_ARRAY_TYPE_*` constants, which are reserved for the arrays this
+    /// message format already carries.
+    pub fn add_string_array(
+        &mut self,
+        array_type: u32,
+        strings: Vec<CString>,
+    ) -> Result<(), ProcessBuilderError> {
+        match array_type {
+            processargs::RESERVED_ARRAY_TYPE_ARGS
+            | processargs::RESERVED_ARRAY_TYPE_ENVIRON
+            | processargs::RESERVED_ARRAY_TYPE_NAMES => {
+                return Err(ProcessBuilderError::InvalidArg(format!(
+                    "array_type {} is reserved for the processargs message's built-in arrays",
+                    array_type
+                )));
+            }
+            _ => {}
+        }
+        self.msg_contents.extra_arrays.push((array_type, strings));
+        Ok(())
+    }
+
+    /// Enables expansion of `${VAR}` references in arguments to this builder's own environment
+    /// variables at [ProcessBuilder::build()] time, e.g. an argument `--x=${FOO}` with an
+    /// environment variable `FOO=bar` becomes `--x=bar`.
+    ///
+    /// `$$` is treated as an escaped literal `$`. A `${VAR}` reference to a variable that isn't
+    /// in this builder's environment variables is left as a literal, unexpanded, in the output
+    /// argument.
+    pub fn enable_arg_env_expansion(&mut self) {
+        self.arg_env_expansion = true;
+    }
+
+    /// Ensures argv[0] is the process name (the `CStr` passed to [ProcessBuilder::new()]) at
+    /// [ProcessBuilder::build()] time, if no arguments have been added by then.
+    ///
+    /// Callers frequently pass the executable path as both the process name and argv[0]; this
+    /// spares them from having to duplicate it into [ProcessBuilder::add_arguments()] themselves.
+    /// Existing arguments (from any prior [ProcessBuilder::add_arguments()] call) are left
+    /// untouched -- this only has an effect when no arguments have been added at all.
+    pub fn set_argv0_from_name(&mut self) {
+        self.argv0_from_name = true;
+    }
+
+    /// Sets the number of times to retry `ldsvc.load_object` when loading the dynamic linker, if
+    /// it fails with a transient, `ZX_ERR_SHOULD_WAIT`-style status. Retries use a short backoff
+    /// and are bounded by the overall load timeout, so a value here doesn't risk hanging forever.
+    /// Non-transient errors are not retried. Defaults to 0 (no retries), matching prior behavior.
+    pub fn set_loader_retries(&mut self, retries: u32) {
+        self.loader_retries = retries;
+    }
+
+    /// Sets whether [ProcessBuilder::build()] should reject handles added through
+    /// [ProcessBuilder::add_handles()] that share a koid with one added by an earlier call,
+    /// i.e. the same kernel object added to the process twice. Defaults to false, since
+    /// legitimate duplicate koids can occur (e.g. intentionally handing the new process two
+    /// handles to the same VMO under different handle types) and this library shouldn't reject
+    /// them unless the caller specifically wants the check.
+    pub fn set_detect_duplicate_handles(&mut self, enabled: bool) {
+        self.detect_duplicate_handles = enabled;
+    }
+
+    /// Applies a scheduling profile to the process's initial thread. Unlike most of this
+    /// builder's setters, this takes effect immediately rather than being deferred to
+    /// [ProcessBuilder::build()], since the initial thread already exists by the time a
+    /// `ProcessBuilder` can be constructed at all.
+    ///
+    /// `profile` must be obtained from the `fuchsia.scheduler.ProfileProvider` service; this
+    /// library has no way to create one itself.
+    ///
+    /// # Errors
+    ///
+    /// Returns Err([ProcessBuilderError::BadHandle]) if `profile` is invalid.
+    pub fn set_initial_thread_profile(
+        &mut self,
+        profile: zx::Profile,
+    ) -> Result<(), ProcessBuilderError> {
+        if profile.is_invalid_handle() {
+            return Err(ProcessBuilderError::BadHandle("Invalid profile handle"));
+        }
+        self.common.thread.set_profile(profile, 0).map_err(|s| {
+            ProcessBuilderError::GenericStatus("Failed to set initial thread profile", s)
+        })
+    }
+
     /// Add handles to the process's bootstrap message. Successive calls append (not replace)
     /// handles.
     ///
@@ -270,12 +537,18 @@ impl ProcessBuilder {
     /// A [HandleType::LdsvcLoader] handle will automatically be passed along to
     /// [ProcessBuilder::set_loader_service()] if provided through this function.
     ///
+    /// A [HandleType::DefaultJob] handle is checked to actually be a job handle; prefer
+    /// [ProcessBuilder::set_default_job()], which does the same thing with a more precise type.
+    ///
     /// # Errors
     ///
     /// [HandleType::NamespaceDirectory] handles should not be added through this function since
     /// they must be accompanied with a path. Use [ProcessBuilder::add_namespace_entries()] for
     /// that instead.
     ///
+    /// Returns Err([ProcessBuilderError::BadHandle]) if a [HandleType::DefaultJob] handle is
+    /// provided but is not actually a job handle.
+    ///
     /// The following handle types cannot be added through this, as they are added automatically by
     /// the ProcessBuilder:
     /// * [HandleType::ProcessSelf]
@@ -302,6 +575,19 @@ impl ProcessBuilder {
                             .into(),
                     ));
                 }
+                HandleType::DefaultJob => {
+                    let info = h.handle.as_handle_ref().basic_info().map_err(|s| {
+                        ProcessBuilderError::GenericStatus(
+                            "Failed to get DefaultJob handle basic info",
+                            s,
+                        )
+                    })?;
+                    if info.object_type != zx::ObjectType::JOB {
+                        return Err(ProcessBuilderError::BadHandle(
+                            "DefaultJob handle is not a job handle",
+                        ));
+                    }
+                }
                 HandleType::ProcessSelf
                 | HandleType::ThreadSelf
                 | HandleType::RootVmar
@@ -342,12 +628,23 @@ impl ProcessBuilder {
     /// Each [NamespaceEntry] contains a client connection to a fuchsia.io.Directory FIDL service
     /// and a path to add that directory to the process's namespace as.
     ///
+    /// Entries may not overlap with each other or with entries added by previous calls, where
+    /// "overlap" means one path is equal to, or a prefix (at a '/' path-component boundary) of,
+    /// another -- e.g. adding both "/pkg" and "/pkg/data" is rejected, since together they'd
+    /// create nested/overlapping mounts that confuse fdio in the new process. "/" is a
+    /// path-component-boundary prefix of every other absolute path, so adding "/" alongside any
+    /// other entry is always rejected as an overlap. Non-overlapping sibling paths like "/pkg" and
+    /// "/data" are unaffected.
+    ///
     /// # Errors
     ///
     /// Returns Err([ProcessBuilderError::InvalidArg]) if the maximum number of namespace entries
     /// (2^16) was reached and the entry could not be added. This is exceedingly unlikely, and most
     /// likely if you are anywhere near this limit [ProcessBuilder::build()] will fail because the
     /// process's processargs startup messsage is over its own length limit.
+    ///
+    /// Returns Err([ProcessBuilderError::InvalidArg]) if any entry's path overlaps with another
+    /// entry's path, as described above.
     pub fn add_namespace_entries(
         &mut self,
         mut entries: Vec<NamespaceEntry>,
@@ -376,6 +673,27 @@ impl ProcessBuilder {
             }
         }
 
+        // Reject overlapping namespace paths, i.e. any two paths where one is a prefix of the
+        // other at a path-component boundary. This subsumes exact duplicates, which are just the
+        // degenerate case of one path being a zero-length-suffix "prefix" of the other -- there
+        // was previously no duplicate-path check here at all, so this also newly catches plain
+        // duplicates, not just nested ones. Mounting both, e.g. "/pkg" and "/pkg/data", would
+        // create nested/overlapping mounts that confuse fdio in the new process. "/" is a
+        // path-component-boundary prefix of every other absolute path, so adding "/" alongside
+        // any other entry is always rejected as an overlap.
+        let existing_paths = self.msg_contents.namespace_paths.iter().map(CString::as_c_str);
+        let new_paths = entries.iter().map(|entry| entry.path.as_c_str());
+        for (i, path) in new_paths.clone().enumerate() {
+            for other in existing_paths.clone().chain(new_paths.clone().take(i)) {
+                if paths_overlap(path, other) {
+                    return Err(ProcessBuilderError::InvalidArg(format!(
+                        "Namespace entry {:?} overlaps with existing entry {:?}",
+                        path, other
+                    )));
+                }
+            }
+        }
+
         // Intentionally separate from validation so that we don't partially add namespace entries=
         for entry in entries.drain(..) {
             self.msg_contents.namespace_paths.push(entry.path);
@@ -388,6 +706,107 @@ impl ProcessBuilder {
         Ok(())
     }
 
+    /// Remove namespace directory entries previously added with
+    /// [ProcessBuilder::add_namespace_entries()] whose path matches one of `paths`. Paths that
+    /// were never added are silently ignored.
+    ///
+    /// Returns the removed entries, in no particular order, so that callers that want to replace
+    /// rather than simply drop them can reuse their directory handles.
+    pub fn remove_namespace_entries(&mut self, paths: &[CString]) -> Vec<NamespaceEntry> {
+        let to_remove: HashSet<&CStr> = paths.iter().map(AsRef::as_ref).collect();
+
+        // Compact namespace_paths, dropping the removed paths and recording how each surviving
+        // entry's index shifts so the namespace handles below can be remapped to match.
+        let old_paths = mem::replace(&mut self.msg_contents.namespace_paths, vec![]);
+        let mut removed_paths: HashMap<u16, CString> = HashMap::new();
+        let mut old_idx_to_new_idx: HashMap<u16, u16> = HashMap::new();
+        for (old_idx, path) in old_paths.into_iter().enumerate() {
+            let old_idx = old_idx as u16;
+            if to_remove.contains(path.as_c_str()) {
+                removed_paths.insert(old_idx, path);
+            } else {
+                let new_idx = self.msg_contents.namespace_paths.len() as u16;
+                old_idx_to_new_idx.insert(old_idx, new_idx);
+                self.msg_contents.namespace_paths.push(path);
+            }
+        }
+
+        // Split the handles between the namespace directory handles being removed, those being
+        // remapped to their new index, and everything else, which is left untouched.
+        let old_handles = mem::replace(&mut self.msg_contents.handles, vec![]);
+        let mut removed_entries = vec![];
+        for h in old_handles {
+            if h.info.handle_type() != HandleType::NamespaceDirectory {
+                self.msg_contents.handles.push(h);
+                continue;
+            }
+            let old_idx = h.info.arg();
+            if let Some(path) = removed_paths.remove(&old_idx) {
+                removed_entries
+                    .push(NamespaceEntry { path, directory: ClientEnd::from(h.handle) });
+            } else {
+                let new_idx = old_idx_to_new_idx[&old_idx];
+                let info = HandleInfo::new(HandleType::NamespaceDirectory, new_idx);
+                self.msg_contents.handles.push(StartupHandle { info, ..h });
+            }
+        }
+        removed_entries
+    }
+
+    /// Replace the namespace directory entry at `path`, previously added with
+    /// [ProcessBuilder::add_namespace_entries()], with `directory`. Unlike calling
+    /// [ProcessBuilder::remove_namespace_entries()] followed by
+    /// [ProcessBuilder::add_namespace_entries()], the new directory handle takes over the
+    /// existing entry's namespace table index rather than being appended at a new one.
+    ///
+    /// # Errors
+    ///
+    /// Returns Err([ProcessBuilderError::InvalidArg]) if no namespace entry matching `path` was
+    /// previously added.
+    ///
+    /// Returns Err([ProcessBuilderError::BadHandle]) if `directory` is an invalid handle.
+    pub fn replace_namespace_entry(
+        &mut self,
+        path: &CStr,
+        directory: ClientEnd<fio::DirectoryMarker>,
+    ) -> Result<(), ProcessBuilderError> {
+        if directory.is_invalid_handle() {
+            return Err(ProcessBuilderError::BadHandle("Invalid handle in namespace entry"));
+        }
+        let idx = self
+            .msg_contents
+            .namespace_paths
+            .iter()
+            .position(|p| p.as_c_str() == path)
+            .ok_or_else(|| {
+                ProcessBuilderError::InvalidArg(format!("Namespace entry {:?} not found", path))
+            })? as u16;
+        let handle = self
+            .msg_contents
+            .handles
+            .iter_mut()
+            .find(|h| h.info.handle_type() == HandleType::NamespaceDirectory && h.info.arg() == idx)
+            .expect("namespace path and handle tables must stay in sync");
+        handle.handle = zx::Handle::from(directory);
+        Ok(())
+    }
+
+    /// Returns a snapshot of this builder's currently staged bootstrap message contents, for
+    /// debugging and tooling use, e.g. logging what will be passed to a process before
+    /// [ProcessBuilder::build()] consumes `self`. Unlike [ProcessBuilder::build()], this does not
+    /// consume the builder and can be called repeatedly as the builder is configured.
+    ///
+    /// Handles are summarized by their [HandleType] only, not exposed directly, since this is
+    /// meant for inspection rather than handle extraction.
+    pub fn debug_dump_contents(&self) -> MessageContentsSummary {
+        MessageContentsSummary {
+            args: self.msg_contents.args.clone(),
+            environment_vars: self.msg_contents.environment_vars.clone(),
+            namespace_paths: self.msg_contents.namespace_paths.clone(),
+            handle_types: self.msg_contents.handles.iter().map(|h| h.info.handle_type()).collect(),
+        }
+    }
+
     /// Build the new process using the data and handles provided to the ProcessBuilder.
     ///
     /// The return value of this function is a [BuiltProcess] struct which contains the new process
@@ -406,6 +825,36 @@ impl ProcessBuilder {
     ///
     /// [zx_process_start]: https://fuchsia.dev/fuchsia-src/reference/syscalls/process_start.md
     pub async fn build(mut self) -> Result<BuiltProcess, ProcessBuilderError> {
+        // If enabled, reject handles added through add_handles() that duplicate the koid of one
+        // added earlier, before doing any other (harder to unwind) build work.
+        if self.detect_duplicate_handles {
+            let mut seen_koids = std::collections::HashSet::new();
+            for h in &self.msg_contents.handles {
+                let info = h.handle.as_handle_ref().basic_info().map_err(|s| {
+                    ProcessBuilderError::GenericStatus("Failed to get handle basic info", s)
+                })?;
+                if !seen_koids.insert(info.koid) {
+                    return Err(ProcessBuilderError::InvalidArg(format!(
+                        "Handle with koid {:?} was added more than once",
+                        info.koid
+                    )));
+                }
+            }
+        }
+
+        // Ensure argv[0] is the process name, if enabled and no arguments were added.
+        if self.argv0_from_name && self.msg_contents.args.is_empty() {
+            self.msg_contents.args.push(self.name.clone());
+        }
+
+        // Expand `${VAR}` references in arguments before any serialization, if enabled.
+        if self.arg_env_expansion {
+            self.msg_contents.args = expand_arg_env_vars(
+                &self.msg_contents.args,
+                &self.msg_contents.environment_vars,
+            )?;
+        }
+
         // Parse the executable as an ELF64 file, reading in the headers we need. Done first since
         // this is most likely to be invalid and error out.
         let elf_headers = elf_parse::Elf64Headers::from_vmo(&self.executable)?;
@@ -441,7 +890,9 @@ impl ProcessBuilder {
                 Some(ReservationVmar::reserve_low_address_space(&self.common.root_vmar)?);
 
             // Get the dynamic linker and map it into the process's address space.
-            let ld_vmo = get_dynamic_linker(&ldsvc, &self.executable, interp_hdr).await?;
+            let ld_vmo =
+                get_dynamic_linker(&ldsvc, &self.executable, interp_hdr, self.loader_retries)
+                    .await?;
             let ld_headers = elf_parse::Elf64Headers::from_vmo(&ld_vmo)?;
             loaded_elf = elf_load::load_elf(&ld_vmo, &self.common.root_vmar, &ld_headers)?;
 
@@ -464,8 +915,10 @@ impl ProcessBuilder {
         }
 
         // Load the vDSO - either the default system vDSO, or the user-provided one - into the
-        // process's address space and a handle to it to the bootstrap message.
-        let vdso_base = self.load_vdso()?;
+        // process's address space and a handle to it to the bootstrap message. Skipped entirely
+        // if disabled via set_map_system_vdso(false), in which case the process gets no vDSO
+        // handle and no mapping.
+        let vdso_base = if self.map_system_vdso { self.load_vdso()? } else { 0 };
 
         // Calculate initial stack size.
         let stack_size;
@@ -495,19 +948,17 @@ impl ProcessBuilder {
         }
 
         // Allocate the initial thread's stack, map it, and add a handle to the bootstrap message.
-        let stack_vmo_name =
-            CString::new(stack_vmo_name).expect("Stack VMO name must not contain interior nul's");
-        let stack_ptr = self.create_stack(stack_size, &stack_vmo_name)?;
+        let stack_vmo_cname = CString::new(stack_vmo_name.clone())
+            .expect("Stack VMO name must not contain interior nul's");
+        let stack_ptr = self.create_stack(stack_size, &stack_vmo_cname)?;
 
         // Build and send the primary bootstrap message.
         let msg = processargs::Message::build(self.msg_contents)?;
         msg.write(&bootstrap_wr).map_err(ProcessBuilderError::WriteBootstrapMessage)?;
 
-        // Explicitly destroy the reservation VMAR before returning so that we can be sure it is
-        // gone (so we don't end up with a process with half its address space gone).
-        if let Some(mut r) = reserve_vmar {
-            r.destroy().map_err(ProcessBuilderError::DestroyReservationVMAR)?;
-        }
+        // Hand the reservation off to the returned BuiltProcess rather than destroying it here, so
+        // the lower address range stays reserved until the process is actually started.
+        let reserved_vmar = reserve_vmar.and_then(ReservationVmar::into_vmar);
 
         Ok(BuiltProcess {
             process: self.common.process,
@@ -515,9 +966,11 @@ impl ProcessBuilder {
             thread: self.common.thread,
             entry: loaded_elf.entry,
             stack: stack_ptr,
+            reserved_vmar,
             bootstrap: bootstrap_rd,
             vdso_base: vdso_base,
             elf_base: loaded_elf.vmar_base,
+            stack_vmo_name,
         })
     }
 
@@ -630,6 +1083,25 @@ impl ProcessBuilder {
     }
 }
 
+/// Returns true if `a` and `b` overlap as namespace paths, i.e. one is equal to the other, or one
+/// is a prefix of the other ending exactly at a '/' path-component boundary. "/" is treated as a
+/// prefix of every other absolute path, since every absolute path starts with "/".
+///
+/// This is symmetric: `paths_overlap(a, b) == paths_overlap(b, a)`.
+fn paths_overlap(a: &CStr, b: &CStr) -> bool {
+    fn is_component_prefix(prefix: &[u8], path: &[u8]) -> bool {
+        if prefix == path {
+            return true;
+        }
+        if prefix == b"/" {
+            return path.starts_with(b"/");
+        }
+        path.starts_with(prefix) && path.get(prefix.len()) == Some(&b'/')
+    }
+    let (a, b) = (a.to_bytes(), b.to_bytes());
+    is_component_prefix(a, b) || is_component_prefix(b, a)
+}
+
 /// Calculate the size of the initial stack to allocate for the dynamic linker, based on the given
 /// processargs message contents.
 ///
@@ -701,6 +1173,68 @@ fn extract_ld_environment_variables(envvars: &[CString]) -> Vec<CString> {
     extracted
 }
 
+/// Expands `${VAR}` references in `args` to the corresponding values in `env_vars` (each
+/// formatted as `VAR=value`). `$$` is an escaped literal `$`. A `${VAR}` whose `VAR` doesn't
+/// appear in `env_vars` is left unexpanded. Arguments that aren't valid UTF-8 are left unchanged,
+/// since there's nothing to expand in an opaque byte string.
+fn expand_arg_env_vars(
+    args: &[CString],
+    env_vars: &[CString],
+) -> Result<Vec<CString>, ProcessBuilderError> {
+    let env_map: HashMap<&str, &str> = env_vars
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .filter_map(|v| v.split_once('='))
+        .collect();
+
+    args.iter()
+        .map(|arg| match arg.to_str() {
+            Ok(s) => {
+                let expanded = expand_str_env_vars(s, &env_map);
+                CString::new(expanded).map_err(|_| {
+                    ProcessBuilderError::InvalidArg(
+                        "Argument env expansion produced an embedded NUL byte".to_string(),
+                    )
+                })
+            }
+            Err(_) => Ok(arg.clone()),
+        })
+        .collect()
+}
+
+/// Expands `${VAR}` references in `s` to values from `env_map`, treating `$$` as an escaped
+/// literal `$` and leaving unknown `${VAR}` references as literal text.
+fn expand_str_env_vars(s: &str, env_map: &HashMap<&str, &str>) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            out.push(c);
+            continue;
+        }
+        match chars.peek() {
+            Some('$') => {
+                chars.next();
+                out.push('$');
+            }
+            Some('{') => {
+                chars.next();
+                let name: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                match env_map.get(name.as_str()) {
+                    Some(value) => out.push_str(value),
+                    None => {
+                        out.push_str("${");
+                        out.push_str(&name);
+                        out.push('}');
+                    }
+                }
+            }
+            _ => out.push('$'),
+        }
+    }
+    out
+}
+
 impl CommonMessageHandles {
     /// Returns a vector of processargs message handles created by this library which are common to
     /// both the linker and main messages, duplicating handles as needed.
@@ -775,6 +1309,7 @@ async fn get_dynamic_linker<'a>(
     ldsvc: &'a fldsvc::LoaderProxy,
     executable: &'a zx::Vmo,
     interp_hdr: &'a elf_parse::Elf64ProgramHeader,
+    retries: u32,
 ) -> Result<zx::Vmo, ProcessBuilderError> {
     // Read the dynamic linker name from the main VMO, based on the PT_INTERP header.
     let mut interp = vec![0u8; interp_hdr.filesz as usize];
@@ -790,14 +1325,29 @@ async fn get_dynamic_linker<'a>(
         .context("Invalid UTF8")
         .map_err(ProcessBuilderError::InvalidInterpHeader)?;
 
-    // Retrieve the dynamic linker as a VMO from fuchsia.ldsvc.Loader
+    // Retrieve the dynamic linker as a VMO from fuchsia.ldsvc.Loader. The whole retry loop below
+    // is bounded by this same overall timeout, so `retries` can't cause us to hang indefinitely.
     const LDSO_LOAD_TIMEOUT_SEC: i64 = 30;
-    let load_fut = ldsvc
-        .load_object(interp_str)
-        .map_err(ProcessBuilderError::LoadDynamicLinker)
-        .on_timeout(fasync::Time::after(LDSO_LOAD_TIMEOUT_SEC.seconds()), || {
-            Err(ProcessBuilderError::LoadDynamicLinkerTimeout())
-        });
+    const LDSO_LOAD_RETRY_BACKOFF_MSEC: i64 = 100;
+    let load_fut = async {
+        let mut retries_left = retries;
+        loop {
+            let (status, vmo) = ldsvc
+                .load_object(interp_str)
+                .await
+                .map_err(ProcessBuilderError::LoadDynamicLinker)?;
+            if zx::Status::from_raw(status) == zx::Status::SHOULD_WAIT && retries_left > 0 {
+                retries_left -= 1;
+                fasync::Timer::new(fasync::Time::after(LDSO_LOAD_RETRY_BACKOFF_MSEC.millis()))
+                    .await;
+                continue;
+            }
+            break Ok((status, vmo));
+        }
+    }
+    .on_timeout(fasync::Time::after(LDSO_LOAD_TIMEOUT_SEC.seconds()), || {
+        Err(ProcessBuilderError::LoadDynamicLinkerTimeout())
+    });
     let (status, ld_vmo) = load_fut.await?;
     zx::Status::ok(status).map_err(|s| {
         ProcessBuilderError::GenericStatus(
@@ -828,10 +1378,54 @@ impl BuiltProcess {
                 self.vdso_base,
             )
             .map_err(ProcessBuilderError::ProcessStart)?;
+
+        // Now that the process has actually started, release the sanitizer reservation so the
+        // process can use its full address space.
+        if let Some(reserved_vmar) = self.reserved_vmar {
+            // This is safe because there are no mappings in the region and it is not a region in
+            // the current process.
+            unsafe { reserved_vmar.destroy() }
+                .map_err(ProcessBuilderError::DestroyReservationVMAR)?;
+        }
+
         Ok(self.process)
     }
 }
 
+/// A pool that shares a single `fuchsia.ldsvc.Loader` connection across many [ProcessBuilder]s.
+///
+/// Each process spawned with a dynamically linked executable needs its own loader connection, but
+/// asking the system loader service to clone itself once per process is wasteful when many
+/// processes are being spawned at once. A `LoaderPool` holds one connection and, via
+/// [LoaderPool::clone_loader], asks the loader's own `Clone` method to hand out a fresh
+/// `ClientEnd` for each process, so every clone shares this pool's loader as their common
+/// ancestor instead of going back to the original source.
+pub struct LoaderPool {
+    ldsvc: fldsvc::LoaderProxy,
+}
+
+impl LoaderPool {
+    /// Creates a pool backed by `ldsvc`.
+    pub fn new(ldsvc: fldsvc::LoaderProxy) -> Self {
+        LoaderPool { ldsvc }
+    }
+
+    /// Asks the pooled loader to clone itself, returning a new `ClientEnd` ready to hand to
+    /// [ProcessBuilder::set_loader_service].
+    pub async fn clone_loader(&self) -> Result<ClientEnd<fldsvc::LoaderMarker>, ProcessBuilderError> {
+        let (client, server) = fidl::endpoints::create_endpoints::<fldsvc::LoaderMarker>()
+            .map_err(|e| {
+                ProcessBuilderError::Internal("Failed to create loader service channel", e.into())
+            })?;
+        let status = self.ldsvc.clone(server).await.map_err(|e| {
+            ProcessBuilderError::Internal("Failed to clone loader service", e.into())
+        })?;
+        zx::Status::ok(status)
+            .map_err(|s| ProcessBuilderError::GenericStatus("Failed to clone loader service", s))?;
+        Ok(client)
+    }
+}
+
 struct ReservationVmar(Option<zx::Vmar>);
 
 impl ReservationVmar {
@@ -873,6 +1467,13 @@ impl ReservationVmar {
             None => Ok(()),
         }
     }
+
+    /// Take the wrapped VMAR handle out of this reservation without destroying it, so the caller
+    /// takes over responsibility for eventually destroying it. Leaves this reservation empty, so
+    /// its Drop impl becomes a no-op.
+    fn into_vmar(mut self) -> Option<zx::Vmar> {
+        self.0.take()
+    }
 }
 
 // This is probably unnecessary, but it feels wrong to rely on the side effect of the process's
@@ -893,6 +1494,8 @@ pub enum ProcessBuilderError {
     InvalidArg(String),
     #[error("{}", _0)]
     BadHandle(&'static str),
+    #[error("Executable VMO does not have the EXECUTE right")]
+    ExecutableNotExecutable(),
     #[error("Failed to create process: {}", _0)]
     CreateProcess(zx::Status),
     #[error("Failed to create thread: {}", _0)]
@@ -929,6 +1532,7 @@ impl ProcessBuilderError {
         match self {
             ProcessBuilderError::InvalidArg(_)
             | ProcessBuilderError::InvalidInterpHeader(_)
+            | ProcessBuilderError::ExecutableNotExecutable()
             | ProcessBuilderError::LoaderMissing() => zx::Status::INVALID_ARGS,
             ProcessBuilderError::BadHandle(_) => zx::Status::BAD_HANDLE,
             ProcessBuilderError::CreateProcess(s)
@@ -952,13 +1556,16 @@ mod tests {
     use {
         super::*,
         anyhow::Error,
-        fidl::endpoints::{Proxy, ServerEnd, ServiceMarker},
+        fidl::endpoints::{create_proxy_and_stream, Proxy, ServerEnd, ServiceMarker},
         fidl_fuchsia_io as fio,
+        fidl_fuchsia_scheduler::ProfileProviderMarker,
         fidl_test_processbuilder::{UtilMarker, UtilProxy},
         fuchsia_async as fasync,
+        fuchsia_component::client::connect_to_service,
         fuchsia_vfs_pseudo_fs::{
             directory::entry::DirectoryEntry, file::simple::read_only, pseudo_directory,
         },
+        futures::TryStreamExt,
         std::iter,
         std::mem,
         zerocopy::LayoutVerified,
@@ -1030,19 +1637,22 @@ mod tests {
         Ok(())
     }
 
-    async fn check_process_exited_ok(process: &zx::Process) -> Result<(), Error> {
+    // Waits for `process` to terminate and returns its return code, asserting that it otherwise
+    // exited cleanly (started, with no debugger attached). Generalizes the fixed "exited with
+    // code 0" check in `check_process_exited_ok` for tests that need to assert a specific
+    // non-zero exit code instead.
+    async fn wait_for_exit(process: &zx::Process) -> Result<i64, Error> {
         fasync::OnSignals::new(process, zx::Signals::PROCESS_TERMINATED).await?;
 
         let info = process.info()?;
-        assert_eq!(
-            info,
-            zx::ProcessInfo {
-                return_code: 0,
-                started: true,
-                exited: true,
-                debugger_attached: false
-            }
-        );
+        assert!(info.started);
+        assert!(info.exited);
+        assert!(!info.debugger_attached);
+        Ok(info.return_code)
+    }
+
+    async fn check_process_exited_ok(process: &zx::Process) -> Result<(), Error> {
+        assert_eq!(wait_for_exit(process).await?, 0);
         Ok(())
     }
 
@@ -1074,6 +1684,88 @@ mod tests {
         Ok(())
     }
 
+    #[fasync::run_singlethreaded(test)]
+    async fn start_util_with_argv0_from_name() -> Result<(), Error> {
+        const TEST_UTIL_BIN: &'static str = "/pkg/bin/process_builder_test_util";
+
+        let (mut builder, proxy) = setup_test_util_builder(true)?;
+        builder.set_argv0_from_name();
+        let process = builder.build().await?.start()?;
+        check_process_running(&process)?;
+
+        let proc_args = proxy.get_arguments().await.context("failed to get args from util")?;
+        assert_eq!(proc_args, vec![TEST_UTIL_BIN]);
+
+        mem::drop(proxy);
+        check_process_exited_ok(&process).await?;
+        Ok(())
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn start_util_with_arg_env_expansion() -> Result<(), Error> {
+        let (mut builder, proxy) = setup_test_util_builder(true)?;
+        builder.add_environment_variables(vec![CString::new("FOO=bar")?]);
+        builder.add_arguments(vec![CString::new("--x=${FOO}")?]);
+        builder.enable_arg_env_expansion();
+        let process = builder.build().await?.start()?;
+        check_process_running(&process)?;
+
+        let proc_args = proxy.get_arguments().await.context("failed to get args from util")?;
+        assert_eq!(proc_args, vec!["--x=bar"]);
+
+        mem::drop(proxy);
+        check_process_exited_ok(&process).await?;
+        Ok(())
+    }
+
+    // Verify that a ProcessBuilderTemplate can be used to instantiate multiple builders that
+    // carry the same arguments, without re-specifying them each time.
+    #[fasync::run_singlethreaded(test)]
+    async fn template_instantiates_builders_with_same_args() -> Result<(), Error> {
+        const TEST_UTIL_BIN: &'static str = "/pkg/bin/process_builder_test_util";
+        let test_args = vec!["arg0", "arg1"];
+        let test_args_cstr: Vec<CString> =
+            test_args.iter().map(|s| CString::new(s.clone())).collect::<Result<_, _>>()?;
+
+        let mut template_builder = create_test_util_builder()?;
+        template_builder.add_arguments(test_args_cstr);
+        let template = template_builder.template();
+
+        let job = fuchsia_runtime::job_default();
+        let procname = CString::new(TEST_UTIL_BIN.to_owned())?;
+
+        let mut processes_and_proxies = Vec::new();
+        for _ in 0..2 {
+            let file =
+                fdio::open_fd(TEST_UTIL_BIN, fio::OPEN_RIGHT_READABLE | fio::OPEN_RIGHT_EXECUTABLE)?;
+            let vmo = fdio::get_vmo_exec_from_file(&file)?;
+
+            let mut builder = template.instantiate(&procname, &job, vmo)?;
+            builder.set_loader_service(clone_loader_service()?)?;
+            let (dir_client, dir_server) = zx::Channel::create()?;
+            builder.add_handles(vec![StartupHandle {
+                handle: dir_server.into_handle(),
+                info: HandleInfo::new(HandleType::DirectoryRequest, 0),
+            }])?;
+            let proxy = connect_util(&dir_client)?;
+            let process = builder.build().await?.start()?;
+            check_process_running(&process)?;
+            processes_and_proxies.push((process, proxy));
+        }
+
+        for (process, proxy) in &processes_and_proxies {
+            let proc_args = proxy.get_arguments().await.context("failed to get args from util")?;
+            assert_eq!(proc_args, test_args);
+            check_process_running(process)?;
+        }
+
+        for (process, proxy) in processes_and_proxies {
+            mem::drop(proxy);
+            check_process_exited_ok(&process).await?;
+        }
+        Ok(())
+    }
+
     #[fasync::run_singlethreaded(test)]
     async fn start_util_with_huge_args() -> Result<(), Error> {
         // This test is partially designed to probe the stack usage of
@@ -1185,6 +1877,74 @@ mod tests {
         Ok(())
     }
 
+    // Verify that a dynamic binary can be started with a hermetic MockLoaderService in place of
+    // the real loader, seeded with just the one dynamic linker VMO the test util binary's
+    // PT_INTERP names (fetched from the real loader, since this test has no other way to get a
+    // real dynamic linker binary to seed the mock with).
+    #[fasync::run_singlethreaded(test)]
+    async fn set_loader_via_mock_loader_service() -> Result<(), Error> {
+        let executable_file = fdio::open_fd(
+            "/pkg/bin/process_builder_test_util",
+            fio::OPEN_RIGHT_READABLE | fio::OPEN_RIGHT_EXECUTABLE,
+        )?;
+        let executable = fdio::get_vmo_exec_from_file(&executable_file)?;
+        let elf_headers = elf_parse::Elf64Headers::from_vmo(&executable)?;
+        let interp_hdr = elf_headers
+            .program_header_with_type(elf_parse::SegmentType::Interp)?
+            .context("Test util binary has no PT_INTERP header")?;
+        let mut interp_name = vec![0u8; interp_hdr.filesz as usize];
+        executable.read(&mut interp_name, interp_hdr.offset as u64)?;
+        assert_eq!(interp_name.pop(), Some(0));
+        let interp_name = String::from_utf8(interp_name)?;
+
+        let real_ldsvc = clone_loader_service()?.into_proxy()?;
+        let (status, interp_vmo) = real_ldsvc.load_object(&interp_name).await?;
+        zx::Status::ok(status)?;
+        let interp_vmo = interp_vmo.context("Real loader has no dynamic linker to seed mock with")?;
+
+        let mock_loader = test_util::MockLoaderService::new();
+        mock_loader.add_object(interp_name, interp_vmo);
+
+        let (mut builder, proxy) = setup_test_util_builder(false)?;
+        builder.set_loader_service(mock_loader.spawn_loader())?;
+        let process = builder.build().await?.start()?;
+        check_process_running(&process)?;
+
+        // Confirm the process actually started and is usable, not just that build() succeeded.
+        proxy.get_arguments().await.context("failed to get args from util")?;
+
+        mem::drop(proxy);
+        check_process_exited_ok(&process).await?;
+        Ok(())
+    }
+
+    // Verify that a LoaderPool can hand out clones of a single loader connection to spawn
+    // multiple processes, all of which run successfully.
+    #[fasync::run_singlethreaded(test)]
+    async fn loader_pool_spawns_multiple_processes() -> Result<(), Error> {
+        let pool = LoaderPool::new(clone_loader_service()?.into_proxy()?);
+
+        let mut processes_and_proxies = Vec::new();
+        for _ in 0..3 {
+            let (mut builder, proxy) = setup_test_util_builder(false)?;
+            builder.set_loader_service(pool.clone_loader().await?)?;
+            let process = builder.build().await?.start()?;
+            check_process_running(&process)?;
+            processes_and_proxies.push((process, proxy));
+        }
+
+        for (process, proxy) in &processes_and_proxies {
+            proxy.get_arguments().await.context("failed to get args from util")?;
+            check_process_running(process)?;
+        }
+
+        for (process, proxy) in processes_and_proxies {
+            mem::drop(proxy);
+            check_process_exited_ok(&process).await?;
+        }
+        Ok(())
+    }
+
     // Verify that a vDSO handle is properly handled if passed directly to set_vdso_vmo instead of
     // relying on the default value.
     // Note: There isn't a great way to tell here whether the vDSO VMO we passed in was used
@@ -1382,6 +2142,81 @@ mod tests {
         Ok(())
     }
 
+    #[fasync::run_singlethreaded(test)]
+    async fn start_util_with_replaced_namespace_entries() -> Result<(), Error> {
+        let mut randbuf = [0; 8];
+        zx::cprng_draw(&mut randbuf)?;
+        let test_content1 = format!("test content 1 {}", u64::from_le_bytes(randbuf));
+        zx::cprng_draw(&mut randbuf)?;
+        let test_content2 = format!("test content 2 {}", u64::from_le_bytes(randbuf));
+
+        let test_content1_bytes = test_content1.clone().into_bytes();
+        let (dir1_server, dir1_client) = zx::Channel::create()?;
+        fasync::Task::spawn(async move {
+            let mut dir1 = pseudo_directory! {
+                "test_file1" => read_only(|| Ok(test_content1_bytes.clone())),
+            };
+            dir1.open(
+                fio::OPEN_RIGHT_READABLE,
+                fio::MODE_TYPE_DIRECTORY,
+                &mut iter::empty(),
+                ServerEnd::new(dir1_server),
+            );
+            dir1.await;
+            panic!("Psuedo dir stopped serving!");
+        })
+        .detach();
+
+        let test_content2_bytes = test_content2.clone().into_bytes();
+        let (dir2_server, dir2_client) = zx::Channel::create()?;
+        fasync::Task::spawn(async move {
+            let mut dir2 = pseudo_directory! {
+                "test_file2" => read_only(|| Ok(test_content2_bytes.clone())),
+            };
+            dir2.open(
+                fio::OPEN_RIGHT_READABLE,
+                fio::MODE_TYPE_DIRECTORY,
+                &mut iter::empty(),
+                ServerEnd::new(dir2_server),
+            );
+            dir2.await;
+            panic!("Psuedo dir stopped serving!");
+        })
+        .detach();
+
+        let (dir1_stale_server, dir1_stale_client) = zx::Channel::create()?;
+        mem::drop(dir1_stale_server);
+
+        let (mut builder, proxy) = setup_test_util_builder(true)?;
+        builder.add_namespace_entries(vec![
+            NamespaceEntry {
+                path: CString::new("/dir1")?,
+                directory: ClientEnd::new(dir1_stale_client),
+            },
+            NamespaceEntry { path: CString::new("/dir2")?, directory: ClientEnd::new(dir2_client) },
+        ])?;
+
+        // Replace the stale "/dir1" entry with the real one, leaving "/dir2" untouched.
+        builder.replace_namespace_entry(&CString::new("/dir1")?, ClientEnd::new(dir1_client))?;
+
+        let process = builder.build().await?.start()?;
+        check_process_running(&process)?;
+
+        let namespace_dump = proxy.dump_namespace().await.context("failed to dump namespace")?;
+        assert_eq!(namespace_dump, "/dir1, /dir1/test_file1, /dir2, /dir2/test_file2");
+
+        let dir1_contents =
+            proxy.read_file("/dir1/test_file1").await.context("failed to read file via util")?;
+        assert_eq!(dir1_contents, test_content1);
+        let dir2_contents =
+            proxy.read_file("/dir2/test_file2").await.context("failed to read file via util")?;
+        assert_eq!(dir2_contents, test_content2);
+
+        mem::drop(proxy);
+        check_process_exited_ok(&process).await?;
+        Ok(())
+    }
+
     // Trying to start a dynamically linked process without providing a loader service should
     // fail. This verifies that nothing is automatically cloning a loader.
     #[fasync::run_singlethreaded(test)]
@@ -1401,12 +2236,14 @@ mod tests {
         Ok(())
     }
 
-    // Checks that, for dynamically linked binaries, the lower half of the address space has been
-    // reserved for sanitizers.
+    // Checks that, for dynamically linked binaries, the lower half of the address space remains
+    // reserved for sanitizers on the returned BuiltProcess, rather than being released at the end
+    // of build() (it's released only once the process actually starts, see BuiltProcess::start()).
     #[fasync::run_singlethreaded(test)]
     async fn verify_low_address_range_reserved() -> Result<(), Error> {
         let (builder, _) = setup_test_util_builder(true)?;
         let built = builder.build().await?;
+        assert!(built.reserved_vmar.is_some());
 
         // This ends up being the same thing ReservationVmar does, but it's not reused here so that
         // this catches bugs or bad changes to ReservationVmar itself.
@@ -1415,7 +2252,41 @@ mod tests {
         built
             .root_vmar
             .allocate(0, lower_half_len, zx::VmarFlags::SPECIFIC)
-            .context("Unable to allocate lower address range of new process")?;
+            .expect_err("Lower address range should still be reserved before the process starts");
+        Ok(())
+    }
+
+    // Pins the human-readable naming contract for the initial thread's stack VMO, which tooling
+    // (e.g. a debugger listing VMOs) may rely on to identify the stack at a glance.
+    fn assert_stack_vmo_name_has_hex_size(name: &str, prefix: &str) {
+        let size_str = name
+            .strip_prefix(prefix)
+            .unwrap_or_else(|| panic!("Stack VMO name {:?} missing prefix {:?}", name, prefix));
+        assert!(
+            size_str.starts_with("0x") && usize::from_str_radix(&size_str[2..], 16).is_ok(),
+            "Stack VMO name {:?} does not end in a hex size",
+            name
+        );
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn stack_vmo_name_format_dynamic() -> Result<(), Error> {
+        let (builder, _) = setup_test_util_builder(true)?;
+        let built = builder.build().await?;
+        assert_stack_vmo_name_has_hex_size(&built.stack_vmo_name, "stack: msg of ");
+        Ok(())
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn stack_vmo_name_format_static() -> Result<(), Error> {
+        const TEST_BIN: &'static str = "/pkg/bin/static_pie_test_util";
+        let file = fdio::open_fd(TEST_BIN, fio::OPEN_RIGHT_READABLE | fio::OPEN_RIGHT_EXECUTABLE)?;
+        let vmo = fdio::get_vmo_exec_from_file(&file)?;
+        let job = fuchsia_runtime::job_default();
+        let procname = CString::new(TEST_BIN.to_owned())?;
+        let builder = ProcessBuilder::new(&procname, &job, vmo)?;
+        let built = builder.build().await?;
+        assert_stack_vmo_name_has_hex_size(&built.stack_vmo_name, "stack: default ");
         Ok(())
     }
 
@@ -1476,6 +2347,28 @@ mod tests {
         Ok(())
     }
 
+    // Verify that disabling the vDSO mapping via set_map_system_vdso(false) results in no
+    // VdsoVmo handle in the bootstrap message and a vdso_base of 0.
+    #[fasync::run_singlethreaded(test)]
+    async fn set_map_system_vdso_false_omits_vdso() -> Result<(), Error> {
+        let mut builder = create_test_util_builder()?;
+        builder.set_loader_service(clone_loader_service()?)?;
+        builder.set_map_system_vdso(false);
+        let built = builder.build().await?;
+        assert_eq!(built.vdso_base, 0);
+
+        let mut linker_msg_buf = zx::MessageBuf::new();
+        built.bootstrap.read(&mut linker_msg_buf)?;
+        let mut main_msg_buf = zx::MessageBuf::new();
+        built.bootstrap.read(&mut main_msg_buf)?;
+        let handle_info = parse_handle_info_from_message(&main_msg_buf)?;
+        assert_eq!(
+            handle_info.iter().filter(|info| info.handle_type() == HandleType::VdsoVmo).count(),
+            0
+        );
+        Ok(())
+    }
+
     // Verify that [ProcessBuilder::add_handles()] rejects handle types that are added
     // automatically by the builder.
     #[fasync::run_singlethreaded(test)]
@@ -1519,6 +2412,81 @@ mod tests {
         Ok(())
     }
 
+    // Verify that [ProcessBuilder::add_string_array()] rejects the reserved array_type values.
+    #[test]
+    fn add_string_array_rejects_reserved_types() -> Result<(), Error> {
+        let mut builder = create_test_util_builder()?;
+
+        for reserved in [
+            processargs::RESERVED_ARRAY_TYPE_ARGS,
+            processargs::RESERVED_ARRAY_TYPE_ENVIRON,
+            processargs::RESERVED_ARRAY_TYPE_NAMES,
+        ] {
+            match builder.add_string_array(reserved, vec![CString::new("x")?]) {
+                Err(ProcessBuilderError::InvalidArg(_)) => {}
+                Err(err) => {
+                    panic!("Unexpected error type, should be invalid arg: {}", err);
+                }
+                Ok(_) => {
+                    panic!("add_string_array unexpectedly succeeded for reserved type {}", reserved);
+                }
+            }
+        }
+
+        builder.add_string_array(100, vec![CString::new("custom")?])?;
+        assert_eq!(builder.msg_contents.extra_arrays.len(), 1);
+        Ok(())
+    }
+
+    // Verify that get_dynamic_linker() retries a transient ZX_ERR_SHOULD_WAIT from
+    // fuchsia.ldsvc.Loader/LoadObject when given a retry budget, and that it eventually succeeds
+    // once the mock loader starts returning the VMO.
+    #[fasync::run_singlethreaded(test)]
+    async fn get_dynamic_linker_retries_should_wait() -> Result<(), Error> {
+        const INTERP_NAME: &[u8] = b"test_ld.so\0";
+        let executable = zx::Vmo::create(INTERP_NAME.len() as u64)?;
+        executable.write(INTERP_NAME, 0)?;
+        let interp_hdr = elf_parse::Elf64ProgramHeader {
+            segment_type: 0,
+            flags: 0,
+            offset: 0,
+            vaddr: 0,
+            paddr: 0,
+            filesz: INTERP_NAME.len() as u64,
+            memsz: 0,
+            align: 0,
+        };
+
+        let (ldsvc, mut stream) = create_proxy_and_stream::<fldsvc::LoaderMarker>()?;
+        fasync::Task::spawn(async move {
+            let mut should_wait_sent = false;
+            while let Some(req) = stream.try_next().await.expect("Failed to read request") {
+                match req {
+                    fldsvc::LoaderRequest::LoadObject { object_name, responder } => {
+                        assert_eq!(object_name, "test_ld.so");
+                        if !should_wait_sent {
+                            should_wait_sent = true;
+                            responder
+                                .send(zx::Status::SHOULD_WAIT.into_raw(), None)
+                                .expect("Failed to send response");
+                        } else {
+                            let vmo = zx::Vmo::create(0).expect("Failed to create VMO");
+                            responder
+                                .send(zx::Status::OK.into_raw(), Some(vmo))
+                                .expect("Failed to send response");
+                        }
+                    }
+                    other => panic!("Unexpected request: {:?}", other),
+                }
+            }
+        })
+        .detach();
+
+        let ld_vmo = get_dynamic_linker(&ldsvc, &executable, &interp_hdr, 1).await?;
+        assert_eq!(ld_vmo.get_size()?, 0);
+        Ok(())
+    }
+
     // Verify that invalid handles are correctly rejected.
     #[fasync::run_singlethreaded(test)]
     async fn rejects_invalid_handles() -> Result<(), Error> {
@@ -1560,6 +2528,207 @@ mod tests {
         Ok(())
     }
 
+    // Verify that a readable-but-not-executable VMO is rejected immediately, rather than
+    // deferring the failure until ELF loading.
+    #[fasync::run_singlethreaded(test)]
+    async fn rejects_non_executable_vmo() -> Result<(), Error> {
+        let vmo = zx::Vmo::create(1)?;
+        let job = fuchsia_runtime::job_default();
+        let procname = CString::new("dummy_name")?;
+
+        match ProcessBuilder::new(&procname, &job, vmo) {
+            Err(ProcessBuilderError::ExecutableNotExecutable()) => {}
+            Err(err) => {
+                panic!("Unexpected error type, should be ExecutableNotExecutable: {}", err);
+            }
+            Ok(_) => {
+                panic!("ProcessBuilder::new unexpectedly accepted a non-executable VMO");
+            }
+        }
+        Ok(())
+    }
+
+    // Verify that exceeding the processargs message's handle-count limit surfaces a clear
+    // ProcessBuilderError::Processargs(ProcessargsError::TooManyHandles) at build time, rather
+    // than failing cryptically when the bootstrap message is written to the channel.
+    #[fasync::run_singlethreaded(test)]
+    async fn rejects_too_many_handles() -> Result<(), Error> {
+        const TOO_MANY: usize = zx::sys::ZX_CHANNEL_MAX_MSG_HANDLES as usize + 1;
+
+        let (mut builder, _) = setup_test_util_builder(true)?;
+        let handles: Vec<StartupHandle> = iter::repeat_with(|| StartupHandle {
+            handle: zx::Vmo::create(1).expect("Failed to create VMO").into_handle(),
+            info: HandleInfo::new(HandleType::User0, 0),
+        })
+        .take(TOO_MANY)
+        .collect();
+        builder.add_handles(handles)?;
+
+        let result = builder.build().await;
+        match result {
+            Err(ProcessBuilderError::Processargs(ProcessargsError::TooManyHandles(_))) => {}
+            Err(err) => {
+                panic!("Unexpected error type, should be Processargs::TooManyHandles: {}", err);
+            }
+            Ok(_) => {
+                panic!("build unexpectedly succeeded with too many handles");
+            }
+        }
+        Ok(())
+    }
+
+    // Verify that set_detect_duplicate_handles(true) catches the same VMO (by koid) being added
+    // twice across separate add_handles calls, and that detection is off by default.
+    #[fasync::run_singlethreaded(test)]
+    async fn detect_duplicate_handles() -> Result<(), Error> {
+        let (mut builder, _) = setup_test_util_builder(true)?;
+        let vmo = zx::Vmo::create(1)?;
+        let dup = vmo.duplicate_handle(zx::Rights::SAME_RIGHTS)?;
+        builder.add_handles(vec![StartupHandle {
+            handle: vmo.into_handle(),
+            info: HandleInfo::new(HandleType::User0, 0),
+        }])?;
+        builder.add_handles(vec![StartupHandle {
+            handle: dup.into_handle(),
+            info: HandleInfo::new(HandleType::User0, 1),
+        }])?;
+
+        assert!(!builder.detect_duplicate_handles);
+        builder.set_detect_duplicate_handles(true);
+        match builder.build().await {
+            Err(ProcessBuilderError::InvalidArg(_)) => {}
+            other => panic!("Expected ProcessBuilderError::InvalidArg, got {:?}", other),
+        }
+        Ok(())
+    }
+
+    // Verify that debug_dump_contents() reflects args, environment variables, namespace paths,
+    // and handle types staged on the builder so far, without consuming it or exposing the handles
+    // themselves.
+    #[test]
+    fn debug_dump_contents() -> Result<(), Error> {
+        let (mut builder, _proxy) = setup_test_util_builder(true)?;
+
+        builder.add_arguments(vec![CString::new("arg0").unwrap()]);
+        builder.add_environment_variables(vec![CString::new("VAR=1").unwrap()]);
+        builder.add_handles(vec![StartupHandle {
+            handle: zx::Vmo::create(1)?.into_handle(),
+            info: HandleInfo::new(HandleType::User0, 0),
+        }])?;
+
+        let summary = builder.debug_dump_contents();
+        assert_eq!(summary.args, vec![CString::new("arg0").unwrap()]);
+        assert_eq!(summary.environment_vars, vec![CString::new("VAR=1").unwrap()]);
+        assert!(summary.namespace_paths.is_empty());
+        assert!(summary.handle_types.contains(&HandleType::User0));
+        // The loader service is held apart from the staged handles, so it isn't listed.
+        assert!(!summary.handle_types.contains(&HandleType::LdsvcLoader));
+        assert!(summary.handle_types.contains(&HandleType::DirectoryRequest));
+        Ok(())
+    }
+
+    // Verify that wait_for_exit() returns the process's actual return code, rather than just
+    // asserting it's 0 like check_process_exited_ok() does.
+    #[fasync::run_singlethreaded(test)]
+    async fn wait_for_exit_returns_return_code() -> Result<(), Error> {
+        let (builder, proxy) = setup_test_util_builder(true)?;
+        let process = builder.build().await?.start()?;
+        check_process_running(&process)?;
+
+        mem::drop(proxy);
+        assert_eq!(wait_for_exit(&process).await?, 0);
+        Ok(())
+    }
+
+    // Verify that a non-job handle passed as the default job, whether through set_default_job or
+    // directly through add_handles, is rejected rather than silently passed along to the new
+    // process.
+    #[test]
+    fn rejects_non_job_default_job() -> Result<(), Error> {
+        let (mut builder, _) = setup_test_util_builder(true)?;
+
+        let bad_job = zx::Vmo::create(1)?;
+        match builder.set_default_job(zx::Job::from(bad_job.into_handle())) {
+            Err(ProcessBuilderError::BadHandle(_)) => {}
+            other => panic!("Expected ProcessBuilderError::BadHandle, got {:?}", other),
+        }
+
+        let bad_job = zx::Vmo::create(1)?;
+        match builder.add_handles(vec![StartupHandle {
+            handle: bad_job.into_handle(),
+            info: HandleInfo::new(HandleType::DefaultJob, 0),
+        }]) {
+            Err(ProcessBuilderError::BadHandle(_)) => {}
+            other => panic!("Expected ProcessBuilderError::BadHandle, got {:?}", other),
+        }
+        Ok(())
+    }
+
+    // Verify that namespace entries whose paths overlap at a path-component boundary, e.g. "/pkg"
+    // and "/pkg/data", are rejected, while non-overlapping sibling paths like "/pkg" and "/data"
+    // are accepted.
+    #[test]
+    fn rejects_overlapping_namespace_paths() -> Result<(), Error> {
+        let (mut builder, _) = setup_test_util_builder(true)?;
+
+        let (_pkg_server, pkg_client) = zx::Channel::create()?;
+        let (_data_server, data_client) = zx::Channel::create()?;
+        match builder.add_namespace_entries(vec![
+            NamespaceEntry { path: CString::new("/pkg")?, directory: ClientEnd::new(pkg_client) },
+            NamespaceEntry {
+                path: CString::new("/pkg/data")?,
+                directory: ClientEnd::new(data_client),
+            },
+        ]) {
+            Err(ProcessBuilderError::InvalidArg(_)) => {}
+            other => panic!("Expected ProcessBuilderError::InvalidArg, got {:?}", other),
+        }
+
+        let (mut builder, _) = setup_test_util_builder(true)?;
+        let (_pkg_server, pkg_client) = zx::Channel::create()?;
+        let (_data_server, data_client) = zx::Channel::create()?;
+        builder.add_namespace_entries(vec![
+            NamespaceEntry { path: CString::new("/pkg")?, directory: ClientEnd::new(pkg_client) },
+            NamespaceEntry { path: CString::new("/data")?, directory: ClientEnd::new(data_client) },
+        ])?;
+        Ok(())
+    }
+
+    // Verify that replace_namespace_entry() substitutes the new handle at the same namespace
+    // table index the replaced entry occupied, rather than appending it at a new one, and that it
+    // rejects paths that were never added.
+    #[test]
+    fn replace_namespace_entry_preserves_index() -> Result<(), Error> {
+        let (mut builder, _) = setup_test_util_builder(true)?;
+
+        let (_pkg_server, pkg_client) = zx::Channel::create()?;
+        let (_data_server, data_client) = zx::Channel::create()?;
+        builder.add_namespace_entries(vec![
+            NamespaceEntry { path: CString::new("/pkg")?, directory: ClientEnd::new(pkg_client) },
+            NamespaceEntry { path: CString::new("/data")?, directory: ClientEnd::new(data_client) },
+        ])?;
+        // "/data" was the second entry added, so it occupies namespace table index 1.
+        let data_idx = 1u16;
+
+        let (_new_data_server, new_data_client) = zx::Channel::create()?;
+        builder
+            .replace_namespace_entry(CString::new("/data")?.as_c_str(), ClientEnd::new(new_data_client))?;
+
+        assert_eq!(builder.msg_contents.namespace_paths.len(), 2);
+        assert!(builder.msg_contents.handles.iter().any(|h| h.info.handle_type()
+            == HandleType::NamespaceDirectory
+            && h.info.arg() == data_idx));
+
+        let (_missing_server, missing_client) = zx::Channel::create()?;
+        match builder
+            .replace_namespace_entry(CString::new("/missing")?.as_c_str(), ClientEnd::new(missing_client))
+        {
+            Err(ProcessBuilderError::InvalidArg(_)) => {}
+            other => panic!("Expected ProcessBuilderError::InvalidArg, got {:?}", other),
+        }
+        Ok(())
+    }
+
     #[fasync::run_singlethreaded]
     #[test]
     async fn start_static_pie_binary() -> Result<(), Error> {
@@ -1600,4 +2769,39 @@ mod tests {
 
         Ok(())
     }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn set_initial_thread_profile_rejects_invalid_handle() -> Result<(), Error> {
+        let mut builder = create_test_util_builder()?;
+        let result = builder.set_initial_thread_profile(zx::Profile::from(zx::Handle::invalid()));
+        match result {
+            Err(ProcessBuilderError::BadHandle(_)) => {}
+            other => panic!("Expected ProcessBuilderError::BadHandle, got {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn set_initial_thread_profile_build_still_succeeds() -> Result<(), Error> {
+        // fuchsia.scheduler.ProfileProvider isn't necessarily reachable from this test's sandbox;
+        // if it isn't, just skip the rest of this test rather than failing outright.
+        let provider = match connect_to_service::<ProfileProviderMarker>() {
+            Ok(provider) => provider,
+            Err(_) => return Ok(()),
+        };
+        let (status, profile) = match provider.get_profile(0, "set_initial_thread_profile_test").await
+        {
+            Ok(result) => result,
+            Err(_) => return Ok(()),
+        };
+        let profile = match zx::Status::ok(status).and(profile.ok_or(zx::Status::INTERNAL)) {
+            Ok(profile) => zx::Profile::from(profile),
+            Err(_) => return Ok(()),
+        };
+
+        let (mut builder, _) = setup_test_util_builder(true)?;
+        builder.set_initial_thread_profile(profile)?;
+        builder.build().await?;
+        Ok(())
+    }
 }
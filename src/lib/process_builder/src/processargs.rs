@@ -11,22 +11,29 @@
 use {
     fuchsia_runtime::HandleInfo,
     fuchsia_zircon as zx,
-    std::convert::TryFrom,
+    std::convert::{TryFrom, TryInto},
     std::ffi::CString,
     std::fmt,
     std::mem,
     std::num,
     thiserror::Error,
-    zerocopy::{AsBytes, FromBytes},
+    zerocopy::{AsBytes, FromBytes, LayoutVerified},
 };
 
-/// Possible errors that can occur during processargs startup message construction
+/// Possible errors that can occur during processargs startup message construction or parsing.
 #[allow(missing_docs)] // No docs on individual error variants.
 #[derive(Error, Debug)]
 pub enum ProcessargsError {
     TryFromInt(num::TryFromIntError),
     SizeTooLarge(usize),
     TooManyHandles(usize),
+    /// The message is too short to contain a well-formed [MessageHeader] at all.
+    MalformedHeader { offset: usize },
+    /// A table the header points to (handle info, args, environ, or names) runs past the end of
+    /// the message. `offset` is where the read that failed started.
+    TruncatedMessage { offset: usize },
+    /// A handle info entry at `offset` doesn't decode to a valid [HandleInfo].
+    InvalidHandleInfo { offset: usize, value: u32 },
 }
 
 impl ProcessargsError {
@@ -36,6 +43,9 @@ impl ProcessargsError {
             ProcessargsError::TryFromInt(_)
             | ProcessargsError::SizeTooLarge(_)
             | ProcessargsError::TooManyHandles(_) => zx::Status::INVALID_ARGS,
+            ProcessargsError::MalformedHeader { .. }
+            | ProcessargsError::TruncatedMessage { .. }
+            | ProcessargsError::InvalidHandleInfo { .. } => zx::Status::INVALID_ARGS,
         }
     }
 }
@@ -59,6 +69,17 @@ impl fmt::Display for ProcessargsError {
                 v,
                 zx::sys::ZX_CHANNEL_MAX_MSG_HANDLES
             ),
+            ProcessargsError::MalformedHeader { offset } => {
+                write!(f, "Malformed processargs message header at offset {}", offset)
+            }
+            ProcessargsError::TruncatedMessage { offset } => {
+                write!(f, "Processargs message is truncated at offset {}", offset)
+            }
+            ProcessargsError::InvalidHandleInfo { offset, value } => write!(
+                f,
+                "Invalid handle info value {:#x} at offset {} in processargs message",
+                value, offset
+            ),
         }
     }
 }
@@ -66,6 +87,16 @@ impl fmt::Display for ProcessargsError {
 const ZX_PROCARGS_PROTOCOL: u32 = 0x4150585d;
 const ZX_PROCARGS_VERSION: u32 = 0x00001000;
 
+/// `array_type` values reserved for the arrays the processargs message format already carries
+/// (args, environment variables, and namespace paths). [ProcessBuilder::add_string_array()]
+/// rejects these to avoid colliding with the arrays that are already addressable without the
+/// extra array table.
+///
+/// [ProcessBuilder::add_string_array()]: crate::ProcessBuilder::add_string_array()
+pub const RESERVED_ARRAY_TYPE_ARGS: u32 = 0;
+pub const RESERVED_ARRAY_TYPE_ENVIRON: u32 = 1;
+pub const RESERVED_ARRAY_TYPE_NAMES: u32 = 2;
+
 /// Header for bootstrap message following the processargs protocol.
 #[derive(FromBytes, AsBytes, Default)]
 #[repr(C)]
@@ -98,6 +129,33 @@ pub(crate) struct MessageHeader {
     // u16 handle info argument is an index into this name table.
     names_off: u32,
     names_num: u32,
+
+    // Offset from start of message to the extra string array table, and the number of entries in
+    // it. This table, and the arrays it describes, are a process_builder-local addition on top of
+    // the canonical processargs protocol: each entry is an [ExtraArrayEntry] identifying a
+    // caller-defined, [ProcessBuilder::add_string_array]-supplied array of null-terminated UTF-8
+    // strings packed after the table. Consumers that only understand the canonical protocol can
+    // ignore this region entirely, since it's placed after everything args/environ_off/names_off
+    // cover.
+    //
+    // [ProcessBuilder::add_string_array]: crate::ProcessBuilder::add_string_array()
+    extra_off: u32,
+    extra_num: u32,
+}
+
+/// An entry in the extra string array table. See [MessageHeader::extra_off].
+#[derive(FromBytes, AsBytes, Default, Clone, Copy)]
+#[repr(C)]
+struct ExtraArrayEntry {
+    /// The caller-defined tag identifying this array, as passed to
+    /// [ProcessBuilder::add_string_array()].
+    ///
+    /// [ProcessBuilder::add_string_array()]: crate::ProcessBuilder::add_string_array()
+    array_type: u32,
+    /// Offset from the start of the message to this array's null-terminated UTF-8 strings.
+    off: u32,
+    /// Number of strings in this array.
+    num: u32,
 }
 
 /// A container for a single startup handle, containing a handle and metadata. Used as an input to
@@ -118,6 +176,12 @@ pub struct MessageContents {
     pub environment_vars: Vec<CString>,
     pub namespace_paths: Vec<CString>,
     pub handles: Vec<StartupHandle>,
+
+    /// Additional, caller-defined string arrays beyond args/environment_vars/namespace_paths, each
+    /// tagged with a caller-defined `array_type`. See [ProcessBuilder::add_string_array()].
+    ///
+    /// [ProcessBuilder::add_string_array()]: crate::ProcessBuilder::add_string_array()
+    pub extra_arrays: Vec<(u32, Vec<CString>)>,
 }
 
 /// A bootstrap message following the processargs protocol.
@@ -171,6 +235,29 @@ impl Message {
             data.extend_from_slice(path.as_bytes_with_nul());
         }
 
+        assert!(data.len() == header.extra_off as usize);
+        let mut strings_off = header.extra_off as usize
+            + mem::size_of::<ExtraArrayEntry>() * contents.extra_arrays.len();
+        let mut entries = Vec::with_capacity(contents.extra_arrays.len());
+        for (array_type, strings) in &contents.extra_arrays {
+            entries.push(ExtraArrayEntry {
+                array_type: *array_type,
+                off: u32::try_from(strings_off).map_err(ProcessargsError::TryFromInt)?,
+                num: u32::try_from(strings.len()).map_err(ProcessargsError::TryFromInt)?,
+            });
+            for s in strings {
+                strings_off += s.as_bytes_with_nul().len();
+            }
+        }
+        for entry in &entries {
+            data.extend_from_slice(entry.as_bytes());
+        }
+        for (_, strings) in &contents.extra_arrays {
+            for s in strings {
+                data.extend_from_slice(s.as_bytes_with_nul());
+            }
+        }
+
         // Sanity check final message size.
         assert!(data.len() == size);
         Ok(Message { bytes: data, handles })
@@ -221,6 +308,15 @@ impl Message {
             for path in &config.namespace_paths {
                 size += path.as_bytes_with_nul().len();
             }
+
+            header.extra_off = u32::try_from(size)?;
+            header.extra_num = u32::try_from(config.extra_arrays.len())?;
+            size += mem::size_of::<ExtraArrayEntry>() * config.extra_arrays.len();
+            for (_, strings) in &config.extra_arrays {
+                for s in strings {
+                    size += s.as_bytes_with_nul().len();
+                }
+            }
             Ok(())
         };
         f().map_err(|e| ProcessargsError::TryFromInt(e))?;
@@ -236,6 +332,77 @@ impl Message {
         let mut handles = self.handles;
         channel.write(self.bytes.as_slice(), &mut handles)
     }
+
+    /// Parses a raw processargs message `bytes`, as produced by [Message::build] and read back
+    /// off a channel, along with the `handles` read alongside it, into its [MessageContents].
+    ///
+    /// Nothing in this crate reads its own messages back -- a new process's C runtime parses the
+    /// processargs message it's bootstrapped with -- but this is useful for tests and tooling
+    /// that want to inspect a message. Every [ProcessargsError] this can return carries the byte
+    /// offset into `bytes` where the problem was found, to make debugging a corrupt bootstrap
+    /// message tractable.
+    ///
+    /// The extra string array table is not read: the returned `extra_arrays` is always empty.
+    pub fn parse(
+        bytes: &[u8],
+        handles: Vec<zx::Handle>,
+    ) -> Result<MessageContents, ProcessargsError> {
+        let header = LayoutVerified::<&[u8], MessageHeader>::new_from_prefix(bytes)
+            .ok_or(ProcessargsError::MalformedHeader { offset: 0 })?
+            .0;
+
+        let handle_info_off = header.handle_info_off as usize;
+        let handle_info_len = handles.len() * mem::size_of::<HandleInfoRaw>();
+        let handle_info_bytes = bytes
+            .get(handle_info_off..handle_info_off + handle_info_len)
+            .ok_or(ProcessargsError::TruncatedMessage { offset: handle_info_off })?;
+        let mut startup_handles = Vec::with_capacity(handles.len());
+        for (i, (raw_bytes, handle)) in
+            handle_info_bytes.chunks_exact(mem::size_of::<HandleInfoRaw>()).zip(handles).enumerate()
+        {
+            let offset = handle_info_off + i * mem::size_of::<HandleInfoRaw>();
+            let raw = HandleInfoRaw::from_ne_bytes(raw_bytes.try_into().unwrap());
+            let info = HandleInfo::try_from(raw)
+                .map_err(|_| ProcessargsError::InvalidHandleInfo { offset, value: raw })?;
+            startup_handles.push(StartupHandle { handle, info });
+        }
+
+        let (args, _) = Self::read_cstrings(bytes, header.args_off as usize, header.args_num)?;
+        let (environment_vars, _) =
+            Self::read_cstrings(bytes, header.environ_off as usize, header.environ_num)?;
+        let (namespace_paths, _) =
+            Self::read_cstrings(bytes, header.names_off as usize, header.names_num)?;
+
+        Ok(MessageContents {
+            args,
+            environment_vars,
+            namespace_paths,
+            handles: startup_handles,
+            extra_arrays: vec![],
+        })
+    }
+
+    /// Reads `count` consecutive null-terminated UTF-8 strings starting at `offset` in `bytes`.
+    /// Returns the strings and the offset just past the last one.
+    fn read_cstrings(
+        bytes: &[u8],
+        mut offset: usize,
+        count: u32,
+    ) -> Result<(Vec<CString>, usize), ProcessargsError> {
+        let mut strings = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let rest = bytes
+                .get(offset..)
+                .ok_or(ProcessargsError::TruncatedMessage { offset })?;
+            let nul_pos = rest
+                .iter()
+                .position(|&b| b == 0)
+                .ok_or(ProcessargsError::TruncatedMessage { offset })?;
+            strings.push(CString::new(&rest[..nul_pos]).expect("slice up to a nul has no nuls"));
+            offset += nul_pos + 1;
+        }
+        Ok((strings, offset))
+    }
 }
 
 #[cfg(test)]
@@ -246,6 +413,7 @@ mod tests {
         fuchsia_runtime::HandleType,
         fuchsia_zircon::{AsHandleRef, HandleBased},
         std::iter,
+        zerocopy::LayoutVerified,
     };
 
     #[test]
@@ -294,13 +462,15 @@ mod tests {
         let mut correct = Vec::new();
         correct.extend_from_slice(b"\x5d\x58\x50\x41"); // protocol
         correct.extend_from_slice(b"\x00\x10\x00\x00"); // version
-        correct.extend_from_slice(b"\x24\x00\x00\x00"); // handle_info_off
-        correct.extend_from_slice(b"\x30\x00\x00\x00"); // args_off
+        correct.extend_from_slice(b"\x2C\x00\x00\x00"); // handle_info_off
+        correct.extend_from_slice(b"\x38\x00\x00\x00"); // args_off
         correct.extend_from_slice(b"\x03\x00\x00\x00"); // args_num
-        correct.extend_from_slice(b"\x3F\x00\x00\x00"); // environ_off
+        correct.extend_from_slice(b"\x47\x00\x00\x00"); // environ_off
         correct.extend_from_slice(b"\x01\x00\x00\x00"); // environ_num
-        correct.extend_from_slice(b"\x47\x00\x00\x00"); // names_off
+        correct.extend_from_slice(b"\x4F\x00\x00\x00"); // names_off
         correct.extend_from_slice(b"\x02\x00\x00\x00"); // names_num
+        correct.extend_from_slice(b"\x5A\x00\x00\x00"); // extra_off
+        correct.extend_from_slice(b"\x00\x00\x00\x00"); // extra_num
         correct.extend_from_slice(b"\xF1\x00\x34\x12"); // handle info
         correct.extend_from_slice(b"\x20\x00\x00\x00"); //
         correct.extend_from_slice(b"\x20\x00\x01\x00"); //
@@ -321,6 +491,40 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn build_and_parse_extra_string_array() -> Result<(), Error> {
+        let config = MessageContents {
+            args: vec![CString::new("arg1")?],
+            extra_arrays: vec![(100, vec![CString::new("foo")?, CString::new("bar")?])],
+            ..Default::default()
+        };
+
+        let message = Message::build(config)?;
+        let (chan_wr, chan_rd) = zx::Channel::create()?;
+        message.write(&chan_wr)?;
+        let mut read_buf = zx::MessageBuf::new();
+        chan_rd.read(&mut read_buf)?;
+        let read_bytes = read_buf.bytes();
+
+        let header = LayoutVerified::<&[u8], MessageHeader>::new_from_prefix(read_bytes)
+            .expect("Failed to parse processargs header")
+            .0;
+        assert_eq!(header.extra_num, 1);
+
+        let entry_off = header.extra_off as usize;
+        let entry_bytes = &read_bytes[entry_off..entry_off + mem::size_of::<ExtraArrayEntry>()];
+        let entry = LayoutVerified::<&[u8], ExtraArrayEntry>::new(entry_bytes)
+            .expect("Failed to parse extra array entry");
+        assert_eq!(entry.array_type, 100);
+        assert_eq!(entry.num, 2);
+
+        let strings_bytes = &read_bytes[entry.off as usize..];
+        let mut strings = strings_bytes.split(|&b| b == 0);
+        assert_eq!(strings.next().unwrap(), b"foo");
+        assert_eq!(strings.next().unwrap(), b"bar");
+        Ok(())
+    }
+
     #[test]
     fn byte_limit() -> Result<(), Error> {
         const LIMIT: usize = zx::sys::ZX_CHANNEL_MAX_MSG_BYTES as usize;
@@ -388,4 +592,54 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn parse_round_trips_build() -> Result<(), Error> {
+        let config = MessageContents {
+            args: vec![CString::new("arg1")?, CString::new("arg2")?],
+            environment_vars: vec![CString::new("FOO=BAR")?],
+            namespace_paths: vec![CString::new("/pkg")?],
+            ..Default::default()
+        };
+        let message = Message::build(config)?;
+        let bytes = message.bytes.clone();
+
+        let parsed = Message::parse(&bytes, vec![])?;
+        assert_eq!(parsed.args, vec![CString::new("arg1")?, CString::new("arg2")?]);
+        assert_eq!(parsed.environment_vars, vec![CString::new("FOO=BAR")?]);
+        assert_eq!(parsed.namespace_paths, vec![CString::new("/pkg")?]);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_truncated_message_reports_plausible_offset() -> Result<(), Error> {
+        let config = MessageContents {
+            args: vec![CString::new("arg1")?, CString::new("arg2")?],
+            environment_vars: vec![CString::new("FOO=BAR")?],
+            ..Default::default()
+        };
+        let message = Message::build(config)?;
+        let args_off = {
+            let header = LayoutVerified::<&[u8], MessageHeader>::new_from_prefix(
+                message.bytes.as_slice(),
+            )
+            .unwrap()
+            .0;
+            header.args_off as usize
+        };
+
+        // Cut the message off partway through the first argument string, so there's no nul
+        // terminator left for `read_cstrings` to find.
+        let truncated = &message.bytes[..args_off + 2];
+
+        match Message::parse(truncated, vec![]) {
+            Err(ProcessargsError::TruncatedMessage { offset }) => {
+                // The read that failed started where the first argument string starts, not at
+                // the very end of the truncated buffer or at offset 0.
+                assert_eq!(offset, args_off);
+            }
+            other => panic!("Expected ProcessargsError::TruncatedMessage, got {:?}", other),
+        }
+        Ok(())
+    }
 }
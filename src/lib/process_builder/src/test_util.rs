@@ -0,0 +1,81 @@
+// Copyright 2020 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Test utilities.
+
+use {
+    fidl::endpoints::{ClientEnd, RequestStream},
+    fidl_fuchsia_ldsvc as fldsvc,
+    fuchsia_async as fasync,
+    fuchsia_zircon as zx,
+    futures::TryStreamExt,
+    std::collections::HashMap,
+    std::sync::{Arc, Mutex},
+};
+
+/// A hermetic, in-memory stand-in for the system loader service
+/// (`fuchsia.ldsvc.Loader`), for tests that want to control exactly what `ProcessBuilder` loads
+/// without depending on (or polluting) the real one. `load_object` requests are resolved from an
+/// in-memory table of names to VMOs seeded ahead of time with [MockLoaderService::add_object];
+/// `config` requests are accepted unconditionally, and `clone` requests are served by a fresh
+/// connection backed by the same table.
+#[derive(Clone, Default)]
+pub struct MockLoaderService {
+    objects: Arc<Mutex<HashMap<String, zx::Vmo>>>,
+}
+
+impl MockLoaderService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Seeds this mock so that a `load_object` request for `name` returns a duplicate of `vmo`.
+    pub fn add_object(&self, name: impl Into<String>, vmo: zx::Vmo) {
+        self.objects.lock().unwrap().insert(name.into(), vmo);
+    }
+
+    /// Creates a new `fuchsia.ldsvc.Loader` connection served by this mock, suitable for passing
+    /// directly to [crate::ProcessBuilder::set_loader_service].
+    pub fn spawn_loader(&self) -> ClientEnd<fldsvc::LoaderMarker> {
+        let (client, stream) = fidl::endpoints::create_request_stream::<fldsvc::LoaderMarker>()
+            .expect("Failed to create loader channel");
+        self.serve(stream);
+        client
+    }
+
+    fn serve(&self, mut stream: fldsvc::LoaderRequestStream) {
+        let this = self.clone();
+        fasync::Task::spawn(async move {
+            while let Some(req) =
+                stream.try_next().await.expect("Failed to read LoaderRequestStream")
+            {
+                match req {
+                    fldsvc::LoaderRequest::Done { control_handle } => {
+                        control_handle.shutdown();
+                    }
+                    fldsvc::LoaderRequest::LoadObject { object_name, responder } => {
+                        let vmo = this
+                            .objects
+                            .lock()
+                            .unwrap()
+                            .get(&object_name)
+                            .map(|vmo| vmo.duplicate_handle(zx::Rights::SAME_RIGHTS))
+                            .transpose()
+                            .expect("Failed to duplicate mock loader VMO");
+                        let status = if vmo.is_some() { zx::Status::OK } else { zx::Status::NOT_FOUND };
+                        responder.send(status.into_raw(), vmo).expect("Failed to send response");
+                    }
+                    fldsvc::LoaderRequest::Config { config: _, responder } => {
+                        responder.send(zx::Status::OK.into_raw()).expect("Failed to send response");
+                    }
+                    fldsvc::LoaderRequest::Clone { loader, responder } => {
+                        this.serve(loader.into_stream().expect("Failed to get request stream"));
+                        responder.send(zx::Status::OK.into_raw()).expect("Failed to send response");
+                    }
+                }
+            }
+        })
+        .detach();
+    }
+}
@@ -1018,6 +1018,7 @@ mod tests {
             source_path: CapabilityNameOrPath::Path(CapabilityPath {
                 dirname: "".to_string(),
                 basename: "".to_string(),
+                ..Default::default()
             }),
             subdir: None,
         });
@@ -1189,6 +1190,7 @@ mod tests {
             source_path: CapabilityNameOrPath::Path(CapabilityPath {
                 dirname: "".to_string(),
                 basename: "".to_string(),
+                ..Default::default()
             }),
             subdir: None,
         });
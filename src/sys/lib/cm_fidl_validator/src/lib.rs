@@ -4,6 +4,7 @@
 
 use {
     directed_graph::DirectedGraph,
+    fidl_fuchsia_io2 as fio2,
     fidl_fuchsia_sys2 as fsys,
     itertools::Itertools,
     std::{
@@ -19,7 +20,7 @@ const MAX_NAME_LENGTH: usize = 100;
 const MAX_URL_LENGTH: usize = 4096;
 
 /// Enum type that can represent any error encountered during validation.
-#[derive(Debug, Error, PartialEq)]
+#[derive(Clone, Debug, Error, PartialEq)]
 pub enum Error {
     #[error("{} missing {}", .0.decl, .0.field)]
     MissingField(DeclField),
@@ -47,6 +48,8 @@ pub enum Error {
     InvalidCapability(DeclField, String),
     #[error("\"{1}\" is referenced in {0} but it does not appear in runners")]
     InvalidRunner(DeclField, String),
+    #[error("\"{1}\" is referenced in {0} but it does not appear in resolvers")]
+    InvalidResolver(DeclField, String),
     #[error("\"{1}\" is referenced in {0} but it does not appear in events")]
     InvalidEventStream(DeclField, String),
     #[error("{0} specifies multiple runners")]
@@ -55,9 +58,363 @@ pub enum Error {
     DependencyCycle(String),
     #[error("{} \"{}\" path overlaps with {} \"{}\"", decl, path, other_decl, other_path)]
     InvalidPathOverlap { decl: DeclField, path: String, other_decl: DeclField, other_path: String },
+    #[error("{} has a program but no {} use declaration was found", .0.decl, .0.field)]
+    ProgramWithoutRunner(DeclField),
+    #[error(
+        "{} offers \"{1}\" from self, but no matching expose was found to verify it's provided",
+        .0
+    )]
+    SelfOfferUnverifiable(DeclField, String),
+    #[error("storage \"{0}\" offered into collection \"{1}\" has a mismatched durability")]
+    DurabilityMismatch(String, String),
+    #[error("component has {0} children, which exceeds the limit of {1}")]
+    TooManyChildren(usize, usize),
+    #[error("component has {0} collections, which exceeds the limit of {1}")]
+    TooManyCollections(usize, usize),
+    #[error("component has {0} storage capabilities, which exceeds the limit of {1}")]
+    TooManyStorage(usize, usize),
+    #[error("storage \"{0}\" is declared but never offered or used")]
+    UnusedStorage(String),
+    #[error("\"{0}\" is used as both a child name and a collection name")]
+    NameCollisionChildCollection(String),
+    #[error("{} has {} \"/meta\", which is reserved for meta storage", .0.decl, .0.field)]
+    ReservedMetaPath(DeclField),
+    #[error("{1} is not a valid source for {0}")]
+    InvalidUseSource(String, String),
+    #[error("capability \"{0}\" is offered to {1} targets, which exceeds the fan-out limit")]
+    HighFanOut(String, usize),
+    #[error(
+        "storage \"{0}\" is backed by a child that the same storage is then offered back to, \
+         creating a bootstrap cycle"
+    )]
+    StorageBackingCycle(String),
+    #[error("child \"{0}\" is started eagerly but is never offered any capability")]
+    EagerOrphanChild(String),
+    #[error(
+        "\"{1}\" is referenced in {0} as a source child, but it names a dynamic instance in \
+         collection \"{2}\" -- statically-declared refs can't name dynamic children"
+    )]
+    SourceChildInCollection(DeclField, String, String),
+    #[error("path \"{0}\" starts with the reserved prefix \"{1}\"")]
+    ReservedPathPrefix(String, String),
+    #[error("\"{0}\" offers more rights than its source provides")]
+    RightsEscalation(String),
+    #[error("child \"{0}\" is offered as a source but does not expose \"{1}\" to its parent")]
+    ChildDoesNotExpose(String, String),
+    #[error("{0}")]
+    CapabilityKindMismatch(String),
+    #[error(
+        "storage use of \"meta\" must target \"/meta\", but \"{0}\" was used as the target_path \
+         instead"
+    )]
+    MetaStorageMisconfigured(String),
+    #[error("\"{0}\" is not available until API level {1}")]
+    CapabilityRequiresApiLevel(String, u32),
+    #[error(
+        "\"{0}\" is used from the realm and also exposed from self with the same path, which \
+         is usually a config error"
+    )]
+    UseAndSelfExpose(String),
+    #[error("{0} references \"{1}\", which is in the denied capability path list")]
+    DeniedCapability(String, String),
+    #[error(
+        "\"{1}\" is offered from self to child \"{0}\", but that same path backs a storage \
+         capability whose source is also \"{0}\" -- routing the capability to \"{0}\" depends \
+         on \"{0}\" having already started, creating a bootstrap cycle"
+    )]
+    SelfOfferToBackingChild(String, String),
+    #[error("path \"{0}\" has depth {1}, which exceeds the limit of {2}")]
+    PathTooDeep(String, usize, usize),
+    #[error("child \"{0}\" is offered {1} capabilities, which exceeds the limit of {2}")]
+    ChildOverProvisioned(String, usize, usize),
+    #[error("service capability path \"{0}\" is not under the conventional \"/svc\" directory")]
+    NonconventionalServicePath(String),
+}
+
+/// The severity of an `Error`. `validate` only fails on `Severity::Error`; `Severity::Warning`
+/// is informational and doesn't by itself cause validation to fail.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// The kind of capability a `use`/`offer`/`expose` declaration refers to. Used by
+/// [ValidationOptions::capability_registry] to catch a capability being routed as the wrong kind,
+/// e.g. a directory being used where a service is expected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CapabilityKind {
+    Service,
+    Protocol,
+    Directory,
+    Storage,
+    Runner,
+    Resolver,
+    Event,
 }
 
 impl Error {
+    /// Returns the severity of this error, used to decide whether it should cause `validate` to
+    /// fail.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Error::ProgramWithoutRunner(_)
+            | Error::SelfOfferUnverifiable(_, _)
+            | Error::DurabilityMismatch(_, _)
+            | Error::UnusedStorage(_)
+            | Error::HighFanOut(_, _)
+            | Error::EagerOrphanChild(_)
+            | Error::UseAndSelfExpose(_)
+            | Error::NonconventionalServicePath(_) => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+
+    pub fn program_without_runner(decl_type: impl Into<String>, keyword: impl Into<String>) -> Self {
+        Error::ProgramWithoutRunner(DeclField { decl: decl_type.into(), field: keyword.into() })
+    }
+
+    pub fn self_offer_unverifiable(
+        decl_type: impl Into<String>,
+        keyword: impl Into<String>,
+        path: impl Into<String>,
+    ) -> Self {
+        Error::SelfOfferUnverifiable(
+            DeclField { decl: decl_type.into(), field: keyword.into() },
+            path.into(),
+        )
+    }
+
+    pub fn durability_mismatch(storage: impl Into<String>, collection: impl Into<String>) -> Self {
+        Error::DurabilityMismatch(storage.into(), collection.into())
+    }
+
+    pub fn too_many_children(actual: usize, max: usize) -> Self {
+        Error::TooManyChildren(actual, max)
+    }
+
+    pub fn too_many_collections(actual: usize, max: usize) -> Self {
+        Error::TooManyCollections(actual, max)
+    }
+
+    pub fn too_many_storage(actual: usize, max: usize) -> Self {
+        Error::TooManyStorage(actual, max)
+    }
+
+    pub fn unused_storage(storage: impl Into<String>) -> Self {
+        Error::UnusedStorage(storage.into())
+    }
+
+    pub fn name_collision_child_collection(name: impl Into<String>) -> Self {
+        Error::NameCollisionChildCollection(name.into())
+    }
+
+    pub fn reserved_meta_path(decl_type: impl Into<String>, keyword: impl Into<String>) -> Self {
+        Error::ReservedMetaPath(DeclField { decl: decl_type.into(), field: keyword.into() })
+    }
+
+    pub fn invalid_use_source(decl_type: impl Into<String>, ref_kind: impl Into<String>) -> Self {
+        Error::InvalidUseSource(decl_type.into(), ref_kind.into())
+    }
+
+    pub fn high_fan_out(capability: impl Into<String>, count: usize) -> Self {
+        Error::HighFanOut(capability.into(), count)
+    }
+
+    pub fn storage_backing_cycle(storage: impl Into<String>) -> Self {
+        Error::StorageBackingCycle(storage.into())
+    }
+
+    pub fn eager_orphan_child(child: impl Into<String>) -> Self {
+        Error::EagerOrphanChild(child.into())
+    }
+
+    pub fn source_child_in_collection(
+        decl_type: impl Into<String>,
+        keyword: impl Into<String>,
+        child: impl Into<String>,
+        collection: impl Into<String>,
+    ) -> Self {
+        Error::SourceChildInCollection(
+            DeclField { decl: decl_type.into(), field: keyword.into() },
+            child.into(),
+            collection.into(),
+        )
+    }
+
+    pub fn reserved_path_prefix(path: impl Into<String>, prefix: impl Into<String>) -> Self {
+        Error::ReservedPathPrefix(path.into(), prefix.into())
+    }
+
+    pub fn rights_escalation(name: impl Into<String>) -> Self {
+        Error::RightsEscalation(name.into())
+    }
+
+    pub fn child_does_not_expose(child: impl Into<String>, name: impl Into<String>) -> Self {
+        Error::ChildDoesNotExpose(child.into(), name.into())
+    }
+
+    pub fn meta_storage_misconfigured(target_path: impl Into<String>) -> Self {
+        Error::MetaStorageMisconfigured(target_path.into())
+    }
+
+    pub fn capability_requires_api_level(
+        path_or_name: impl Into<String>,
+        min_level: u32,
+    ) -> Self {
+        Error::CapabilityRequiresApiLevel(path_or_name.into(), min_level)
+    }
+
+    pub fn use_and_self_expose(path: impl Into<String>) -> Self {
+        Error::UseAndSelfExpose(path.into())
+    }
+
+    pub fn denied_capability(decl_type: impl Into<String>, path_or_name: impl Into<String>) -> Self {
+        Error::DeniedCapability(decl_type.into(), path_or_name.into())
+    }
+
+    pub fn self_offer_to_backing_child(child: impl Into<String>, path: impl Into<String>) -> Self {
+        Error::SelfOfferToBackingChild(child.into(), path.into())
+    }
+
+    pub fn path_too_deep(path: impl Into<String>, depth: usize, max: usize) -> Self {
+        Error::PathTooDeep(path.into(), depth, max)
+    }
+
+    pub fn child_over_provisioned(child: impl Into<String>, count: usize, max: usize) -> Self {
+        Error::ChildOverProvisioned(child.into(), count, max)
+    }
+
+    pub fn nonconventional_service_path(path: impl Into<String>) -> Self {
+        Error::NonconventionalServicePath(path.into())
+    }
+
+    pub fn capability_kind_mismatch(
+        path_or_name: impl Into<String>,
+        registered: CapabilityKind,
+        actual: CapabilityKind,
+    ) -> Self {
+        Error::CapabilityKindMismatch(format!(
+            "\"{}\" is registered as a {:?} capability, but is used here as a {:?}",
+            path_or_name.into(),
+            registered,
+            actual
+        ))
+    }
+
+    /// Returns `(decl_type, keyword, debug)`, a total order over errors that's independent of
+    /// the order in which validation visited the decl. `debug` is the error's `Debug` rendering
+    /// (not [Error::code]); it disambiguates errors that share a `decl_type`/`keyword` but differ
+    /// in their specific value (e.g. two `DuplicateField`s on the same field with different
+    /// offending names).
+    fn sort_key(&self) -> (&str, &str, String) {
+        let (decl_type, keyword) = self.decl_field();
+        (decl_type, keyword, format!("{:?}", self))
+    }
+
+    fn decl_field(&self) -> (&str, &str) {
+        match self {
+            Error::MissingField(f)
+            | Error::EmptyField(f)
+            | Error::ExtraneousField(f)
+            | Error::DuplicateField(f, _)
+            | Error::InvalidField(f)
+            | Error::FieldTooLong(f)
+            | Error::InvalidChild(f, _)
+            | Error::InvalidCollection(f, _)
+            | Error::InvalidStorage(f, _)
+            | Error::InvalidEnvironment(f, _)
+            | Error::InvalidCapability(f, _)
+            | Error::InvalidRunner(f, _)
+            | Error::InvalidResolver(f, _)
+            | Error::InvalidEventStream(f, _)
+            | Error::ProgramWithoutRunner(f)
+            | Error::SelfOfferUnverifiable(f, _)
+            | Error::ReservedMetaPath(f) => (f.decl.as_str(), f.field.as_str()),
+            Error::OfferTargetEqualsSource(decl, _)
+            | Error::MultipleRunnersSpecified(decl)
+            | Error::DependencyCycle(decl) => (decl.as_str(), ""),
+            Error::DurabilityMismatch(storage, _) => (storage.as_str(), ""),
+            Error::TooManyChildren(_, _) => ("ComponentDecl", "children"),
+            Error::TooManyCollections(_, _) => ("ComponentDecl", "collections"),
+            Error::TooManyStorage(_, _) => ("ComponentDecl", "capabilities"),
+            Error::UnusedStorage(name) => (name.as_str(), ""),
+            Error::NameCollisionChildCollection(name) => (name.as_str(), ""),
+            Error::InvalidPathOverlap { decl, .. } => (decl.decl.as_str(), decl.field.as_str()),
+            Error::InvalidUseSource(decl, _) => (decl.as_str(), ""),
+            Error::HighFanOut(capability, _) => (capability.as_str(), ""),
+            Error::StorageBackingCycle(name) => (name.as_str(), ""),
+            Error::EagerOrphanChild(name) => (name.as_str(), ""),
+            Error::SourceChildInCollection(f, _, _) => (f.decl.as_str(), f.field.as_str()),
+            Error::ReservedPathPrefix(path, prefix) => (path.as_str(), prefix.as_str()),
+            Error::RightsEscalation(name) => (name.as_str(), ""),
+            Error::ChildDoesNotExpose(child, _) => (child.as_str(), ""),
+            Error::CapabilityKindMismatch(message) => (message.as_str(), ""),
+            Error::MetaStorageMisconfigured(target_path) => (target_path.as_str(), ""),
+            Error::CapabilityRequiresApiLevel(path_or_name, _) => (path_or_name.as_str(), ""),
+            Error::UseAndSelfExpose(path) => (path.as_str(), ""),
+            Error::DeniedCapability(decl, _) => (decl.as_str(), ""),
+            Error::SelfOfferToBackingChild(child, _) => (child.as_str(), ""),
+            Error::PathTooDeep(path, _, _) => (path.as_str(), ""),
+            Error::ChildOverProvisioned(child, _, _) => (child.as_str(), ""),
+            Error::NonconventionalServicePath(path) => (path.as_str(), ""),
+        }
+    }
+
+    /// Returns a stable, snake_case identifier for this error's variant, independent of its
+    /// specific field values -- e.g. both `Error::missing_field("ChildDecl", "name")` and
+    /// `Error::missing_field("OfferDecl", "target")` return `"missing_field"`. Each identifier
+    /// matches the name of the constructor function used to build that variant. Intended for
+    /// tools that want to group or count errors by kind, such as [ErrorList::summary].
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::MissingField(_) => "missing_field",
+            Error::EmptyField(_) => "empty_field",
+            Error::ExtraneousField(_) => "extraneous_field",
+            Error::DuplicateField(_, _) => "duplicate_field",
+            Error::InvalidField(_) => "invalid_field",
+            Error::FieldTooLong(_) => "field_too_long",
+            Error::OfferTargetEqualsSource(_, _) => "offer_target_equals_source",
+            Error::InvalidChild(_, _) => "invalid_child",
+            Error::InvalidCollection(_, _) => "invalid_collection",
+            Error::InvalidStorage(_, _) => "invalid_storage",
+            Error::InvalidEnvironment(_, _) => "invalid_environment",
+            Error::InvalidCapability(_, _) => "invalid_capability",
+            Error::InvalidRunner(_, _) => "invalid_runner",
+            Error::InvalidResolver(_, _) => "invalid_resolver",
+            Error::InvalidEventStream(_, _) => "invalid_event_stream",
+            Error::MultipleRunnersSpecified(_) => "multiple_runners_specified",
+            Error::DependencyCycle(_) => "dependency_cycle",
+            Error::InvalidPathOverlap { .. } => "invalid_path_overlap",
+            Error::ProgramWithoutRunner(_) => "program_without_runner",
+            Error::SelfOfferUnverifiable(_, _) => "self_offer_unverifiable",
+            Error::DurabilityMismatch(_, _) => "durability_mismatch",
+            Error::TooManyChildren(_, _) => "too_many_children",
+            Error::TooManyCollections(_, _) => "too_many_collections",
+            Error::TooManyStorage(_, _) => "too_many_storage",
+            Error::UnusedStorage(_) => "unused_storage",
+            Error::NameCollisionChildCollection(_) => "name_collision_child_collection",
+            Error::ReservedMetaPath(_) => "reserved_meta_path",
+            Error::InvalidUseSource(_, _) => "invalid_use_source",
+            Error::HighFanOut(_, _) => "high_fan_out",
+            Error::StorageBackingCycle(_) => "storage_backing_cycle",
+            Error::EagerOrphanChild(_) => "eager_orphan_child",
+            Error::SourceChildInCollection(_, _, _) => "source_child_in_collection",
+            Error::ReservedPathPrefix(_, _) => "reserved_path_prefix",
+            Error::RightsEscalation(_) => "rights_escalation",
+            Error::ChildDoesNotExpose(_, _) => "child_does_not_expose",
+            Error::CapabilityKindMismatch(_) => "capability_kind_mismatch",
+            Error::MetaStorageMisconfigured(_) => "meta_storage_misconfigured",
+            Error::CapabilityRequiresApiLevel(_, _) => "capability_requires_api_level",
+            Error::UseAndSelfExpose(_) => "use_and_self_expose",
+            Error::DeniedCapability(_, _) => "denied_capability",
+            Error::SelfOfferToBackingChild(_, _) => "self_offer_to_backing_child",
+            Error::PathTooDeep(_, _, _) => "path_too_deep",
+            Error::ChildOverProvisioned(_, _, _) => "child_over_provisioned",
+            Error::NonconventionalServicePath(_) => "nonconventional_service_path",
+        }
+    }
+
     pub fn missing_field(decl_type: impl Into<String>, keyword: impl Into<String>) -> Self {
         Error::MissingField(DeclField { decl: decl_type.into(), field: keyword.into() })
     }
@@ -149,6 +506,17 @@ impl Error {
         )
     }
 
+    pub fn invalid_resolver(
+        decl_type: impl Into<String>,
+        keyword: impl Into<String>,
+        resolver: impl Into<String>,
+    ) -> Self {
+        Error::InvalidResolver(
+            DeclField { decl: decl_type.into(), field: keyword.into() },
+            resolver.into(),
+        )
+    }
+
     pub fn invalid_capability(
         decl_type: impl Into<String>,
         keyword: impl Into<String>,
@@ -216,6 +584,87 @@ impl ErrorList {
     fn new(errs: Vec<Error>) -> ErrorList {
         ErrorList { errs }
     }
+
+    /// Returns the errors sorted by `(decl_type, keyword)` and then by their `Debug` rendering.
+    /// Unlike the default order returned by `validate`, which follows decl iteration order, this
+    /// order is independent of how the decl was traversed, so it's suitable for
+    /// order-independent test assertions.
+    pub fn sorted(mut self) -> ErrorList {
+        self.errs.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+        self
+    }
+
+    /// Returns a count of errors grouped by [Error::code], e.g. `{"missing_field": 12,
+    /// "invalid_child": 3}`. Intended for tools that want a quick overview of a failed
+    /// validation without walking the full error list.
+    pub fn summary(&self) -> HashMap<&'static str, usize> {
+        let mut counts = HashMap::new();
+        for err in &self.errs {
+            *counts.entry(err.code()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Renders this error list as a minimal SARIF 2.1.0 document, with one `result` per error.
+    /// Each result's `ruleId` is the error's [Error::code] and its `message.text` is the error's
+    /// `Display` rendering; `source_file` is recorded as the artifact location for every result,
+    /// since `ErrorList` doesn't track which file (if any) a decl came from. Intended for
+    /// integrating `cm_fidl_validator` output with code-scanning dashboards that consume SARIF.
+    #[cfg(feature = "sarif")]
+    pub fn to_sarif(&self, source_file: &str) -> String {
+        let results: Vec<String> = self
+            .errs
+            .iter()
+            .map(|e| {
+                format!(
+                    concat!(
+                        "{{",
+                        "\"ruleId\":{},",
+                        "\"message\":{{\"text\":{}}},",
+                        "\"locations\":[{{\"physicalLocation\":{{\"artifactLocation\":{{\"uri\":{}}}}}}}]",
+                        "}}"
+                    ),
+                    sarif_json_string(e.code()),
+                    sarif_json_string(&e.to_string()),
+                    sarif_json_string(source_file),
+                )
+            })
+            .collect();
+        format!(
+            concat!(
+                "{{",
+                "\"$schema\":\"https://raw.githubusercontent.com/oasis-tcs/sarif-spec/master/Schemata/sarif-schema-2.1.0.json\",",
+                "\"version\":\"2.1.0\",",
+                "\"runs\":[{{",
+                "\"tool\":{{\"driver\":{{\"name\":\"cm_fidl_validator\"}}}},",
+                "\"results\":[{}]",
+                "}}]",
+                "}}"
+            ),
+            results.join(",")
+        )
+    }
+}
+
+/// Escapes and quotes `s` as a JSON string literal, for hand-assembling the minimal SARIF
+/// document in [ErrorList::to_sarif] without pulling in a JSON serialization library.
+#[cfg(feature = "sarif")]
+fn sarif_json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
 }
 
 impl fmt::Display for ErrorList {
@@ -238,10 +687,323 @@ impl fmt::Display for ErrorList {
 ///
 /// All checks are local to this ComponentDecl.
 pub fn validate(decl: &fsys::ComponentDecl) -> Result<(), ErrorList> {
-    let ctx = ValidationContext::default();
+    validate_with_options(decl, ValidationOptions::default())
+}
+
+/// Like `validate`, but allows product-specific policy to be plugged in via `options`.
+pub fn validate_with_options(
+    decl: &fsys::ComponentDecl,
+    options: ValidationOptions,
+) -> Result<(), ErrorList> {
+    let ctx = ValidationContext { options, ..ValidationContext::default() };
     ctx.validate(decl).map_err(|errs| ErrorList::new(errs))
 }
 
+/// A summary of how much of a `ComponentDecl` a `validate_with_stats` call processed, for tooling
+/// that wants to report on manifest complexity (e.g. across a build) without re-deriving it from
+/// the `ComponentDecl` itself.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ValidationStats {
+    pub children: usize,
+    pub collections: usize,
+    pub storage: usize,
+    pub uses: usize,
+    pub exposes: usize,
+    pub offers: usize,
+    pub errors: usize,
+}
+
+/// Like `validate`, but also returns a `ValidationStats` summary of `decl`'s contents alongside
+/// the validation result.
+pub fn validate_with_stats(
+    decl: &fsys::ComponentDecl,
+) -> (Result<(), ErrorList>, ValidationStats) {
+    let storage = decl.capabilities.as_ref().map_or(0, |caps| {
+        caps.iter().filter(|c| matches!(c, fsys::CapabilityDecl::Storage(_))).count()
+    });
+    let result = validate(decl);
+    let errors = result.as_ref().err().map_or(0, |errs| errs.errs.len());
+    let stats = ValidationStats {
+        children: decl.children.as_ref().map_or(0, |v| v.len()),
+        collections: decl.collections.as_ref().map_or(0, |v| v.len()),
+        storage,
+        uses: decl.uses.as_ref().map_or(0, |v| v.len()),
+        exposes: decl.exposes.as_ref().map_or(0, |v| v.len()),
+        offers: decl.offers.as_ref().map_or(0, |v| v.len()),
+        errors,
+    };
+    (result, stats)
+}
+
+/// Validates each of `decls` independently, keyed by an arbitrary identifier supplied by the
+/// caller (e.g. a component URL). A convenience for validating a whole build's worth of
+/// manifests at once.
+///
+/// Every check this crate performs locally (see `validate`'s doc comment) is local to a single
+/// `ComponentDecl`, so each entry in the map is exactly what `validate(&decls[key])` would have
+/// produced. For the one check that needs to see the whole set -- whether an offer sourced from a
+/// child is actually exposed by that child -- see [validate_cross_decl_child_exposes].
+pub fn validate_set(
+    decls: &HashMap<String, fsys::ComponentDecl>,
+) -> HashMap<String, Result<(), ErrorList>> {
+    decls.iter().map(|(key, decl)| (key.clone(), validate(decl))).collect()
+}
+
+/// Cross-decl check: for every offer in `decls` whose source is a child, verifies that the named
+/// child actually exposes the offered capability to its parent. This can't be checked by
+/// `validate`/`validate_set`, since those validate a single `ComponentDecl` in isolation and have
+/// no way to see what a child component's own decl exposes -- this function instead looks up the
+/// source child's decl in `decls`, keyed the same way `validate_set` expects (by an identifier
+/// that also appears as the child's `url`).
+///
+/// Children whose `url` isn't present in `decls` (e.g. the child lives in a package outside the
+/// set being validated) are skipped rather than flagged, since this check has nothing to compare
+/// against either way.
+///
+/// Returns a map, keyed like `decls`, from a decl's key to the `Error::ChildDoesNotExpose` errors
+/// found for it; keys with no such errors are omitted entirely.
+pub fn validate_cross_decl_child_exposes(
+    decls: &HashMap<String, fsys::ComponentDecl>,
+) -> HashMap<String, ErrorList> {
+    decls
+        .iter()
+        .filter_map(|(key, decl)| {
+            let errors = find_child_expose_errors(decl, decls);
+            if errors.is_empty() {
+                None
+            } else {
+                Some((key.clone(), ErrorList::new(errors)))
+            }
+        })
+        .collect()
+}
+
+/// Returns the `(child_name, source_id)` this offer is sourced from, if its source is a `Ref::Child`
+/// and it's a kind of offer that can validly be sourced from a child (storage and event offers
+/// can't be, per their FIDL doc comments, so they're never returned here).
+fn offer_child_source(offer: &fsys::OfferDecl) -> Option<(&str, &str)> {
+    let (source, source_id) = match offer {
+        fsys::OfferDecl::Service(o) => (o.source.as_ref(), o.source_name.as_ref()),
+        fsys::OfferDecl::Protocol(o) => (o.source.as_ref(), o.source_path.as_ref()),
+        fsys::OfferDecl::Directory(o) => (o.source.as_ref(), o.source_path.as_ref()),
+        fsys::OfferDecl::Runner(o) => (o.source.as_ref(), o.source_name.as_ref()),
+        fsys::OfferDecl::Resolver(o) => (o.source.as_ref(), o.source_name.as_ref()),
+        fsys::OfferDecl::Storage(_) | fsys::OfferDecl::Event(_) => return None,
+    };
+    match (source, source_id) {
+        (Some(fsys::Ref::Child(child)), Some(source_id)) => {
+            Some((child.name.as_str(), source_id.as_str()))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the `target` ref of any kind of offer, to back `ValidationOptions::max_offers_per_child`.
+fn offer_target(offer: &fsys::OfferDecl) -> Option<&fsys::Ref> {
+    match offer {
+        fsys::OfferDecl::Service(o) => o.target.as_ref(),
+        fsys::OfferDecl::Protocol(o) => o.target.as_ref(),
+        fsys::OfferDecl::Directory(o) => o.target.as_ref(),
+        fsys::OfferDecl::Storage(o) => o.target.as_ref(),
+        fsys::OfferDecl::Runner(o) => o.target.as_ref(),
+        fsys::OfferDecl::Resolver(o) => o.target.as_ref(),
+        fsys::OfferDecl::Event(o) => o.target.as_ref(),
+    }
+}
+
+/// Returns whether `decl` exposes `source_id` to its parent, under any expose kind that carries
+/// the matching name-or-path field.
+fn child_exposes(decl: &fsys::ComponentDecl, source_id: &str) -> bool {
+    let exposes = match decl.exposes.as_ref() {
+        Some(exposes) => exposes,
+        None => return false,
+    };
+    exposes.iter().any(|expose| {
+        let (target, target_id) = match expose {
+            fsys::ExposeDecl::Service(e) => (e.target.as_ref(), e.target_name.as_ref()),
+            fsys::ExposeDecl::Protocol(e) => (e.target.as_ref(), e.target_path.as_ref()),
+            fsys::ExposeDecl::Directory(e) => (e.target.as_ref(), e.target_path.as_ref()),
+            fsys::ExposeDecl::Runner(e) => (e.target.as_ref(), e.target_name.as_ref()),
+            fsys::ExposeDecl::Resolver(e) => (e.target.as_ref(), e.target_name.as_ref()),
+        };
+        matches!(target, Some(fsys::Ref::Parent(_)))
+            && target_id.map_or(false, |id| id == source_id)
+    })
+}
+
+fn find_child_expose_errors(
+    decl: &fsys::ComponentDecl,
+    decls: &HashMap<String, fsys::ComponentDecl>,
+) -> Vec<Error> {
+    let offers = match decl.offers.as_ref() {
+        Some(offers) => offers,
+        None => return vec![],
+    };
+    let children = match decl.children.as_ref() {
+        Some(children) => children,
+        None => return vec![],
+    };
+    let mut errors = vec![];
+    for offer in offers {
+        let (child_name, source_id) = match offer_child_source(offer) {
+            Some(v) => v,
+            None => continue,
+        };
+        let child = match children.iter().find(|c| c.name.as_deref() == Some(child_name)) {
+            Some(c) => c,
+            // An unresolvable child name is already flagged by `validate`.
+            None => continue,
+        };
+        let child_url = match child.url.as_ref() {
+            Some(url) => url,
+            None => continue,
+        };
+        let child_decl = match decls.get(child_url) {
+            Some(d) => d,
+            None => continue,
+        };
+        if !child_exposes(child_decl, source_id) {
+            errors.push(Error::child_does_not_expose(child_name, source_id));
+        }
+    }
+    errors
+}
+
+/// Like `validate_set`, but validates each decl on a `rayon` thread pool instead of serially.
+/// Results are identical to `validate_set`, since every check is local to a single decl.
+///
+/// Dispatch overhead makes this slower than `validate_set` for small sets; in local testing the
+/// break-even point was on the order of a few hundred decls, so prefer `validate_set` unless a
+/// build is validating at least that many manifests at once.
+#[cfg(feature = "parallel_validation")]
+pub fn validate_set_parallel(
+    decls: &HashMap<String, fsys::ComponentDecl>,
+) -> HashMap<String, Result<(), ErrorList>> {
+    use rayon::prelude::*;
+    decls.par_iter().map(|(key, decl)| (key.clone(), validate(decl))).collect()
+}
+
+/// Options that customize `validate_with_options`'s behavior. Use `..Default::default()` when
+/// constructing these, since new options may be added over time.
+pub struct ValidationOptions {
+    /// Given the `Durability` of a collection that a storage offer targets and the name of the
+    /// storage capability being offered, returns `true` if the pairing should be flagged with a
+    /// `Severity::Warning` `Error::DurabilityMismatch`. Durability policy (e.g. disallowing
+    /// ephemeral-looking storage from being offered into persistent collections) is
+    /// product-specific, so this defaults to a no-op that never flags a mismatch.
+    pub collection_durability_mismatch: Box<dyn Fn(&fsys::Durability, &str) -> bool>,
+
+    /// Maximum number of children a component may declare, or `None` for unlimited. Exceeding
+    /// this is a `Severity::Error` `Error::TooManyChildren`. Resource-constrained products may
+    /// use this to cap topology size.
+    pub max_children: Option<usize>,
+
+    /// Maximum number of collections a component may declare, or `None` for unlimited. Exceeding
+    /// this is a `Severity::Error` `Error::TooManyCollections`.
+    pub max_collections: Option<usize>,
+
+    /// Maximum number of storage capabilities a component may declare, or `None` for unlimited.
+    /// Exceeding this is a `Severity::Error` `Error::TooManyStorage`.
+    pub max_storage: Option<usize>,
+
+    /// Maximum number of children/collections the same `(source, source_path)` capability may be
+    /// offered to, or `None` for unlimited. Exceeding this is a `Severity::Warning`
+    /// `Error::HighFanOut`; offering one capability to hundreds of targets is usually a sign of a
+    /// copy-pasted manifest rather than an intentional topology.
+    pub max_fan_out: Option<usize>,
+
+    /// If `true`, any `Severity::Warning` finding causes `validate_with_options` to fail, the
+    /// same as a `Severity::Error` finding. For CI gates that want to treat warnings as hard
+    /// failures. Defaults to `false`, matching `validate`'s warnings-are-informational behavior.
+    pub deny_warnings: bool,
+
+    /// Path prefixes (e.g. `/svc/fuchsia.component.`) that a `use` target_path or `expose`
+    /// source_path may not start with, a `Severity::Error` `Error::ReservedPathPrefix`.
+    /// Reserving namespaces like this is product-specific policy, so this defaults to empty,
+    /// i.e. no restrictions.
+    pub reserved_path_prefixes: Vec<String>,
+
+    /// If `true`, and `facets` contains a `fuchsia.test` dictionary, validates that its
+    /// `injected-services` entries map to well-formed component URLs, a `Severity::Error`
+    /// `Error::InvalidField("facets", "fuchsia.test.injected-services")`. The `fuchsia.test`
+    /// facet is only meaningful to the test runner framework, not component manager itself, so
+    /// this defaults to `false`.
+    pub validate_test_facets: bool,
+
+    /// A registry of well-known capability paths or names to their expected [CapabilityKind],
+    /// e.g. `{"/svc/fuchsia.foo.Bar": CapabilityKind::Protocol}`. When present, any
+    /// `use`/`offer`/`expose` of a registered path or name whose own kind doesn't match is a
+    /// `Severity::Error` `Error::CapabilityKindMismatch` -- this catches a directory being used
+    /// where a service is expected. Maintaining this registry is product-specific, so this
+    /// defaults to `None`, i.e. no checking.
+    pub capability_registry: Option<HashMap<String, CapabilityKind>>,
+
+    /// The target platform API level this `ComponentDecl` is built against, or `None` to skip
+    /// this check entirely. When present, any `use`/`offer`/`expose` of a [CapabilityKind] that
+    /// isn't available until a later level (see `min_api_level`) is a `Severity::Error`
+    /// `Error::CapabilityRequiresApiLevel` -- e.g. catching a manifest using storage while
+    /// targeting a level from before storage capabilities existed. Gating by API level is
+    /// product-specific, so this defaults to `None`, i.e. no checking.
+    pub api_level: Option<u32>,
+
+    /// Capability paths or names that no `use`/`offer`/`expose` may reference at all, a
+    /// `Severity::Error` `Error::DeniedCapability`. Security reviewers use this to forbid
+    /// especially powerful capabilities (e.g. a debug service) from being routed by any
+    /// component, regardless of source. Maintaining this list is product-specific, so this
+    /// defaults to empty, i.e. no restrictions.
+    pub denied_capability_paths: HashSet<String>,
+
+    /// Maximum number of path components (e.g. `/svc/foo` has 2) a capability path may have, or
+    /// `None` for unlimited. Exceeding this is a `Severity::Error` `Error::PathTooDeep`. Deeply
+    /// nested paths are usually a sign of an overly specific namespace layout, so products may
+    /// use this to keep manifests consistent.
+    pub max_path_depth: Option<usize>,
+
+    /// Maximum number of capabilities that may be offered to any single child, or `None` for
+    /// unlimited. Exceeding this is a `Severity::Error` `Error::ChildOverProvisioned`. Unlike
+    /// `max_fan_out`, which limits how widely one capability is spread, this limits how many
+    /// distinct capabilities land on one child -- useful for keeping a child's effective sandbox
+    /// reviewable.
+    pub max_offers_per_child: Option<usize>,
+
+    /// If `true`, a `use` `target_path` for a service capability whose dirname isn't `/svc` is a
+    /// `Severity::Warning` `Error::NonconventionalServicePath`. Directory capabilities are exempt,
+    /// since they're commonly mounted elsewhere in the namespace (e.g. `/data`, `/pkg`). Defaults
+    /// to `false`, since some products intentionally mount services outside `/svc`.
+    pub warn_nonconventional_service_paths: bool,
+}
+
+impl Default for ValidationOptions {
+    fn default() -> Self {
+        Self {
+            collection_durability_mismatch: Box::new(|_, _| false),
+            max_children: None,
+            max_collections: None,
+            max_storage: None,
+            max_fan_out: None,
+            deny_warnings: false,
+            reserved_path_prefixes: vec![],
+            validate_test_facets: false,
+            capability_registry: None,
+            api_level: None,
+            denied_capability_paths: HashSet::new(),
+            max_path_depth: None,
+            max_offers_per_child: None,
+            warn_nonconventional_service_paths: false,
+        }
+    }
+}
+
+/// The platform API level at which each [CapabilityKind] became available, for
+/// [ValidationOptions::api_level]. Capability kinds not listed here have always been available,
+/// at any level.
+fn min_api_level(kind: CapabilityKind) -> Option<u32> {
+    match kind {
+        CapabilityKind::Storage => Some(5),
+        _ => None,
+    }
+}
+
 /// Validates a list of CapabilityDecls independently.
 pub fn validate_capabilities(capabilities: &Vec<fsys::CapabilityDecl>) -> Result<(), ErrorList> {
     let mut ctx = ValidationContext::default();
@@ -255,6 +1017,80 @@ pub fn validate_capabilities(capabilities: &Vec<fsys::CapabilityDecl>) -> Result
     }
 }
 
+/// Validates a list of ExposeDecls independently, including duplicate target_path detection
+/// across the slice, against a known set of child names. Useful for tooling that assembles
+/// exposes incrementally without a full `ComponentDecl`.
+pub fn validate_exposes(
+    exposes: &[fsys::ExposeDecl],
+    children: &HashSet<String>,
+) -> Result<(), ErrorList> {
+    let dummy_children: Vec<fsys::ChildDecl> = children
+        .iter()
+        .map(|name| fsys::ChildDecl {
+            name: Some(name.clone()),
+            url: None,
+            startup: None,
+            environment: None,
+        })
+        .collect();
+    let mut ctx = ValidationContext::default();
+    for child in &dummy_children {
+        ctx.all_children.insert(child.name.as_ref().unwrap().as_str(), child);
+    }
+    let mut target_ids = HashMap::new();
+    for expose in exposes {
+        ctx.validate_expose_decl(expose, &mut target_ids);
+    }
+    if ctx.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ErrorList::new(ctx.errors))
+    }
+}
+
+/// Validates a single `offer` being added to an already-valid `decl`, without re-validating the
+/// rest of `decl`. This is cheaper than calling `validate` on the whole decl after appending
+/// `offer`, which matters for editors that re-check after every edit.
+///
+/// `decl` is assumed to already be valid; errors from `decl` itself are not reported, only those
+/// caused by `offer` (including `offer` duplicating a target path already claimed by one of
+/// `decl`'s existing offers).
+pub fn validate_added_offer<'a>(
+    decl: &'a fsys::ComponentDecl,
+    offer: &'a fsys::OfferDecl,
+) -> Result<(), ErrorList> {
+    let mut ctx = ValidationContext::default();
+    if let Some(children) = decl.children.as_ref() {
+        for child in children {
+            ctx.validate_child_decl(&child);
+        }
+    }
+    if let Some(collections) = decl.collections.as_ref() {
+        for collection in collections {
+            ctx.validate_collection_decl(&collection);
+        }
+    }
+    if let Some(capabilities) = decl.capabilities.as_ref() {
+        for capability in capabilities {
+            ctx.validate_capability_decl(capability);
+        }
+    }
+    if let Some(offers) = decl.offers.as_ref() {
+        for existing_offer in offers {
+            ctx.validate_offers_decl(&existing_offer);
+        }
+    }
+    ctx.errors.clear();
+    ctx.validate_offers_decl(offer);
+    let hard_errors: Vec<_> =
+        ctx.errors.into_iter().filter(|e| e.severity() == Severity::Error).collect();
+    if hard_errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ErrorList::new(hard_errors))
+    }
+}
+
 /// Validates an independent ChildDecl. Performs the same validation on it as `validate`.
 pub fn validate_child(child: &fsys::ChildDecl) -> Result<(), ErrorList> {
     let mut errors = vec![];
@@ -273,15 +1109,66 @@ pub fn validate_child(child: &fsys::ChildDecl) -> Result<(), ErrorList> {
     }
 }
 
+/// Validates a `ChildDecl` to be added to an already-valid `decl`: checks the new child's own
+/// fields via `validate_child`, plus that its name doesn't collide with a child or collection
+/// `decl` already has. Doesn't revalidate the rest of `decl`, so editors that add children one at
+/// a time don't pay for a full re-validation on every edit.
+pub fn validate_added_child(
+    decl: &fsys::ComponentDecl,
+    child: &fsys::ChildDecl,
+) -> Result<(), ErrorList> {
+    let mut errors = match validate_child(child) {
+        Ok(()) => vec![],
+        Err(e) => e.errs,
+    };
+    if let Some(name) = child.name.as_ref() {
+        let name: &str = name;
+        let collides_with_child = decl
+            .children
+            .as_ref()
+            .map_or(false, |children| children.iter().any(|c| c.name.as_deref() == Some(name)));
+        if collides_with_child {
+            errors.push(Error::duplicate_field("ChildDecl", "name", name));
+        }
+        let collides_with_collection = decl.collections.as_ref().map_or(false, |collections| {
+            collections.iter().any(|c| c.name.as_deref() == Some(name))
+        });
+        if collides_with_collection {
+            errors.push(Error::name_collision_child_collection(name));
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ErrorList { errs: errors })
+    }
+}
+
 #[derive(Default)]
 struct ValidationContext<'a> {
     all_children: HashMap<&'a str, &'a fsys::ChildDecl>,
     all_collections: HashSet<&'a str>,
+    collection_durability: HashMap<&'a str, &'a fsys::Durability>,
     all_capability_ids: HashSet<&'a str>,
     all_storage_and_sources: HashMap<&'a str, Option<&'a str>>,
+    // Path-form `source_path`s of `StorageDecl`s, keyed by the child name that backs them, to
+    // back the `Error::SelfOfferToBackingChild` check.
+    storage_backing_paths: HashMap<&'a str, HashSet<&'a str>>,
+    referenced_storage: HashSet<&'a str>,
+    // Names of children that are the target of at least one offer, to back the
+    // `Error::EagerOrphanChild` warning.
+    offered_to_children: HashSet<&'a str>,
     all_services: HashSet<&'a str>,
+    self_exposed_dir_paths: HashSet<&'a str>,
+    // `target_path`s of `UseProtocolDecl`/`UseDirectoryDecl` entries, to back the
+    // `Error::UseAndSelfExpose` check: a path used from the realm and then re-exposed from self
+    // at the same path usually isn't the same capability in both directions.
+    use_target_paths: HashSet<&'a str>,
     all_protocols: HashSet<&'a str>,
     all_directories: HashSet<&'a str>,
+    // Rights declared by each `DirectoryDecl`, keyed by name, to back the rights-escalation
+    // check on self-sourced `OfferDirectoryDecl`/`ExposeDirectoryDecl`.
+    directory_rights: HashMap<&'a str, fio2::Operations>,
     all_runners: HashSet<&'a str>,
     all_resolvers: HashSet<&'a str>,
     all_environment_names: HashSet<&'a str>,
@@ -289,7 +1176,13 @@ struct ValidationContext<'a> {
     all_event_streams: HashSet<&'a str>,
     strong_dependencies: DirectedGraph<DependencyNode<'a>>,
     target_ids: IdMap<'a>,
+    // Counts offers per `(source, source_path)` key, as produced by `fan_out_key`, to back
+    // `ValidationOptions::max_fan_out`.
+    offer_fan_out: HashMap<String, usize>,
+    // Counts offers per target child name, to back `ValidationOptions::max_offers_per_child`.
+    offers_per_child: HashMap<&'a str, usize>,
     errors: Vec<Error>,
+    options: ValidationOptions,
 }
 
 /// A node in the DependencyGraph. The first string describes the type of node and the second
@@ -336,6 +1229,11 @@ impl<'a> ValidationContext<'a> {
                 self.validate_child_decl(&child);
             }
         }
+        if let Some(max_children) = self.options.max_children {
+            if self.all_children.len() > max_children {
+                self.errors.push(Error::too_many_children(self.all_children.len(), max_children));
+            }
+        }
 
         // Validate "collections" and build the set of all collections.
         if let Some(collections) = decl.collections.as_ref() {
@@ -343,6 +1241,13 @@ impl<'a> ValidationContext<'a> {
                 self.validate_collection_decl(&collection);
             }
         }
+        if let Some(max_collections) = self.options.max_collections {
+            if self.all_collections.len() > max_collections {
+                self.errors
+                    .push(Error::too_many_collections(self.all_collections.len(), max_collections));
+            }
+        }
+        self.check_child_collection_name_collisions();
 
         // Validate "capabilities" and build the set of all capabilities.
         if let Some(capabilities) = decl.capabilities.as_ref() {
@@ -350,12 +1255,31 @@ impl<'a> ValidationContext<'a> {
                 self.validate_capability_decl(capability);
             }
         }
+        if let Some(max_storage) = self.options.max_storage {
+            if self.all_storage_and_sources.len() > max_storage {
+                self.errors
+                    .push(Error::too_many_storage(self.all_storage_and_sources.len(), max_storage));
+            }
+        }
 
         // Validate "uses".
         if let Some(uses) = decl.uses.as_ref() {
             self.validate_use_decls(uses);
         }
 
+        // A component with a `program` should be using a runner to run it, either explicitly
+        // or (unprovable from this decl alone) implicitly through an inherited environment. We
+        // can only detect the explicit case, so this is a warning rather than a hard error.
+        if decl.program.is_some() {
+            let has_runner_use = decl
+                .uses
+                .as_ref()
+                .map_or(false, |uses| uses.iter().any(|u| matches!(u, fsys::UseDecl::Runner(_))));
+            if !has_runner_use {
+                self.errors.push(Error::program_without_runner("ComponentDecl", "use"));
+            }
+        }
+
         // Validate "exposes".
         if let Some(exposes) = decl.exposes.as_ref() {
             let mut target_ids = HashMap::new();
@@ -371,6 +1295,18 @@ impl<'a> ValidationContext<'a> {
             }
         }
 
+        // Warn about storage capabilities that no offer or use ever references.
+        self.check_unused_storage();
+
+        // Warn about capabilities offered to more targets than `ValidationOptions::max_fan_out`.
+        self.check_offer_fan_out();
+
+        // Flag children offered more capabilities than `ValidationOptions::max_offers_per_child`.
+        self.check_offers_per_child();
+
+        // Warn about eager children that are never offered anything.
+        self.check_eager_orphan_children();
+
         // Validate "environments" after all other declarations are processed.
         if let Some(environment) = decl.environments.as_ref() {
             for environment in environment {
@@ -383,10 +1319,25 @@ impl<'a> ValidationContext<'a> {
             self.errors.push(Error::dependency_cycle(e.format_cycle()));
         }
 
-        if self.errors.is_empty() {
-            Ok(())
-        } else {
+        // Optionally validate the `fuchsia.test` facet's shape.
+        if self.options.validate_test_facets {
+            if let Some(facets) = decl.facets.as_ref() {
+                self.validate_test_facets(facets);
+            }
+        }
+
+        // Normally only `Severity::Error` findings fail validation; with `deny_warnings` set, a
+        // `Severity::Warning` finding fails it too. Either way, once validation is failing, the
+        // returned list carries every finding (errors and warnings alike) rather than just the
+        // ones that caused the failure, so callers can see the whole picture.
+        let fails = self.errors.iter().any(|e| {
+            e.severity() == Severity::Error
+                || (self.options.deny_warnings && e.severity() == Severity::Warning)
+        });
+        if fails {
             Err(self.errors)
+        } else {
+            Ok(())
         }
     }
 
@@ -441,6 +1392,14 @@ impl<'a> ValidationContext<'a> {
                     "target_path",
                     &mut self.errors,
                 );
+                if let Some(target_path) = u.target_path.as_ref() {
+                    self.check_reserved_path_prefix(target_path);
+                    self.check_service_path_convention(target_path);
+                }
+                if let Some(source_name) = u.source_name.as_ref() {
+                    self.check_capability_registry(source_name, CapabilityKind::Service);
+                    self.check_api_level(source_name, CapabilityKind::Service);
+                }
             }
             fsys::UseDecl::Protocol(u) => {
                 self.validate_source(u.source.as_ref(), "UseProtocolDecl", "source");
@@ -456,6 +1415,16 @@ impl<'a> ValidationContext<'a> {
                     "target_path",
                     &mut self.errors,
                 );
+                if let Some(target_path) = u.target_path.as_ref().filter(|p| p.starts_with('/')) {
+                    self.check_reserved_path_prefix(target_path);
+                    self.check_path_depth(target_path);
+                    self.use_target_paths.insert(target_path.as_str());
+                }
+                if let Some(source_path) = u.source_path.as_ref() {
+                    self.check_capability_registry(source_path, CapabilityKind::Protocol);
+                    self.check_api_level(source_path, CapabilityKind::Protocol);
+                    self.check_denied_capability("UseProtocolDecl", source_path);
+                }
             }
             fsys::UseDecl::Directory(u) => {
                 self.validate_source(u.source.as_ref(), "UseDirectoryDecl", "source");
@@ -471,6 +1440,11 @@ impl<'a> ValidationContext<'a> {
                     "target_path",
                     &mut self.errors,
                 );
+                if let Some(target_path) = u.target_path.as_ref().filter(|p| p.starts_with('/')) {
+                    self.check_reserved_path_prefix(target_path);
+                    self.check_path_depth(target_path);
+                    self.use_target_paths.insert(target_path.as_str());
+                }
                 if u.rights.is_none() {
                     self.errors.push(Error::missing_field("UseDirectoryDecl", "rights"));
                 }
@@ -482,6 +1456,11 @@ impl<'a> ValidationContext<'a> {
                         &mut self.errors,
                     );
                 }
+                if let Some(source_path) = u.source_path.as_ref() {
+                    self.check_capability_registry(source_path, CapabilityKind::Directory);
+                    self.check_api_level(source_path, CapabilityKind::Directory);
+                    self.check_denied_capability("UseDirectoryDecl", source_path);
+                }
             }
             fsys::UseDecl::Storage(u) => {
                 check_name(
@@ -496,6 +1475,33 @@ impl<'a> ValidationContext<'a> {
                     "target_path",
                     &mut self.errors,
                 );
+                if let Some(name) = u.source_name.as_ref() {
+                    self.referenced_storage.insert(name.as_str());
+                }
+                // "/meta" is reserved for meta storage; any other storage use targeting it
+                // would collide with meta storage's fixed location.
+                if u.source_name.as_deref() != Some("meta")
+                    && u.target_path.as_deref() == Some("/meta")
+                {
+                    self.errors.push(Error::reserved_meta_path("UseStorageDecl", "target_path"));
+                }
+                // This schema has no `StorageType`/Data-vs-Cache-vs-Meta field on
+                // `UseStorageDecl`/`StorageDecl` -- "meta" storage is identified purely by the
+                // convention that its source_name is literally "meta", and its routing
+                // requirement is that it always lands at the fixed "/meta" target_path. So the
+                // closest single-decl check to "a meta storage use paired with a non-meta-capable
+                // declaration" is the converse of the check above: a use that names the "meta"
+                // storage but doesn't target "/meta" is just as misconfigured.
+                if u.source_name.as_deref() == Some("meta")
+                    && u.target_path.as_deref() != Some("/meta")
+                {
+                    if let Some(target_path) = u.target_path.as_ref() {
+                        self.errors.push(Error::meta_storage_misconfigured(target_path));
+                    }
+                }
+                if let Some(source_name) = u.source_name.as_ref() {
+                    self.check_api_level(source_name, CapabilityKind::Storage);
+                }
             }
             fsys::UseDecl::Runner(r) => {
                 check_name(
@@ -504,6 +1510,10 @@ impl<'a> ValidationContext<'a> {
                     "source_name",
                     &mut self.errors,
                 );
+                if let Some(source_name) = r.source_name.as_ref() {
+                    self.check_capability_registry(source_name, CapabilityKind::Runner);
+                    self.check_api_level(source_name, CapabilityKind::Runner);
+                }
             }
             fsys::UseDecl::Event(e) => {
                 self.validate_event(e);
@@ -673,6 +1683,15 @@ impl<'a> ValidationContext<'a> {
         match source {
             Some(fsys::Ref::Parent(_)) => {}
             Some(fsys::Ref::Framework(_)) => {}
+            Some(fsys::Ref::Self_(_)) => {
+                self.errors.push(Error::invalid_use_source(decl, "self"));
+            }
+            Some(fsys::Ref::Child(_)) => {
+                self.errors.push(Error::invalid_use_source(decl, "child"));
+            }
+            Some(fsys::Ref::Collection(_)) => {
+                self.errors.push(Error::invalid_use_source(decl, "collection"));
+            }
             Some(_) => {
                 self.errors.push(Error::invalid_field(decl, field));
             }
@@ -715,6 +1734,9 @@ impl<'a> ValidationContext<'a> {
             if !self.all_collections.insert(name) {
                 self.errors.push(Error::duplicate_field("CollectionDecl", "name", name));
             }
+            if let Some(durability) = collection.durability.as_ref() {
+                self.collection_durability.insert(name, durability);
+            }
             // If there is an environment, we don't need to account for it in the dependency
             // graph because a collection is always a sink node.
         }
@@ -821,6 +1843,14 @@ impl<'a> ValidationContext<'a> {
             resolver_registration.source.as_ref(),
             "ResolverRegistration",
         );
+        // If the source is `self`, ensure we have a corresponding ResolverDecl.
+        if let (Some(fsys::Ref::Self_(_)), Some(ref name)) =
+            (&resolver_registration.source, &resolver_registration.resolver)
+        {
+            if !self.all_resolvers.contains(name as &str) {
+                self.errors.push(Error::invalid_resolver("ResolverRegistration", "resolver", name));
+            }
+        }
         check_url_scheme(
             resolver_registration.scheme.as_ref(),
             "ResolverRegistration",
@@ -898,7 +1928,11 @@ impl<'a> ValidationContext<'a> {
             "source_path",
             &mut self.errors,
         );
-        if directory.rights.is_none() {
+        if let Some(rights) = directory.rights {
+            if let Some(name) = directory.name.as_ref() {
+                self.directory_rights.insert(name, rights);
+            }
+        } else {
             self.errors.push(Error::missing_field("DirectoryDecl", "rights"));
         }
     }
@@ -933,6 +1967,15 @@ impl<'a> ValidationContext<'a> {
             "source_path",
             &mut self.errors,
         );
+        if let (Some(child_name), Some(source_path)) = (
+            source_child_name,
+            storage.source_path.as_ref().filter(|p| p.starts_with('/')),
+        ) {
+            self.storage_backing_paths
+                .entry(child_name)
+                .or_insert_with(HashSet::new)
+                .insert(source_path.as_str());
+        }
     }
 
     fn validate_runner_decl(&mut self, runner: &'a fsys::RunnerDecl) {
@@ -972,27 +2015,146 @@ impl<'a> ValidationContext<'a> {
         check_path(resolver.source_path.as_ref(), "ResolverDecl", "source_path", &mut self.errors);
     }
 
-    fn validate_source_child(&mut self, child: &fsys::ChildRef, decl_type: &str) {
-        let mut valid = true;
-        valid &= check_name(Some(&child.name), decl_type, "source.child.name", &mut self.errors);
-        valid &= if child.collection.is_some() {
-            self.errors.push(Error::extraneous_field(decl_type, "source.child.collection"));
-            false
-        } else {
-            true
-        };
-        if !valid {
-            return;
-        }
-        if !self.all_children.contains_key(&child.name as &str) {
-            self.errors.push(Error::invalid_child(decl_type, "source", &child.name as &str));
+    /// Flags `path` if it starts with one of `self.options.reserved_path_prefixes`. A no-op
+    /// when that list is empty, which it is unless the caller opted in via `ValidationOptions`.
+    fn check_reserved_path_prefix(&mut self, path: &str) {
+        for prefix in &self.options.reserved_path_prefixes {
+            if path.starts_with(prefix.as_str()) {
+                self.errors.push(Error::reserved_path_prefix(path, prefix.as_str()));
+            }
         }
     }
 
-    fn validate_storage_source(&mut self, source_name: &String, decl_type: &str) {
-        if check_name(Some(source_name), decl_type, "source.storage.name", &mut self.errors) {
-            if !self.all_storage_and_sources.contains_key(source_name.as_str()) {
-                self.errors.push(Error::invalid_storage(decl_type, "source", source_name));
+    /// Flags `path` with `Error::PathTooDeep` if it has more path components than
+    /// `self.options.max_path_depth`. A no-op when that limit isn't configured.
+    fn check_path_depth(&mut self, path: &str) {
+        if let Some(max) = self.options.max_path_depth {
+            let depth = path.split('/').filter(|s| !s.is_empty()).count();
+            if depth > max {
+                self.errors.push(Error::path_too_deep(path, depth, max));
+            }
+        }
+    }
+
+    /// Flags `path` with a `Severity::Warning` `Error::NonconventionalServicePath` if its dirname
+    /// isn't `/svc`. A no-op unless `self.options.warn_nonconventional_service_paths` is set.
+    fn check_service_path_convention(&mut self, path: &str) {
+        if !self.options.warn_nonconventional_service_paths {
+            return;
+        }
+        let dirname = match path.rfind('/') {
+            Some(0) => "/",
+            Some(idx) => &path[..idx],
+            None => path,
+        };
+        if dirname != "/svc" {
+            self.errors.push(Error::nonconventional_service_path(path));
+        }
+    }
+
+    /// Flags `path` with a `Severity::Warning` `Error::UseAndSelfExpose` if it's also the
+    /// `target_path` of a `use` from realm. A component that both pulls a path in from its realm
+    /// and re-exposes the identical path from itself is usually a config error: the path can't
+    /// simultaneously be routed through and provided locally.
+    fn check_use_and_self_expose(&mut self, path: &str) {
+        if self.use_target_paths.contains(path) {
+            self.errors.push(Error::use_and_self_expose(path));
+        }
+    }
+
+    /// Flags `path_or_name` if `self.options.capability_registry` has an entry for it whose
+    /// [CapabilityKind] doesn't match `actual`. A no-op when the registry isn't configured or
+    /// doesn't mention `path_or_name`.
+    fn check_capability_registry(&mut self, path_or_name: &str, actual: CapabilityKind) {
+        if let Some(registry) = self.options.capability_registry.as_ref() {
+            if let Some(registered) = registry.get(path_or_name) {
+                if *registered != actual {
+                    self.errors.push(Error::capability_kind_mismatch(
+                        path_or_name,
+                        *registered,
+                        actual,
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Flags a `Self_`-sourced offer to child `target` at `path` if `path` also backs a storage
+    /// capability declared in this same decl whose source is that same child `target`: routing
+    /// the offered capability to `target` would then depend on `target` having already started
+    /// to back the storage, creating a bootstrap cycle much like `Error::StorageBackingCycle`.
+    ///
+    /// Only catches the case visible from this one `ComponentDecl` -- the offered path must
+    /// literally equal the storage's `source_path` on the same child here.
+    fn check_self_offer_to_backing_child(&mut self, target: Option<&'a fsys::Ref>, path: &str) {
+        if let Some(fsys::Ref::Child(c)) = target {
+            if self
+                .storage_backing_paths
+                .get(c.name.as_str())
+                .map_or(false, |paths| paths.contains(path))
+            {
+                self.errors.push(Error::self_offer_to_backing_child(c.name.as_str(), path));
+            }
+        }
+    }
+
+    /// Flags `path_or_name` with `Error::DeniedCapability` if it appears in
+    /// `self.options.denied_capability_paths`. A no-op when the list is empty.
+    fn check_denied_capability(&mut self, decl_type: &str, path_or_name: &str) {
+        if self.options.denied_capability_paths.contains(path_or_name) {
+            self.errors.push(Error::denied_capability(decl_type, path_or_name));
+        }
+    }
+
+    /// Flags `path_or_name` if `self.options.api_level` is set and is earlier than `kind`'s
+    /// `min_api_level`. A no-op when `api_level` isn't configured or `kind` has no minimum.
+    fn check_api_level(&mut self, path_or_name: &str, kind: CapabilityKind) {
+        if let Some(level) = self.options.api_level {
+            if let Some(min_level) = min_api_level(kind) {
+                if level < min_level {
+                    self.errors.push(Error::capability_requires_api_level(path_or_name, min_level));
+                }
+            }
+        }
+    }
+
+    fn validate_source_child(&mut self, child: &fsys::ChildRef, decl_type: &str) {
+        let mut valid = true;
+        valid &= check_name(Some(&child.name), decl_type, "source.child.name", &mut self.errors);
+        valid &= if let Some(collection) = &child.collection {
+            // A source `ChildRef` is only ever meant to name a statically-declared child, so
+            // `collection` should never be set here. If it happens to name a real collection,
+            // call that out specifically -- it means the reference is trying to name a dynamic
+            // instance, which can't be done statically -- rather than reporting the same
+            // generic extraneous-field error as any other unexpected `collection` value.
+            if self.all_collections.contains(collection.as_str()) {
+                self.errors.push(Error::source_child_in_collection(
+                    decl_type,
+                    "source.child.collection",
+                    &child.name as &str,
+                    collection.as_str(),
+                ));
+            } else {
+                self.errors.push(Error::extraneous_field(decl_type, "source.child.collection"));
+            }
+            false
+        } else {
+            true
+        };
+        if !valid {
+            return;
+        }
+        if !self.all_children.contains_key(&child.name as &str) {
+            self.errors.push(Error::invalid_child(decl_type, "source", &child.name as &str));
+        }
+    }
+
+    fn validate_storage_source(&mut self, source_name: &'a String, decl_type: &str) {
+        if check_name(Some(source_name), decl_type, "source.storage.name", &mut self.errors) {
+            if !self.all_storage_and_sources.contains_key(source_name.as_str()) {
+                self.errors.push(Error::invalid_storage(decl_type, "source", source_name));
+            } else {
+                self.referenced_storage.insert(source_name.as_str());
             }
         }
     }
@@ -1021,6 +2183,10 @@ impl<'a> ValidationContext<'a> {
                         self.errors.push(Error::invalid_capability(decl, "source", name));
                     }
                 }
+                if let Some(source_name) = e.source_name.as_ref() {
+                    self.check_capability_registry(source_name, CapabilityKind::Service);
+                    self.check_api_level(source_name, CapabilityKind::Service);
+                }
             }
             fsys::ExposeDecl::Protocol(e) => {
                 let decl = "ExposeProtocolDecl";
@@ -1040,6 +2206,18 @@ impl<'a> ValidationContext<'a> {
                         self.errors.push(Error::invalid_capability(decl, "source", name));
                     }
                 }
+                if let Some(source_path) = e.source_path.as_ref().filter(|p| p.starts_with('/')) {
+                    self.check_reserved_path_prefix(source_path);
+                    self.check_path_depth(source_path);
+                    if matches!(&e.source, Some(fsys::Ref::Self_(_))) {
+                        self.check_use_and_self_expose(source_path);
+                    }
+                }
+                if let Some(source_path) = e.source_path.as_ref() {
+                    self.check_capability_registry(source_path, CapabilityKind::Protocol);
+                    self.check_api_level(source_path, CapabilityKind::Protocol);
+                    self.check_denied_capability(decl, source_path);
+                }
             }
             fsys::ExposeDecl::Directory(e) => {
                 let decl = "ExposeDirectoryDecl";
@@ -1061,6 +2239,19 @@ impl<'a> ValidationContext<'a> {
                     if name.starts_with('/') && e.rights.is_none() {
                         self.errors.push(Error::missing_field(decl, "rights"));
                     }
+                    if name.starts_with('/') {
+                        self.self_exposed_dir_paths.insert(name.as_str());
+                        self.check_use_and_self_expose(name);
+                    }
+                }
+                if let Some(source_path) = e.source_path.as_ref().filter(|p| p.starts_with('/')) {
+                    self.check_reserved_path_prefix(source_path);
+                    self.check_path_depth(source_path);
+                }
+                if let Some(source_path) = e.source_path.as_ref() {
+                    self.check_capability_registry(source_path, CapabilityKind::Directory);
+                    self.check_api_level(source_path, CapabilityKind::Directory);
+                    self.check_denied_capability(decl, source_path);
                 }
 
                 // Subdir makes sense when routing, but when exposing to framework the subdirectory
@@ -1242,10 +2433,24 @@ impl<'a> ValidationContext<'a> {
         }
     }
 
+    /// Records that `offer` contributes one more target to its `(source, source_path)` fan-out
+    /// count, backing `ValidationOptions::max_fan_out`.
+    fn count_offer_fan_out(&mut self, source: Option<&fsys::Ref>, capability: Option<&str>) {
+        let capability = match capability {
+            Some(capability) => capability,
+            None => return,
+        };
+        *self.offer_fan_out.entry(fan_out_key(source, capability)).or_insert(0) += 1;
+    }
+
     fn validate_offers_decl(&mut self, offer: &'a fsys::OfferDecl) {
+        if let Some(fsys::Ref::Child(c)) = offer_target(offer) {
+            *self.offers_per_child.entry(c.name.as_str()).or_insert(0) += 1;
+        }
         match offer {
             fsys::OfferDecl::Service(o) => {
                 let decl = "OfferServiceDecl";
+                self.count_offer_fan_out(o.source.as_ref(), o.source_name.as_deref());
                 self.validate_offer_fields_with_name(
                     decl,
                     AllowableIds::Many,
@@ -1261,10 +2466,15 @@ impl<'a> ValidationContext<'a> {
                         self.errors.push(Error::invalid_field(decl, "source"));
                     }
                 }
+                if let Some(source_name) = o.source_name.as_ref() {
+                    self.check_capability_registry(source_name, CapabilityKind::Service);
+                    self.check_api_level(source_name, CapabilityKind::Service);
+                }
                 self.add_strong_dep(o.source.as_ref(), o.target.as_ref());
             }
             fsys::OfferDecl::Protocol(o) => {
                 let decl = "OfferProtocolDecl";
+                self.count_offer_fan_out(o.source.as_ref(), o.source_path.as_deref());
                 self.validate_offer_fields_with_name_or_path(
                     decl,
                     AllowableIds::One,
@@ -1284,10 +2494,19 @@ impl<'a> ValidationContext<'a> {
                     if !name.starts_with('/') && !self.all_protocols.contains(&name as &str) {
                         self.errors.push(Error::invalid_capability(decl, "source", name));
                     }
+                    if name.starts_with('/') {
+                        self.check_self_offer_to_backing_child(o.target.as_ref(), name);
+                    }
+                }
+                if let Some(source_path) = o.source_path.as_ref() {
+                    self.check_capability_registry(source_path, CapabilityKind::Protocol);
+                    self.check_api_level(source_path, CapabilityKind::Protocol);
+                    self.check_denied_capability(decl, source_path);
                 }
             }
             fsys::OfferDecl::Directory(o) => {
                 let decl = "OfferDirectoryDecl";
+                self.count_offer_fan_out(o.source.as_ref(), o.source_path.as_deref());
                 self.validate_offer_fields_with_name_or_path(
                     decl,
                     AllowableIds::One,
@@ -1306,10 +2525,30 @@ impl<'a> ValidationContext<'a> {
                 if let (Some(fsys::Ref::Self_(_)), Some(ref name)) = (&o.source, &o.source_path) {
                     if !name.starts_with('/') && !self.all_directories.contains(&name as &str) {
                         self.errors.push(Error::invalid_capability(decl, "source", name));
+                    } else if !name.starts_with('/') {
+                        // Offering more rights than the source `DirectoryDecl` declares is an
+                        // escalation and not allowed.
+                        if let (Some(source_rights), Some(offered_rights)) =
+                            (self.directory_rights.get(name as &str), o.rights.as_ref())
+                        {
+                            if !source_rights.contains(*offered_rights) {
+                                self.errors.push(Error::rights_escalation(name));
+                            }
+                        }
                     }
                     if name.starts_with('/') && o.rights.is_none() {
                         self.errors.push(Error::missing_field(decl, "rights"));
                     }
+                    // A raw path offer from self isn't backed by a declared DirectoryDecl, so we
+                    // can't fully confirm this component provides it. The best we can do locally
+                    // is check for a matching self expose of the same path.
+                    if name.starts_with('/') && !self.self_exposed_dir_paths.contains(name.as_str())
+                    {
+                        self.errors.push(Error::self_offer_unverifiable(decl, "source_path", name));
+                    }
+                    if name.starts_with('/') {
+                        self.check_self_offer_to_backing_child(o.target.as_ref(), name);
+                    }
                 }
                 if let Some(subdir) = o.subdir.as_ref() {
                     check_relative_path(
@@ -1319,8 +2558,22 @@ impl<'a> ValidationContext<'a> {
                         &mut self.errors,
                     );
                 }
+                // "/meta" is reserved for component metadata; only the framework may offer a
+                // directory rooted there.
+                if let Some(path) = o.source_path.as_ref() {
+                    if path.starts_with("/meta") && !matches!(o.source, Some(fsys::Ref::Framework(_)))
+                    {
+                        self.errors.push(Error::invalid_field(decl, "source_path"));
+                    }
+                }
+                if let Some(source_path) = o.source_path.as_ref() {
+                    self.check_capability_registry(source_path, CapabilityKind::Directory);
+                    self.check_api_level(source_path, CapabilityKind::Directory);
+                    self.check_denied_capability(decl, source_path);
+                }
             }
             fsys::OfferDecl::Storage(o) => {
+                self.count_offer_fan_out(o.source.as_ref(), o.source_name.as_deref());
                 self.validate_storage_offer_fields(
                     "OfferStorageDecl",
                     o.source_name.as_ref(),
@@ -1331,6 +2584,7 @@ impl<'a> ValidationContext<'a> {
             }
             fsys::OfferDecl::Runner(o) => {
                 let decl = "OfferRunnerDecl";
+                self.count_offer_fan_out(o.source.as_ref(), o.source_name.as_deref());
                 self.validate_offer_fields_with_name(
                     decl,
                     AllowableIds::One,
@@ -1349,6 +2603,7 @@ impl<'a> ValidationContext<'a> {
             }
             fsys::OfferDecl::Resolver(o) => {
                 let decl = "OfferResolverDecl";
+                self.count_offer_fan_out(o.source.as_ref(), o.source_name.as_deref());
                 self.validate_offer_fields_with_name(
                     decl,
                     AllowableIds::One,
@@ -1366,6 +2621,7 @@ impl<'a> ValidationContext<'a> {
                 self.add_strong_dep(o.source.as_ref(), o.target.as_ref());
             }
             fsys::OfferDecl::Event(e) => {
+                self.count_offer_fan_out(e.source.as_ref(), e.source_name.as_deref());
                 self.validate_event_offer_fields(e);
             }
             fsys::OfferDecl::__UnknownVariant { .. } => {
@@ -1567,6 +2823,10 @@ impl<'a> ValidationContext<'a> {
             return false;
         }
 
+        if field_name == "target" {
+            self.offered_to_children.insert(name);
+        }
+
         true
     }
 
@@ -1718,6 +2978,16 @@ impl<'a> ValidationContext<'a> {
         }
     }
 
+    /// Checking a storage offer's target against its source child warrants a more specific error
+    /// than the generic `Error::OfferTargetEqualsSource` other offer kinds use for this shape of
+    /// mistake: a storage capability backed by directory storage on child `c`, offered back into
+    /// `c`, isn't just a redundant self-offer -- it's a bootstrap cycle, since mounting `c`'s
+    /// storage depends on `c` having already started.
+    ///
+    /// This is necessarily limited to what's visible from this one `ComponentDecl`: it can only
+    /// catch the cycle when the storage is offered directly back to its backing child here, not
+    /// the more general case of `c` itself (from its own manifest, which this crate never sees)
+    /// using that same storage capability indirectly.
     fn validate_storage_target(
         &mut self,
         decl: &str,
@@ -1732,17 +3002,152 @@ impl<'a> ValidationContext<'a> {
                 let name = &c.name;
                 if let Some(source_name) = storage_source_name {
                     if self.all_storage_and_sources.get(source_name) == Some(&Some(name)) {
-                        self.errors.push(Error::offer_target_equals_source(decl, name));
+                        self.errors.push(Error::storage_backing_cycle(source_name));
                     }
                 }
             }
             Some(fsys::Ref::Collection(c)) => {
-                self.validate_collection_ref(decl, "target", &c);
+                if self.validate_collection_ref(decl, "target", &c) {
+                    self.validate_target_collection_durability(c, storage_source_name);
+                }
             }
             Some(_) => self.errors.push(Error::invalid_field(decl, "target")),
             None => self.errors.push(Error::missing_field(decl, "target")),
         }
     }
+
+    /// Flags every name shared by `all_children` and `all_collections`, since a shared name
+    /// makes `Ref::Child{name}` vs `Ref::Collection{name}` ambiguous.
+    fn check_child_collection_name_collisions(&mut self) {
+        let mut collisions: Vec<&str> = self
+            .all_children
+            .keys()
+            .filter(|name| self.all_collections.contains(*name))
+            .copied()
+            .collect();
+        collisions.sort_unstable();
+        for name in collisions {
+            self.errors.push(Error::name_collision_child_collection(name));
+        }
+    }
+
+    /// Warns about every `StorageDecl` in `all_storage_and_sources` that no offer or use ever
+    /// referenced, per `Error::UnusedStorage`.
+    fn check_unused_storage(&mut self) {
+        let mut unused_storage: Vec<&str> = self
+            .all_storage_and_sources
+            .keys()
+            .filter(|name| !self.referenced_storage.contains(*name))
+            .copied()
+            .collect();
+        unused_storage.sort_unstable();
+        for name in unused_storage {
+            self.errors.push(Error::unused_storage(name));
+        }
+    }
+
+    /// Warns about every `Eager` child in `all_children` that `offered_to_children` never saw as
+    /// an offer target, per `Error::EagerOrphanChild`. A truly standalone eager child (e.g. one
+    /// that only consumes capabilities from its parent) is a legitimate pattern, but an eager
+    /// child offered nothing at all is also a common sign of a leftover from a refactor, so this
+    /// is a warning rather than a hard error.
+    fn check_eager_orphan_children(&mut self) {
+        let mut orphans: Vec<&str> = self
+            .all_children
+            .iter()
+            .filter(|(name, child)| {
+                child.startup == Some(fsys::StartupMode::Eager)
+                    && !self.offered_to_children.contains(*name)
+            })
+            .map(|(name, _)| *name)
+            .collect();
+        orphans.sort_unstable();
+        for name in orphans {
+            self.errors.push(Error::eager_orphan_child(name));
+        }
+    }
+
+    /// Warns about every `(source, source_path)` offer grouping in `offer_fan_out` whose count
+    /// exceeds `ValidationOptions::max_fan_out`, per `Error::HighFanOut`.
+    fn check_offer_fan_out(&mut self) {
+        let max_fan_out = match self.options.max_fan_out {
+            Some(max_fan_out) => max_fan_out,
+            None => return,
+        };
+        let mut high_fan_out: Vec<(String, usize)> = self
+            .offer_fan_out
+            .iter()
+            .filter(|(_, count)| **count > max_fan_out)
+            .map(|(key, count)| (key.clone(), *count))
+            .collect();
+        high_fan_out.sort_unstable();
+        for (key, count) in high_fan_out {
+            self.errors.push(Error::high_fan_out(key, count));
+        }
+    }
+
+    /// Flags every child in `offers_per_child` whose offer count exceeds
+    /// `ValidationOptions::max_offers_per_child`, per `Error::ChildOverProvisioned`. A no-op when
+    /// that limit isn't configured.
+    fn check_offers_per_child(&mut self) {
+        let max_offers_per_child = match self.options.max_offers_per_child {
+            Some(max) => max,
+            None => return,
+        };
+        let mut over_provisioned: Vec<(&str, usize)> = self
+            .offers_per_child
+            .iter()
+            .filter(|(_, count)| **count > max_offers_per_child)
+            .map(|(child, count)| (*child, *count))
+            .collect();
+        over_provisioned.sort_unstable();
+        for (child, count) in over_provisioned {
+            self.errors.push(Error::child_over_provisioned(child, count, max_offers_per_child));
+        }
+    }
+
+    /// Validates the shape of the `fuchsia.test` entry of `facets`, if present: each value in its
+    /// `injected-services` dictionary must be a well-formed component URL string. Only called
+    /// when `ValidationOptions::validate_test_facets` is set, since `facets` is opaque,
+    /// product-specific metadata that component manager itself doesn't interpret.
+    fn validate_test_facets(&mut self, facets: &'a fsys::Object) {
+        let fuchsia_test = match find_object_entry(facets, "fuchsia.test") {
+            Some(fsys::Value::Obj(obj)) => obj,
+            _ => return,
+        };
+        let injected_services = match find_object_entry(fuchsia_test, "injected-services") {
+            Some(fsys::Value::Obj(obj)) => obj,
+            _ => return,
+        };
+        for entry in &injected_services.entries {
+            let url = match entry.value.as_deref() {
+                Some(fsys::Value::Str(url)) => Some(url),
+                _ => None,
+            };
+            if !check_url(url, "facets", "fuchsia.test.injected-services", &mut vec![]) {
+                self.errors.push(Error::invalid_field("facets", "fuchsia.test.injected-services"));
+            }
+        }
+    }
+
+    /// Warns if the collection's durability and the storage capability being offered into it are
+    /// considered mismatched by `ValidationOptions::collection_durability_mismatch`.
+    fn validate_target_collection_durability(
+        &mut self,
+        collection: &'a fsys::CollectionRef,
+        storage_source_name: Option<&'a str>,
+    ) {
+        let storage_source_name = match storage_source_name {
+            Some(name) => name,
+            None => return,
+        };
+        if let Some(durability) = self.collection_durability.get(collection.name.as_str()) {
+            if (self.options.collection_durability_mismatch)(*durability, storage_source_name) {
+                self.errors
+                    .push(Error::durability_mismatch(storage_source_name, &collection.name));
+            }
+        }
+    }
 }
 
 fn check_presence_and_length(
@@ -1847,6 +3252,20 @@ fn check_relative_path(
     start_err_len == errors.len()
 }
 
+/// Builds the `(source, source_path)` grouping key an offer contributes to for
+/// `ValidationOptions::max_fan_out`, based on its source ref and source name/path.
+fn fan_out_key(source: Option<&fsys::Ref>, capability: &str) -> String {
+    let source = match source {
+        Some(fsys::Ref::Parent(_)) => "parent".to_string(),
+        Some(fsys::Ref::Self_(_)) => "self".to_string(),
+        Some(fsys::Ref::Child(c)) => format!("child({})", c.name),
+        Some(fsys::Ref::Collection(c)) => format!("collection({})", c.name),
+        Some(fsys::Ref::Framework(_)) => "framework".to_string(),
+        Some(_) | None => "unknown".to_string(),
+    };
+    format!("{}:{}", source, capability)
+}
+
 fn check_name(
     prop: Option<&String>,
     decl_type: &str,
@@ -1868,6 +3287,12 @@ fn check_name(
     start_err_len == errors.len()
 }
 
+/// Returns the value of the entry keyed `key` in `obj`, the facets-as-untyped-JSON representation
+/// used by `fsys::ComponentDecl::facets`, or `None` if there is no such entry (or it's null).
+fn find_object_entry<'a>(obj: &'a fsys::Object, key: &str) -> Option<&'a fsys::Value> {
+    obj.entries.iter().find(|e| e.key == key).and_then(|e| e.value.as_deref())
+}
+
 // TODO: This should probably be checking with the `url` crate
 fn check_url(
     prop: Option<&String>,
@@ -1946,7 +3371,7 @@ fn check_url_scheme(
 #[cfg(test)]
 mod tests {
     use {
-        super::*, fidl_fuchsia_data as fdata, fidl_fuchsia_io2 as fio2, fidl_fuchsia_sys2::*,
+        super::*, fidl_fuchsia_data as fdata, fidl_fuchsia_sys2::*,
         lazy_static::lazy_static, proptest::prelude::*, regex::Regex,
     };
 
@@ -2018,51 +3443,1282 @@ mod tests {
         assert_eq!(res, expected_res);
     }
 
-    fn validate_test_any_result(input: ComponentDecl, expected_res: Vec<Result<(), ErrorList>>) {
-        let res = format!("{:?}", validate(&input));
-        let expected_res_debug = format!("{:?}", expected_res);
+    fn validate_test_any_result(input: ComponentDecl, expected_res: Vec<Result<(), ErrorList>>) {
+        let res = format!("{:?}", validate(&input));
+        let expected_res_debug = format!("{:?}", expected_res);
+
+        let matched_exp =
+            expected_res.into_iter().find(|expected| res == format!("{:?}", expected));
+
+        assert!(
+            matched_exp.is_some(),
+            "assertion failed: Expected one of:\n{:?}\nActual:\n{:?}",
+            expected_res_debug,
+            res
+        );
+    }
+
+    fn validate_capabilities_test(input: Vec<CapabilityDecl>, expected_res: Result<(), ErrorList>) {
+        let res = validate_capabilities(&input);
+        assert_eq!(res, expected_res);
+    }
+
+    fn check_test<F>(check_fn: F, input: &str, expected_res: Result<(), ErrorList>)
+    where
+        F: FnOnce(Option<&String>, &str, &str, &mut Vec<Error>) -> bool,
+    {
+        let mut errors = vec![];
+        let res: Result<(), ErrorList> =
+            match check_fn(Some(&input.to_string()), "FooDecl", "foo", &mut errors) {
+                true => Ok(()),
+                false => Err(ErrorList::new(errors)),
+            };
+        assert_eq!(format!("{:?}", res), format!("{:?}", expected_res));
+    }
+
+    fn new_component_decl() -> ComponentDecl {
+        ComponentDecl {
+            program: None,
+            uses: None,
+            exposes: None,
+            offers: None,
+            facets: None,
+            capabilities: None,
+            children: None,
+            collections: None,
+            environments: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_set_matches_serial_validation() {
+        let mut valid = new_component_decl();
+        valid.children = Some(vec![ChildDecl {
+            name: Some("logger".to_string()),
+            url: Some("fuchsia-pkg://fuchsia.com/logger#meta/logger.cm".to_string()),
+            startup: Some(StartupMode::Lazy),
+            environment: None,
+        }]);
+        let mut invalid = new_component_decl();
+        invalid.children = Some(vec![ChildDecl {
+            name: Some("logger".to_string()),
+            url: None,
+            startup: Some(StartupMode::Lazy),
+            environment: None,
+        }]);
+
+        let mut decls = HashMap::new();
+        decls.insert("fuchsia-pkg://fuchsia.com/valid#meta/valid.cm".to_string(), valid);
+        decls.insert("fuchsia-pkg://fuchsia.com/invalid#meta/invalid.cm".to_string(), invalid);
+
+        let results = validate_set(&decls);
+        assert_eq!(results.len(), decls.len());
+        for (url, decl) in &decls {
+            assert_eq!(results[url], validate(decl));
+        }
+
+        #[cfg(feature = "parallel_validation")]
+        {
+            let parallel_results = validate_set_parallel(&decls);
+            assert_eq!(parallel_results, results);
+        }
+    }
+
+    #[test]
+    fn test_validate_cross_decl_child_exposes() {
+        let mut parent = new_component_decl();
+        parent.children = Some(vec![ChildDecl {
+            name: Some("logger".to_string()),
+            url: Some("fuchsia-pkg://fuchsia.com/logger#meta/logger.cm".to_string()),
+            startup: Some(StartupMode::Lazy),
+            environment: None,
+        }]);
+        parent.offers = Some(vec![OfferDecl::Protocol(OfferProtocolDecl {
+            source: Some(Ref::Child(ChildRef { name: "logger".to_string(), collection: None })),
+            source_path: Some("/svc/fuchsia.logger.LogSink".to_string()),
+            target: Some(Ref::Child(ChildRef { name: "echo".to_string(), collection: None })),
+            target_path: Some("/svc/fuchsia.logger.LogSink".to_string()),
+            dependency_type: Some(DependencyType::Strong),
+        })]);
+
+        let mut exposing_logger = new_component_decl();
+        exposing_logger.exposes = Some(vec![ExposeDecl::Protocol(ExposeProtocolDecl {
+            source: Some(Ref::Self_(SelfRef {})),
+            source_path: Some("/svc/fuchsia.logger.LogSink".to_string()),
+            target_path: Some("/svc/fuchsia.logger.LogSink".to_string()),
+            target: Some(Ref::Parent(ParentRef {})),
+        })]);
+
+        let mut silent_logger = new_component_decl();
+        silent_logger.exposes = Some(vec![]);
+
+        let logger_url = "fuchsia-pkg://fuchsia.com/logger#meta/logger.cm".to_string();
+
+        // The child exposes the offered capability: no error.
+        let mut decls = HashMap::new();
+        decls.insert("fuchsia-pkg://fuchsia.com/parent#meta/parent.cm".to_string(), parent.clone());
+        decls.insert(logger_url.clone(), exposing_logger);
+        assert_eq!(validate_cross_decl_child_exposes(&decls), HashMap::new());
+
+        // The child doesn't expose it: an error, attributed to the parent's key.
+        let mut decls = HashMap::new();
+        let parent_key = "fuchsia-pkg://fuchsia.com/parent#meta/parent.cm".to_string();
+        decls.insert(parent_key.clone(), parent);
+        decls.insert(logger_url, silent_logger);
+        let results = validate_cross_decl_child_exposes(&decls);
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            results[&parent_key],
+            ErrorList::new(vec![Error::child_does_not_expose(
+                "logger",
+                "/svc/fuchsia.logger.LogSink"
+            )])
+        );
+    }
+
+    #[test]
+    fn test_error_list_sorted_is_permutation_independent() {
+        let a = vec![
+            Error::missing_field("ChildDecl", "name"),
+            Error::duplicate_field("UseProtocolDecl", "path", "/svc/foo"),
+            Error::invalid_child("OfferDecl", "source", "bar"),
+        ];
+        let b = vec![a[2].clone(), a[0].clone(), a[1].clone()];
+        assert_eq!(ErrorList::new(a).sorted(), ErrorList::new(b).sorted());
+    }
+
+    #[cfg(feature = "sarif")]
+    #[test]
+    fn test_error_list_to_sarif_has_one_result_per_error() {
+        let errs = vec![
+            Error::missing_field("ChildDecl", "name"),
+            Error::duplicate_field("UseProtocolDecl", "path", "/svc/foo"),
+            Error::invalid_child("OfferDecl", "source", "bar"),
+        ];
+        let expected_codes: Vec<&'static str> = errs.iter().map(|e| e.code()).collect();
+        let sarif = ErrorList::new(errs).to_sarif("meta/my_component.cm");
+
+        let doc: serde_json::Value = serde_json::from_str(&sarif).unwrap();
+        let results = doc["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), expected_codes.len());
+        let rule_ids: Vec<&str> =
+            results.iter().map(|r| r["ruleId"].as_str().unwrap()).collect();
+        assert_eq!(rule_ids, expected_codes);
+    }
+
+    #[test]
+    fn test_validate_exposes() {
+        let mut children = HashSet::new();
+        children.insert("logger".to_string());
+
+        let valid_exposes = vec![
+            ExposeDecl::Directory(ExposeDirectoryDecl {
+                source: Some(Ref::Child(ChildRef { name: "logger".to_string(), collection: None })),
+                source_path: Some("/data/logs".to_string()),
+                target_path: Some("/logs".to_string()),
+                target: Some(Ref::Parent(ParentRef {})),
+                rights: Some(fio2::Operations::Connect),
+                subdir: None,
+            }),
+            ExposeDecl::Directory(ExposeDirectoryDecl {
+                source: Some(Ref::Child(ChildRef { name: "logger".to_string(), collection: None })),
+                source_path: Some("/data/config".to_string()),
+                target_path: Some("/config".to_string()),
+                target: Some(Ref::Parent(ParentRef {})),
+                rights: Some(fio2::Operations::Connect),
+                subdir: None,
+            }),
+        ];
+        assert_eq!(validate_exposes(&valid_exposes, &children), Ok(()));
+
+        // Two exposes that collide on target_path are rejected.
+        let duplicate_target_exposes = vec![
+            ExposeDecl::Directory(ExposeDirectoryDecl {
+                source: Some(Ref::Child(ChildRef { name: "logger".to_string(), collection: None })),
+                source_path: Some("/data/logs".to_string()),
+                target_path: Some("/logs".to_string()),
+                target: Some(Ref::Parent(ParentRef {})),
+                rights: Some(fio2::Operations::Connect),
+                subdir: None,
+            }),
+            ExposeDecl::Directory(ExposeDirectoryDecl {
+                source: Some(Ref::Child(ChildRef { name: "logger".to_string(), collection: None })),
+                source_path: Some("/data/other_logs".to_string()),
+                target_path: Some("/logs".to_string()),
+                target: Some(Ref::Parent(ParentRef {})),
+                rights: Some(fio2::Operations::Connect),
+                subdir: None,
+            }),
+        ];
+        assert_eq!(
+            validate_exposes(&duplicate_target_exposes, &children),
+            Err(ErrorList::new(vec![Error::duplicate_field(
+                "ExposeDirectoryDecl",
+                "target_path",
+                "/logs"
+            )]))
+        );
+
+        // An expose sourced from an unknown child is rejected.
+        let invalid_child_exposes = vec![ExposeDecl::Directory(ExposeDirectoryDecl {
+            source: Some(Ref::Child(ChildRef { name: "unknown".to_string(), collection: None })),
+            source_path: Some("/data/logs".to_string()),
+            target_path: Some("/logs".to_string()),
+            target: Some(Ref::Parent(ParentRef {})),
+            rights: Some(fio2::Operations::Connect),
+            subdir: None,
+        })];
+        assert_eq!(
+            validate_exposes(&invalid_child_exposes, &children),
+            Err(ErrorList::new(vec![Error::invalid_child(
+                "ExposeDirectoryDecl",
+                "source",
+                "unknown"
+            )]))
+        );
+    }
+
+    #[test]
+    fn test_validate_added_offer() {
+        let mut decl = new_component_decl();
+        decl.children = Some(vec![ChildDecl {
+            name: Some("logger".to_string()),
+            url: Some("fuchsia-pkg://fuchsia.com/logger#meta/logger.cm".to_string()),
+            startup: Some(StartupMode::Lazy),
+            environment: None,
+        }]);
+        decl.offers = Some(vec![OfferDecl::Directory(OfferDirectoryDecl {
+            source: Some(Ref::Parent(ParentRef {})),
+            source_path: Some("/data/logs".to_string()),
+            target: Some(Ref::Child(ChildRef { name: "logger".to_string(), collection: None })),
+            target_path: Some("/logs".to_string()),
+            rights: Some(fio2::Operations::Connect),
+            subdir: None,
+            dependency_type: Some(DependencyType::Strong),
+        })]);
+
+        // A new offer to a distinct target path on the same child is valid.
+        let valid_offer = OfferDecl::Directory(OfferDirectoryDecl {
+            source: Some(Ref::Parent(ParentRef {})),
+            source_path: Some("/data/config".to_string()),
+            target: Some(Ref::Child(ChildRef { name: "logger".to_string(), collection: None })),
+            target_path: Some("/config".to_string()),
+            rights: Some(fio2::Operations::Connect),
+            subdir: None,
+            dependency_type: Some(DependencyType::Strong),
+        });
+        assert_eq!(validate_added_offer(&decl, &valid_offer), Ok(()));
+
+        // A new offer that reuses the existing offer's target path conflicts.
+        let conflicting_offer = OfferDecl::Directory(OfferDirectoryDecl {
+            source: Some(Ref::Parent(ParentRef {})),
+            source_path: Some("/data/other_logs".to_string()),
+            target: Some(Ref::Child(ChildRef { name: "logger".to_string(), collection: None })),
+            target_path: Some("/logs".to_string()),
+            rights: Some(fio2::Operations::Connect),
+            subdir: None,
+            dependency_type: Some(DependencyType::Strong),
+        });
+        assert_eq!(
+            validate_added_offer(&decl, &conflicting_offer),
+            Err(ErrorList::new(vec![Error::duplicate_field(
+                "OfferDirectoryDecl",
+                "target_path",
+                "/logs"
+            )]))
+        );
+    }
+
+    #[test]
+    fn test_program_without_runner_is_a_warning_not_a_failure() {
+        let mut decl = new_component_decl();
+        decl.program = Some(fdata::Dictionary { entries: None });
+        assert_eq!(validate(&decl), Ok(()));
+
+        decl.uses = Some(vec![UseDecl::Runner(UseRunnerDecl {
+            source_name: Some("elf".to_string()),
+        })]);
+        assert_eq!(validate(&decl), Ok(()));
+    }
+
+    #[test]
+    fn test_self_directory_offer_unverifiable_is_a_warning() {
+        let child = ChildDecl {
+            name: Some("logger".to_string()),
+            url: Some("fuchsia-pkg://fuchsia.com/logger#meta/logger.cm".to_string()),
+            startup: Some(StartupMode::Lazy),
+            environment: None,
+        };
+        let offer = OfferDecl::Directory(OfferDirectoryDecl {
+            source: Some(Ref::Self_(SelfRef {})),
+            source_path: Some("/data/logs".to_string()),
+            target: Some(Ref::Child(ChildRef { name: "logger".to_string(), collection: None })),
+            target_path: Some("/logs".to_string()),
+            rights: Some(fio2::Operations::Connect),
+            subdir: None,
+            dependency_type: Some(DependencyType::Strong),
+        });
+
+        // No matching self expose of the same path: flagged, but only as a non-fatal warning.
+        let mut ctx = ValidationContext::default();
+        ctx.all_children.insert("logger", &child);
+        ctx.validate_offers_decl(&offer);
+        assert_eq!(
+            ctx.errors,
+            vec![Error::self_offer_unverifiable(
+                "OfferDirectoryDecl",
+                "source_path",
+                "/data/logs"
+            )]
+        );
+        assert_eq!(ctx.errors[0].severity(), Severity::Warning);
+
+        // A matching self expose of the same path makes the offer verifiable.
+        let mut ctx = ValidationContext::default();
+        ctx.all_children.insert("logger", &child);
+        ctx.self_exposed_dir_paths.insert("/data/logs");
+        ctx.validate_offers_decl(&offer);
+        assert_eq!(ctx.errors, vec![]);
+    }
+
+    #[test]
+    fn test_collection_durability_mismatch_is_a_warning() {
+        // A product policy that flags "cache" storage being offered into persistent collections.
+        let options = ValidationOptions {
+            collection_durability_mismatch: Box::new(|durability, storage_name| {
+                *durability == Durability::Persistent && storage_name == "cache"
+            }),
+            ..ValidationOptions::default()
+        };
+        let offer = OfferDecl::Storage(OfferStorageDecl {
+            source_name: Some("cache".to_string()),
+            source: Some(Ref::Parent(ParentRef {})),
+            target: Some(Ref::Collection(CollectionRef {
+                name: "persistent_collection".to_string(),
+            })),
+            target_name: Some("cache".to_string()),
+        });
+
+        // Mismatch: "cache" storage offered into a persistent collection is flagged, but only as
+        // a non-fatal warning.
+        let persistent = Durability::Persistent;
+        let mut ctx = ValidationContext { options, ..ValidationContext::default() };
+        ctx.all_collections.insert("persistent_collection");
+        ctx.collection_durability.insert("persistent_collection", &persistent);
+        ctx.validate_offers_decl(&offer);
+        assert_eq!(
+            ctx.errors,
+            vec![Error::durability_mismatch("cache", "persistent_collection")]
+        );
+        assert_eq!(ctx.errors[0].severity(), Severity::Warning);
+
+        // Match: the same policy doesn't flag a transient collection.
+        let options = ValidationOptions {
+            collection_durability_mismatch: Box::new(|durability, storage_name| {
+                *durability == Durability::Persistent && storage_name == "cache"
+            }),
+            ..ValidationOptions::default()
+        };
+        let transient = Durability::Transient;
+        let offer = OfferDecl::Storage(OfferStorageDecl {
+            source_name: Some("cache".to_string()),
+            source: Some(Ref::Parent(ParentRef {})),
+            target: Some(Ref::Collection(CollectionRef {
+                name: "transient_collection".to_string(),
+            })),
+            target_name: Some("cache".to_string()),
+        });
+        let mut ctx = ValidationContext { options, ..ValidationContext::default() };
+        ctx.all_collections.insert("transient_collection");
+        ctx.collection_durability.insert("transient_collection", &transient);
+        ctx.validate_offers_decl(&offer);
+        assert_eq!(ctx.errors, vec![]);
+    }
+
+    #[test]
+    fn test_max_children_collections_storage() {
+        let child = |name: &str| ChildDecl {
+            name: Some(name.to_string()),
+            url: Some("fuchsia-pkg://fuchsia.com/foo#meta/foo.cm".to_string()),
+            startup: Some(StartupMode::Lazy),
+            environment: None,
+        };
+        let collection = |name: &str| CollectionDecl {
+            name: Some(name.to_string()),
+            durability: Some(Durability::Transient),
+            environment: None,
+        };
+        let storage = |name: &str| {
+            CapabilityDecl::Storage(StorageDecl {
+                name: Some(name.to_string()),
+                source: Some(Ref::Parent(ParentRef {})),
+                source_path: Some("/data".to_string()),
+                subdir: None,
+            })
+        };
+
+        // At the limit: no error.
+        let mut decl = new_component_decl();
+        decl.children = Some(vec![child("a"), child("b")]);
+        let options = ValidationOptions { max_children: Some(2), ..ValidationOptions::default() };
+        assert_eq!(validate_with_options(&decl, options), Ok(()));
+
+        // Over the limit: a hard error.
+        let mut decl = new_component_decl();
+        decl.children = Some(vec![child("a"), child("b"), child("c")]);
+        let options = ValidationOptions { max_children: Some(2), ..ValidationOptions::default() };
+        assert_eq!(
+            validate_with_options(&decl, options),
+            Err(ErrorList::new(vec![Error::too_many_children(3, 2)]))
+        );
+
+        // At the limit: no error.
+        let mut decl = new_component_decl();
+        decl.collections = Some(vec![collection("a"), collection("b")]);
+        let options = ValidationOptions { max_collections: Some(2), ..ValidationOptions::default() };
+        assert_eq!(validate_with_options(&decl, options), Ok(()));
+
+        // Over the limit: a hard error.
+        let mut decl = new_component_decl();
+        decl.collections = Some(vec![collection("a"), collection("b"), collection("c")]);
+        let options = ValidationOptions { max_collections: Some(2), ..ValidationOptions::default() };
+        assert_eq!(
+            validate_with_options(&decl, options),
+            Err(ErrorList::new(vec![Error::too_many_collections(3, 2)]))
+        );
+
+        // At the limit: no error.
+        let mut decl = new_component_decl();
+        decl.capabilities = Some(vec![storage("a"), storage("b")]);
+        let options = ValidationOptions { max_storage: Some(2), ..ValidationOptions::default() };
+        assert_eq!(validate_with_options(&decl, options), Ok(()));
+
+        // Over the limit: a hard error. The failing result also carries the unused-storage
+        // warnings for the three storage capabilities, which nothing offers or uses.
+        let mut decl = new_component_decl();
+        decl.capabilities = Some(vec![storage("a"), storage("b"), storage("c")]);
+        let options = ValidationOptions { max_storage: Some(2), ..ValidationOptions::default() };
+        assert_eq!(
+            validate_with_options(&decl, options),
+            Err(ErrorList::new(vec![
+                Error::too_many_storage(3, 2),
+                Error::unused_storage("a"),
+                Error::unused_storage("b"),
+                Error::unused_storage("c"),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_offer_fan_out_is_a_warning() {
+        let protocol_offer = |target_name: &str| {
+            OfferDecl::Protocol(OfferProtocolDecl {
+                source: Some(Ref::Parent(ParentRef {})),
+                source_path: Some("/svc/fuchsia.logger.LogSink".to_string()),
+                target: Some(Ref::Child(ChildRef {
+                    name: target_name.to_string(),
+                    collection: None,
+                })),
+                target_path: Some("/svc/fuchsia.logger.LogSink".to_string()),
+                dependency_type: Some(DependencyType::Strong),
+            })
+        };
+        let child = |name: &str| ChildDecl {
+            name: Some(name.to_string()),
+            url: Some("fuchsia-pkg://fuchsia.com/foo#meta/foo.cm".to_string()),
+            startup: Some(StartupMode::Lazy),
+            environment: None,
+        };
+        let children = [child("a"), child("b"), child("c")];
+        let offers = [protocol_offer("a"), protocol_offer("b"), protocol_offer("c")];
+
+        // Over the limit: a warning, not a hard error.
+        let options = ValidationOptions { max_fan_out: Some(2), ..ValidationOptions::default() };
+        let mut ctx = ValidationContext { options, ..ValidationContext::default() };
+        for c in children.iter() {
+            ctx.validate_child_decl(c);
+        }
+        for offer in offers.iter() {
+            ctx.validate_offers_decl(offer);
+        }
+        ctx.check_offer_fan_out();
+        assert_eq!(
+            ctx.errors,
+            vec![Error::high_fan_out("parent:/svc/fuchsia.logger.LogSink", 3)]
+        );
+        assert_eq!(ctx.errors[0].severity(), Severity::Warning);
+
+        // At the limit: no warning.
+        let options = ValidationOptions { max_fan_out: Some(3), ..ValidationOptions::default() };
+        let mut ctx = ValidationContext { options, ..ValidationContext::default() };
+        for c in children.iter() {
+            ctx.validate_child_decl(c);
+        }
+        for offer in offers.iter() {
+            ctx.validate_offers_decl(offer);
+        }
+        ctx.check_offer_fan_out();
+        assert_eq!(ctx.errors, vec![]);
+    }
+
+    #[test]
+    fn test_max_offers_per_child() {
+        let protocol_offer = |target_path: &str| {
+            OfferDecl::Protocol(OfferProtocolDecl {
+                source: Some(Ref::Parent(ParentRef {})),
+                source_path: Some(target_path.to_string()),
+                target: Some(Ref::Child(ChildRef {
+                    name: "logger".to_string(),
+                    collection: None,
+                })),
+                target_path: Some(target_path.to_string()),
+                dependency_type: Some(DependencyType::Strong),
+            })
+        };
+        let child = ChildDecl {
+            name: Some("logger".to_string()),
+            url: Some("fuchsia-pkg://fuchsia.com/logger#meta/logger.cm".to_string()),
+            startup: Some(StartupMode::Lazy),
+            environment: None,
+        };
+        let offers = [protocol_offer("/svc/a"), protocol_offer("/svc/b"), protocol_offer("/svc/c")];
+
+        // Over the limit: a hard error.
+        let options = ValidationOptions { max_offers_per_child: Some(2), ..ValidationOptions::default() };
+        let mut ctx = ValidationContext { options, ..ValidationContext::default() };
+        ctx.validate_child_decl(&child);
+        for offer in offers.iter() {
+            ctx.validate_offers_decl(offer);
+        }
+        ctx.check_offers_per_child();
+        assert_eq!(ctx.errors, vec![Error::child_over_provisioned("logger", 3, 2)]);
+
+        // At the limit: no error.
+        let options = ValidationOptions { max_offers_per_child: Some(3), ..ValidationOptions::default() };
+        let mut ctx = ValidationContext { options, ..ValidationContext::default() };
+        ctx.validate_child_decl(&child);
+        for offer in offers.iter() {
+            ctx.validate_offers_decl(offer);
+        }
+        ctx.check_offers_per_child();
+        assert_eq!(ctx.errors, vec![]);
+    }
+
+    #[test]
+    fn test_child_collection_name_collision_is_an_error() {
+        let mut decl = new_component_decl();
+        decl.children = Some(vec![ChildDecl {
+            name: Some("foo".to_string()),
+            url: Some("fuchsia-pkg://fuchsia.com/foo#meta/foo.cm".to_string()),
+            startup: Some(StartupMode::Lazy),
+            environment: None,
+        }]);
+        decl.collections = Some(vec![CollectionDecl {
+            name: Some("foo".to_string()),
+            durability: Some(Durability::Transient),
+            environment: None,
+        }]);
+        assert_eq!(
+            validate(&decl),
+            Err(ErrorList::new(vec![Error::name_collision_child_collection("foo")]))
+        );
+    }
+
+    #[test]
+    fn test_validate_added_child() {
+        let mut decl = new_component_decl();
+        decl.children = Some(vec![ChildDecl {
+            name: Some("logger".to_string()),
+            url: Some("fuchsia-pkg://fuchsia.com/logger#meta/logger.cm".to_string()),
+            startup: Some(StartupMode::Lazy),
+            environment: None,
+        }]);
+        decl.collections = Some(vec![CollectionDecl {
+            name: Some("coll".to_string()),
+            durability: Some(Durability::Transient),
+            environment: None,
+        }]);
+
+        // Colliding with an existing child name.
+        let colliding_child = ChildDecl {
+            name: Some("logger".to_string()),
+            url: Some("fuchsia-pkg://fuchsia.com/logger2#meta/logger.cm".to_string()),
+            startup: Some(StartupMode::Lazy),
+            environment: None,
+        };
+        assert_eq!(
+            validate_added_child(&decl, &colliding_child),
+            Err(ErrorList::new(vec![Error::duplicate_field("ChildDecl", "name", "logger")]))
+        );
+
+        // Colliding with an existing collection name.
+        let collection_colliding_child = ChildDecl {
+            name: Some("coll".to_string()),
+            url: Some("fuchsia-pkg://fuchsia.com/coll#meta/coll.cm".to_string()),
+            startup: Some(StartupMode::Lazy),
+            environment: None,
+        };
+        assert_eq!(
+            validate_added_child(&decl, &collection_colliding_child),
+            Err(ErrorList::new(vec![Error::name_collision_child_collection("coll")]))
+        );
+
+        // No collision.
+        let new_child = ChildDecl {
+            name: Some("netstack".to_string()),
+            url: Some("fuchsia-pkg://fuchsia.com/netstack#meta/netstack.cm".to_string()),
+            startup: Some(StartupMode::Lazy),
+            environment: None,
+        };
+        assert_eq!(validate_added_child(&decl, &new_child), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_source_child_in_collection() {
+        let mut decl = new_component_decl();
+        decl.children = Some(vec![ChildDecl {
+            name: Some("logger".to_string()),
+            url: Some("fuchsia-pkg://fuchsia.com/logger#meta/logger.cm".to_string()),
+            startup: Some(StartupMode::Lazy),
+            environment: None,
+        }]);
+        decl.collections = Some(vec![CollectionDecl {
+            name: Some("modular".to_string()),
+            durability: Some(Durability::Transient),
+            environment: None,
+        }]);
+        decl.exposes = Some(vec![ExposeDecl::Protocol(ExposeProtocolDecl {
+            source: Some(Ref::Child(ChildRef {
+                name: "logger".to_string(),
+                collection: Some("modular".to_string()),
+            })),
+            source_path: Some("/svc/legacy_logger".to_string()),
+            target_path: Some("/svc/legacy_logger".to_string()),
+            target: Some(Ref::Parent(ParentRef {})),
+        })]);
+
+        // Naming a real collection in `source.child.collection` is distinguished from the
+        // generic extraneous-field error, since it specifically means the ref is trying to name
+        // a dynamic instance, which a source child ref can never do.
+        assert_eq!(
+            validate(&decl),
+            Err(ErrorList::new(vec![Error::source_child_in_collection(
+                "ExposeProtocolDecl",
+                "source.child.collection",
+                "logger",
+                "modular",
+            )]))
+        );
+    }
+
+    #[test]
+    fn test_unused_storage_is_a_warning() {
+        let used = CapabilityDecl::Storage(StorageDecl {
+            name: Some("used".to_string()),
+            source: Some(Ref::Self_(SelfRef {})),
+            source_path: Some("/data/used".to_string()),
+            subdir: None,
+        });
+        let orphan = CapabilityDecl::Storage(StorageDecl {
+            name: Some("orphan".to_string()),
+            source: Some(Ref::Self_(SelfRef {})),
+            source_path: Some("/data/orphan".to_string()),
+            subdir: None,
+        });
+        let child = ChildDecl {
+            name: Some("logger".to_string()),
+            url: Some("fuchsia-pkg://fuchsia.com/logger#meta/logger.cm".to_string()),
+            startup: Some(StartupMode::Lazy),
+            environment: None,
+        };
+        let offer = OfferDecl::Storage(OfferStorageDecl {
+            source_name: Some("used".to_string()),
+            source: Some(Ref::Self_(SelfRef {})),
+            target: Some(Ref::Child(ChildRef { name: "logger".to_string(), collection: None })),
+            target_name: Some("used".to_string()),
+        });
+
+        let mut ctx = ValidationContext::default();
+        ctx.validate_capability_decl(&used);
+        ctx.validate_capability_decl(&orphan);
+        ctx.validate_child_decl(&child);
+        ctx.validate_offers_decl(&offer);
+        ctx.check_unused_storage();
+        assert_eq!(ctx.errors, vec![Error::unused_storage("orphan")]);
+        assert_eq!(ctx.errors[0].severity(), Severity::Warning);
+    }
+
+    #[test]
+    fn test_eager_orphan_child_is_a_warning() {
+        let orphan = ChildDecl {
+            name: Some("orphan".to_string()),
+            url: Some("fuchsia-pkg://fuchsia.com/orphan#meta/orphan.cm".to_string()),
+            startup: Some(StartupMode::Eager),
+            environment: None,
+        };
+        let served = ChildDecl {
+            name: Some("served".to_string()),
+            url: Some("fuchsia-pkg://fuchsia.com/served#meta/served.cm".to_string()),
+            startup: Some(StartupMode::Eager),
+            environment: None,
+        };
+        let lazy_orphan = ChildDecl {
+            name: Some("lazy_orphan".to_string()),
+            url: Some("fuchsia-pkg://fuchsia.com/lazy_orphan#meta/lazy_orphan.cm".to_string()),
+            startup: Some(StartupMode::Lazy),
+            environment: None,
+        };
+        let offer = OfferDecl::Protocol(OfferProtocolDecl {
+            source: Some(Ref::Parent(ParentRef {})),
+            source_path: Some("/svc/fuchsia.logger.LogSink".to_string()),
+            target: Some(Ref::Child(ChildRef { name: "served".to_string(), collection: None })),
+            target_path: Some("/svc/fuchsia.logger.LogSink".to_string()),
+            dependency_type: Some(DependencyType::Strong),
+        });
+
+        let mut ctx = ValidationContext::default();
+        ctx.validate_child_decl(&orphan);
+        ctx.validate_child_decl(&served);
+        ctx.validate_child_decl(&lazy_orphan);
+        ctx.validate_offers_decl(&offer);
+        ctx.check_eager_orphan_children();
+        assert_eq!(ctx.errors, vec![Error::eager_orphan_child("orphan")]);
+        assert_eq!(ctx.errors[0].severity(), Severity::Warning);
+    }
+
+    #[test]
+    fn test_deny_warnings() {
+        let mut decl = new_component_decl();
+        decl.capabilities = Some(vec![CapabilityDecl::Storage(StorageDecl {
+            name: Some("unused".to_string()),
+            source: Some(Ref::Self_(SelfRef {})),
+            source_path: Some("/data/unused".to_string()),
+            subdir: None,
+        })]);
+
+        // By default, a warning-only decl passes.
+        assert_eq!(validate(&decl), Ok(()));
+
+        // With `deny_warnings`, the same decl fails, and the returned list carries the warning.
+        let options = ValidationOptions { deny_warnings: true, ..ValidationOptions::default() };
+        assert_eq!(
+            validate_with_options(&decl, options),
+            Err(ErrorList::new(vec![Error::unused_storage("unused")]))
+        );
+    }
+
+    #[test]
+    fn test_reserved_path_prefix() {
+        let mut decl = new_component_decl();
+        decl.uses = Some(vec![
+            UseDecl::Protocol(UseProtocolDecl {
+                source: Some(Ref::Parent(ParentRef {})),
+                source_path: Some("/svc/fuchsia.component.Reserved".to_string()),
+                target_path: Some("/svc/fuchsia.component.Reserved".to_string()),
+                dependency_type: Some(DependencyType::Strong),
+            }),
+            UseDecl::Protocol(UseProtocolDecl {
+                source: Some(Ref::Parent(ParentRef {})),
+                source_path: Some("/svc/fuchsia.logger.LogSink".to_string()),
+                target_path: Some("/svc/fuchsia.logger.LogSink".to_string()),
+                dependency_type: Some(DependencyType::Strong),
+            }),
+        ]);
+
+        // By default, there are no reserved prefixes, so both uses pass.
+        assert_eq!(validate(&decl), Ok(()));
+
+        // With a reserved prefix configured, only the use whose path starts with it fails; the
+        // other, allowed path is unaffected.
+        let options = ValidationOptions {
+            reserved_path_prefixes: vec!["/svc/fuchsia.component.".to_string()],
+            ..ValidationOptions::default()
+        };
+        assert_eq!(
+            validate_with_options(&decl, options),
+            Err(ErrorList::new(vec![Error::reserved_path_prefix(
+                "/svc/fuchsia.component.Reserved",
+                "/svc/fuchsia.component."
+            )]))
+        );
+    }
+
+    #[test]
+    fn test_use_and_self_expose() {
+        let mut decl = new_component_decl();
+        decl.uses = Some(vec![UseDecl::Protocol(UseProtocolDecl {
+            source: Some(Ref::Parent(ParentRef {})),
+            source_path: Some("/svc/fuchsia.logger.LogSink".to_string()),
+            target_path: Some("/svc/foo".to_string()),
+            dependency_type: Some(DependencyType::Strong),
+        })]);
+        decl.exposes = Some(vec![ExposeDecl::Protocol(ExposeProtocolDecl {
+            source: Some(Ref::Self_(SelfRef {})),
+            source_path: Some("/svc/foo".to_string()),
+            target_path: Some("/svc/foo".to_string()),
+            target: Some(Ref::Parent(ParentRef {})),
+        })]);
+
+        // `/svc/foo` is both used from the realm and re-exposed from self at the same path, but
+        // that's only a non-fatal warning, so by default the decl still passes.
+        assert_eq!(validate(&decl), Ok(()));
+
+        // With `deny_warnings`, the same decl fails, and the returned list carries the warning.
+        let options = ValidationOptions { deny_warnings: true, ..ValidationOptions::default() };
+        assert_eq!(
+            validate_with_options(&decl, options),
+            Err(ErrorList::new(vec![Error::use_and_self_expose("/svc/foo")]))
+        );
+    }
+
+    #[test]
+    fn test_use_and_self_expose_clean() {
+        let mut decl = new_component_decl();
+        decl.uses = Some(vec![UseDecl::Protocol(UseProtocolDecl {
+            source: Some(Ref::Parent(ParentRef {})),
+            source_path: Some("/svc/fuchsia.logger.LogSink".to_string()),
+            target_path: Some("/svc/foo".to_string()),
+            dependency_type: Some(DependencyType::Strong),
+        })]);
+        decl.exposes = Some(vec![ExposeDecl::Protocol(ExposeProtocolDecl {
+            source: Some(Ref::Self_(SelfRef {})),
+            source_path: Some("/svc/bar".to_string()),
+            target_path: Some("/svc/bar".to_string()),
+            target: Some(Ref::Parent(ParentRef {})),
+        })]);
+
+        // Different paths in the use and the self-sourced expose: no conflict.
+        assert_eq!(validate(&decl), Ok(()));
+    }
+
+    #[test]
+    fn test_capability_registry_mismatch() {
+        let mut decl = new_component_decl();
+        decl.uses = Some(vec![
+            UseDecl::Protocol(UseProtocolDecl {
+                source: Some(Ref::Parent(ParentRef {})),
+                source_path: Some("/svc/fuchsia.logger.LogSink".to_string()),
+                target_path: Some("/svc/fuchsia.logger.LogSink".to_string()),
+                dependency_type: Some(DependencyType::Strong),
+            }),
+            UseDecl::Directory(UseDirectoryDecl {
+                source: Some(Ref::Parent(ParentRef {})),
+                source_path: Some("/svc/fuchsia.logger.LogSink".to_string()),
+                target_path: Some("/data/misused".to_string()),
+                rights: Some(fio2::Operations::Connect),
+                subdir: None,
+            }),
+        ]);
+
+        // By default, there's no registry, so a path being used as the "wrong" kind is
+        // undetectable and both uses pass.
+        assert_eq!(validate(&decl), Ok(()));
+
+        // With the path registered as a protocol, the matching use is unaffected, but using the
+        // same path as a directory is flagged.
+        let mut capability_registry = HashMap::new();
+        capability_registry
+            .insert("/svc/fuchsia.logger.LogSink".to_string(), CapabilityKind::Protocol);
+        let options = ValidationOptions {
+            capability_registry: Some(capability_registry),
+            ..ValidationOptions::default()
+        };
+        assert_eq!(
+            validate_with_options(&decl, options),
+            Err(ErrorList::new(vec![Error::capability_kind_mismatch(
+                "/svc/fuchsia.logger.LogSink",
+                CapabilityKind::Protocol,
+                CapabilityKind::Directory,
+            )]))
+        );
+    }
+
+    #[test]
+    fn test_denied_capability_paths() {
+        let mut decl = new_component_decl();
+        decl.children = Some(vec![ChildDecl {
+            name: Some("logger".to_string()),
+            url: Some("fuchsia-pkg://fuchsia.com/pkg#meta/logger.cm".to_string()),
+            startup: Some(StartupMode::Lazy),
+            environment: None,
+        }]);
+        decl.uses = Some(vec![UseDecl::Protocol(UseProtocolDecl {
+            source: Some(Ref::Parent(ParentRef {})),
+            source_path: Some("/svc/denied.Use".to_string()),
+            target_path: Some("/svc/denied.Use".to_string()),
+            dependency_type: Some(DependencyType::Strong),
+        })]);
+        decl.exposes = Some(vec![ExposeDecl::Protocol(ExposeProtocolDecl {
+            source: Some(Ref::Self_(SelfRef {})),
+            source_path: Some("/svc/denied.Expose".to_string()),
+            target_path: Some("/svc/denied.Expose".to_string()),
+            target: Some(Ref::Parent(ParentRef {})),
+        })]);
+        decl.offers = Some(vec![OfferDecl::Protocol(OfferProtocolDecl {
+            source: Some(Ref::Parent(ParentRef {})),
+            source_path: Some("/svc/denied.Offer".to_string()),
+            target: Some(Ref::Child(ChildRef { name: "logger".to_string(), collection: None })),
+            target_path: Some("/svc/denied.Offer".to_string()),
+            dependency_type: Some(DependencyType::Strong),
+        })]);
+
+        // By default, there's no deny-list, so all three pass.
+        assert_eq!(validate(&decl), Ok(()));
+
+        let options = ValidationOptions {
+            denied_capability_paths: vec![
+                "/svc/denied.Use".to_string(),
+                "/svc/denied.Expose".to_string(),
+                "/svc/denied.Offer".to_string(),
+            ]
+            .into_iter()
+            .collect(),
+            ..ValidationOptions::default()
+        };
+        assert_eq!(
+            validate_with_options(&decl, options),
+            Err(ErrorList::new(vec![
+                Error::denied_capability("UseProtocolDecl", "/svc/denied.Use"),
+                Error::denied_capability("ExposeProtocolDecl", "/svc/denied.Expose"),
+                Error::denied_capability("OfferProtocolDecl", "/svc/denied.Offer"),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_max_path_depth() {
+        let mut decl = new_component_decl();
+        decl.uses = Some(vec![UseDecl::Protocol(UseProtocolDecl {
+            source: Some(Ref::Parent(ParentRef {})),
+            source_path: Some("/svc/fuchsia.logger.LogSink".to_string()),
+            target_path: Some("/data/a/b/c".to_string()),
+            dependency_type: Some(DependencyType::Strong),
+        })]);
+
+        // Without a limit configured, there's nothing to gate against.
+        assert_eq!(validate(&decl), Ok(()));
+
+        // "/data/a/b/c" has depth 4, which exceeds a limit of 3.
+        let options = ValidationOptions { max_path_depth: Some(3), ..ValidationOptions::default() };
+        assert_eq!(
+            validate_with_options(&decl, options),
+            Err(ErrorList::new(vec![Error::path_too_deep("/data/a/b/c", 4, 3)]))
+        );
+
+        // The same path is allowed at or above its own depth.
+        let options = ValidationOptions { max_path_depth: Some(4), ..ValidationOptions::default() };
+        assert_eq!(validate_with_options(&decl, options), Ok(()));
+    }
+
+    #[test]
+    fn test_warn_nonconventional_service_paths() {
+        let use_service = |target_path: &str| {
+            UseDecl::Service(UseServiceDecl {
+                source: Some(Ref::Parent(ParentRef {})),
+                source_name: Some("fuchsia.logger.Log".to_string()),
+                target_path: Some(target_path.to_string()),
+            })
+        };
+
+        // Disabled by default, even for an unconventional path.
+        let mut decl = new_component_decl();
+        decl.uses = Some(vec![use_service("/foo/bar")]);
+        assert_eq!(validate(&decl), Ok(()));
+
+        // Enabled, a path under "/svc" is clean.
+        let mut decl = new_component_decl();
+        decl.uses = Some(vec![use_service("/svc/fuchsia.logger.Log")]);
+        let options = ValidationOptions {
+            warn_nonconventional_service_paths: true,
+            ..ValidationOptions::default()
+        };
+        assert_eq!(validate_with_options(&decl, options), Ok(()));
+
+        // Enabled, a path outside "/svc" is flagged, but that's only a non-fatal warning, so the
+        // decl still passes.
+        let mut decl = new_component_decl();
+        decl.uses = Some(vec![use_service("/foo/bar")]);
+        let options = ValidationOptions {
+            warn_nonconventional_service_paths: true,
+            ..ValidationOptions::default()
+        };
+        assert_eq!(validate_with_options(&decl, options), Ok(()));
+
+        // With `deny_warnings` also set, the same decl fails, and the returned list carries the
+        // warning.
+        let options = ValidationOptions {
+            warn_nonconventional_service_paths: true,
+            deny_warnings: true,
+            ..ValidationOptions::default()
+        };
+        assert_eq!(
+            validate_with_options(&decl, options),
+            Err(ErrorList::new(vec![Error::nonconventional_service_path("/foo/bar")]))
+        );
+    }
+
+    #[test]
+    fn test_api_level_gates_capability_kinds() {
+        let mut decl = new_component_decl();
+        decl.uses = Some(vec![UseDecl::Storage(UseStorageDecl {
+            source_name: Some("cache".to_string()),
+            target_path: Some("/cache".to_string()),
+        })]);
+
+        // Storage requires API level 5; a component targeting an earlier level is flagged.
+        let options = ValidationOptions { api_level: Some(4), ..ValidationOptions::default() };
+        assert_eq!(
+            validate_with_options(&decl, options),
+            Err(ErrorList::new(vec![Error::capability_requires_api_level("cache", 5)]))
+        );
+
+        // The same use is allowed at or after the level storage became available.
+        let options = ValidationOptions { api_level: Some(5), ..ValidationOptions::default() };
+        assert_eq!(validate_with_options(&decl, options), Ok(()));
+
+        // Without an api_level configured at all, there's nothing to gate against.
+        assert_eq!(validate(&decl), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_test_facets() {
+        let mut decl = new_component_decl();
+        decl.facets = Some(Object {
+            entries: vec![Entry {
+                key: "fuchsia.test".to_string(),
+                value: Some(Box::new(Value::Obj(Object {
+                    entries: vec![Entry {
+                        key: "injected-services".to_string(),
+                        value: Some(Box::new(Value::Obj(Object {
+                            entries: vec![
+                                Entry {
+                                    key: "fuchsia.logger.LogSink".to_string(),
+                                    value: Some(Box::new(Value::Str(
+                                        "fuchsia-pkg://fuchsia.com/logger#meta/logger.cmx"
+                                            .to_string(),
+                                    ))),
+                                },
+                                Entry {
+                                    key: "fuchsia.broken.Service".to_string(),
+                                    value: Some(Box::new(Value::Str("not a url".to_string()))),
+                                },
+                            ],
+                        }))),
+                    }],
+                }))),
+            }],
+        });
+
+        // By default, `facets` isn't interpreted at all, so this passes.
+        assert_eq!(validate(&decl), Ok(()));
+
+        // With `validate_test_facets` set, the malformed injected-services URL is flagged.
+        let options =
+            ValidationOptions { validate_test_facets: true, ..ValidationOptions::default() };
+        assert_eq!(
+            validate_with_options(&decl, options),
+            Err(ErrorList::new(vec![Error::invalid_field(
+                "facets",
+                "fuchsia.test.injected-services"
+            )]))
+        );
+    }
+
+    #[test]
+    fn test_error_list_summary() {
+        let errors = ErrorList::new(vec![
+            Error::missing_field("ChildDecl", "name"),
+            Error::missing_field("OfferDecl", "target"),
+            Error::invalid_child("OfferDecl", "source", "netstack"),
+            Error::unused_storage("cache"),
+        ]);
+        let summary = errors.summary();
+        let expected: HashMap<&'static str, usize> =
+            [("missing_field", 2), ("invalid_child", 1), ("unused_storage", 1)]
+                .iter()
+                .cloned()
+                .collect();
+        assert_eq!(summary, expected);
+    }
+
+    #[test]
+    fn test_validate_with_stats() {
+        let mut decl = new_component_decl();
+        decl.children = Some(vec![ChildDecl {
+            name: Some("logger".to_string()),
+            url: Some("fuchsia-pkg://fuchsia.com/logger#meta/logger.cm".to_string()),
+            startup: Some(StartupMode::Lazy),
+            environment: None,
+        }]);
+        decl.capabilities = Some(vec![CapabilityDecl::Storage(StorageDecl {
+            name: Some("data".to_string()),
+            source_path: Some("/minfs".to_string()),
+            source: Some(Ref::Parent(ParentRef {})),
+            subdir: None,
+        })]);
+        decl.uses = Some(vec![UseDecl::Storage(UseStorageDecl {
+            source_name: Some("data".to_string()),
+            target_path: Some("/data".to_string()),
+        })]);
+        decl.offers = Some(vec![OfferDecl::Storage(OfferStorageDecl {
+            source_name: Some("data".to_string()),
+            source: Some(Ref::Self_(SelfRef {})),
+            target: Some(Ref::Child(ChildRef { name: "logger".to_string(), collection: None })),
+            target_name: Some("data".to_string()),
+        })]);
+
+        let (result, stats) = validate_with_stats(&decl);
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            stats,
+            ValidationStats {
+                children: 1,
+                collections: 0,
+                storage: 1,
+                uses: 1,
+                exposes: 0,
+                offers: 1,
+                errors: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_data_storage_use_at_reserved_meta_path_is_an_error() {
+        let use_ = UseDecl::Storage(UseStorageDecl {
+            source_name: Some("data".to_string()),
+            target_path: Some("/meta".to_string()),
+        });
+        let mut ctx = ValidationContext::default();
+        ctx.validate_use_decl(&use_);
+        assert_eq!(
+            ctx.errors,
+            vec![Error::reserved_meta_path("UseStorageDecl", "target_path")]
+        );
 
-        let matched_exp =
-            expected_res.into_iter().find(|expected| res == format!("{:?}", expected));
+        // A meta storage use targeting "/meta" is exactly what's expected, and not flagged.
+        let use_ = UseDecl::Storage(UseStorageDecl {
+            source_name: Some("meta".to_string()),
+            target_path: Some("/meta".to_string()),
+        });
+        let mut ctx = ValidationContext::default();
+        ctx.validate_use_decl(&use_);
+        assert_eq!(ctx.errors, vec![]);
+    }
 
-        assert!(
-            matched_exp.is_some(),
-            "assertion failed: Expected one of:\n{:?}\nActual:\n{:?}",
-            expected_res_debug,
-            res
+    #[test]
+    fn test_meta_storage_use_not_targeting_meta_path_is_an_error() {
+        let use_ = UseDecl::Storage(UseStorageDecl {
+            source_name: Some("meta".to_string()),
+            target_path: Some("/data/misconfigured".to_string()),
+        });
+        let mut ctx = ValidationContext::default();
+        ctx.validate_use_decl(&use_);
+        assert_eq!(
+            ctx.errors,
+            vec![Error::meta_storage_misconfigured("/data/misconfigured")]
         );
-    }
 
-    fn validate_capabilities_test(input: Vec<CapabilityDecl>, expected_res: Result<(), ErrorList>) {
-        let res = validate_capabilities(&input);
-        assert_eq!(res, expected_res);
+        // A non-meta storage use not targeting "/meta" is exactly what's expected, and not
+        // flagged.
+        let use_ = UseDecl::Storage(UseStorageDecl {
+            source_name: Some("cache".to_string()),
+            target_path: Some("/data/cache".to_string()),
+        });
+        let mut ctx = ValidationContext::default();
+        ctx.validate_use_decl(&use_);
+        assert_eq!(ctx.errors, vec![]);
     }
 
-    fn check_test<F>(check_fn: F, input: &str, expected_res: Result<(), ErrorList>)
-    where
-        F: FnOnce(Option<&String>, &str, &str, &mut Vec<Error>) -> bool,
-    {
-        let mut errors = vec![];
-        let res: Result<(), ErrorList> =
-            match check_fn(Some(&input.to_string()), "FooDecl", "foo", &mut errors) {
-                true => Ok(()),
-                false => Err(ErrorList::new(errors)),
-            };
-        assert_eq!(format!("{:?}", res), format!("{:?}", expected_res));
+    #[test]
+    fn test_offer_directory_from_meta_path_is_an_error() {
+        let child = ChildDecl {
+            name: Some("logger".to_string()),
+            url: Some("fuchsia-pkg://fuchsia.com/logger#meta/logger.cm".to_string()),
+            startup: Some(StartupMode::Lazy),
+            environment: None,
+        };
+
+        let offer = OfferDecl::Directory(OfferDirectoryDecl {
+            source: Some(Ref::Parent(ParentRef {})),
+            source_path: Some("/meta/foo".to_string()),
+            target: Some(Ref::Child(ChildRef { name: "logger".to_string(), collection: None })),
+            target_path: Some("/meta/foo".to_string()),
+            rights: None,
+            subdir: None,
+            dependency_type: Some(DependencyType::Strong),
+        });
+        let mut ctx = ValidationContext::default();
+        ctx.validate_child_decl(&child);
+        ctx.validate_offers_decl(&offer);
+        assert_eq!(ctx.errors, vec![Error::invalid_field("OfferDirectoryDecl", "source_path")]);
+
+        // Offering a directory rooted at "/meta" from the framework is exactly how meta storage
+        // is exposed, and not flagged.
+        let offer = OfferDecl::Directory(OfferDirectoryDecl {
+            source: Some(Ref::Framework(FrameworkRef {})),
+            source_path: Some("/meta/foo".to_string()),
+            target: Some(Ref::Child(ChildRef { name: "logger".to_string(), collection: None })),
+            target_path: Some("/meta/foo".to_string()),
+            rights: None,
+            subdir: None,
+            dependency_type: Some(DependencyType::Strong),
+        });
+        let mut ctx = ValidationContext::default();
+        ctx.validate_child_decl(&child);
+        ctx.validate_offers_decl(&offer);
+        assert_eq!(ctx.errors, vec![]);
     }
 
-    fn new_component_decl() -> ComponentDecl {
-        ComponentDecl {
-            program: None,
-            uses: None,
-            exposes: None,
-            offers: None,
-            facets: None,
-            capabilities: None,
-            children: None,
-            collections: None,
-            environments: None,
-        }
+    #[test]
+    fn test_use_source_must_be_parent_or_framework() {
+        let use_ = UseDecl::Protocol(UseProtocolDecl {
+            source: Some(Ref::Self_(SelfRef {})),
+            source_path: Some("/svc/fuchsia.foo.Bar".to_string()),
+            target_path: Some("/svc/fuchsia.foo.Bar".to_string()),
+        });
+        let mut ctx = ValidationContext::default();
+        ctx.validate_use_decl(&use_);
+        assert_eq!(
+            ctx.errors,
+            vec![Error::invalid_use_source("UseProtocolDecl", "self")]
+        );
+
+        let use_ = UseDecl::Protocol(UseProtocolDecl {
+            source: Some(Ref::Child(ChildRef { name: "logger".to_string(), collection: None })),
+            source_path: Some("/svc/fuchsia.foo.Bar".to_string()),
+            target_path: Some("/svc/fuchsia.foo.Bar".to_string()),
+        });
+        let mut ctx = ValidationContext::default();
+        ctx.validate_use_decl(&use_);
+        assert_eq!(
+            ctx.errors,
+            vec![Error::invalid_use_source("UseProtocolDecl", "child")]
+        );
+
+        let use_ = UseDecl::Directory(UseDirectoryDecl {
+            source: Some(Ref::Collection(CollectionRef { name: "coll".to_string() })),
+            source_path: Some("/data/foo".to_string()),
+            target_path: Some("/data/foo".to_string()),
+            rights: Some(fio2::Operations::Connect),
+            subdir: None,
+        });
+        let mut ctx = ValidationContext::default();
+        ctx.validate_use_decl(&use_);
+        assert_eq!(
+            ctx.errors,
+            vec![Error::invalid_use_source("UseDirectoryDecl", "collection")]
+        );
+
+        // A parent source is exactly what's expected, and not flagged.
+        let use_ = UseDecl::Protocol(UseProtocolDecl {
+            source: Some(Ref::Parent(ParentRef {})),
+            source_path: Some("/svc/fuchsia.foo.Bar".to_string()),
+            target_path: Some("/svc/fuchsia.foo.Bar".to_string()),
+        });
+        let mut ctx = ValidationContext::default();
+        ctx.validate_use_decl(&use_);
+        assert_eq!(ctx.errors, vec![]);
     }
 
     #[test]
@@ -3639,6 +6295,63 @@ mod tests {
                 Error::missing_field("OfferDirectoryDecl", "rights"),
             ])),
         },
+        test_validate_offers_directory_rights_escalation => {
+            input = {
+                let mut decl = new_component_decl();
+                decl.offers = Some(vec![
+                    OfferDecl::Directory(OfferDirectoryDecl {
+                        source: Some(Ref::Self_(SelfRef{})),
+                        source_path: Some("assets".to_string()),
+                        target: Some(Ref::Child(
+                           ChildRef {
+                               name: "logger".to_string(),
+                               collection: None,
+                           }
+                        )),
+                        target_path: Some("assets".to_string()),
+                        rights: Some(fio2::Operations::Connect | fio2::Operations::WriteBytes),
+                        subdir: None,
+                        dependency_type: Some(DependencyType::Strong),
+                    }),
+                    OfferDecl::Directory(OfferDirectoryDecl {
+                        source: Some(Ref::Self_(SelfRef{})),
+                        source_path: Some("data".to_string()),
+                        target: Some(Ref::Child(
+                           ChildRef {
+                               name: "logger".to_string(),
+                               collection: None,
+                           }
+                        )),
+                        target_path: Some("data".to_string()),
+                        rights: Some(fio2::Operations::Connect),
+                        subdir: None,
+                        dependency_type: Some(DependencyType::Strong),
+                    }),
+                ]);
+                decl.capabilities = Some(vec![
+                    CapabilityDecl::Directory(DirectoryDecl {
+                        name: Some("assets".to_string()),
+                        source_path: Some("/data/assets".to_string()),
+                        rights: Some(fio2::Operations::Connect),
+                    }),
+                    CapabilityDecl::Directory(DirectoryDecl {
+                        name: Some("data".to_string()),
+                        source_path: Some("/data".to_string()),
+                        rights: Some(fio2::Operations::Connect | fio2::Operations::WriteBytes),
+                    }),
+                ]);
+                decl.children = Some(vec![ChildDecl{
+                    name: Some("logger".to_string()),
+                    url: Some("fuchsia-pkg://fuchsia.com/logger#meta/logger.cm".to_string()),
+                    startup: Some(StartupMode::Lazy),
+                    environment: None,
+                }]);
+                decl
+            },
+            result = Err(ErrorList::new(vec![
+                Error::rights_escalation("assets"),
+            ])),
+        },
         test_validate_offers_extraneous => {
             input = {
                 let mut decl = new_component_decl();
@@ -3996,7 +6709,90 @@ mod tests {
                 ..new_component_decl()
             },
             result = Err(ErrorList::new(vec![
-                Error::offer_target_equals_source("OfferStorageDecl", "logger"),
+                Error::storage_backing_cycle("data"),
+            ])),
+        },
+        test_validate_offers_storage_target_not_backing_child_is_allowed => {
+            input = ComponentDecl {
+                offers: Some(vec![
+                    OfferDecl::Storage(OfferStorageDecl {
+                        source_name: Some("data".to_string()),
+                        source: Some(Ref::Self_(SelfRef { })),
+                        target: Some(Ref::Child(
+                            ChildRef {
+                                name: "netstack".to_string(),
+                                collection: None,
+                            }
+                        )),
+                        target_name: Some("data".to_string()),
+                    })
+                ]),
+                capabilities: Some(vec![
+                    CapabilityDecl::Storage(StorageDecl {
+                        name: Some("data".to_string()),
+                        source_path: Some("/minfs".to_string()),
+                        source: Some(Ref::Child(ChildRef {
+                            name: "logger".to_string(),
+                            collection: None,
+                        })),
+                        subdir: None,
+                    }),
+                ]),
+                children: Some(vec![
+                    ChildDecl {
+                        name: Some("logger".to_string()),
+                        url: Some("fuchsia-pkg://fuchsia.com/logger/stable#meta/logger.cm".to_string()),
+                        startup: Some(StartupMode::Lazy),
+                        environment: None,
+                    },
+                    ChildDecl {
+                        name: Some("netstack".to_string()),
+                        url: Some("fuchsia-pkg://fuchsia.com/netstack/stable#meta/netstack.cm".to_string()),
+                        startup: Some(StartupMode::Lazy),
+                        environment: None,
+                    },
+                ]),
+                ..new_component_decl()
+            },
+            result = Ok(()),
+        },
+        test_self_offer_to_backing_child => {
+            input = ComponentDecl {
+                offers: Some(vec![
+                    OfferDecl::Protocol(OfferProtocolDecl {
+                        source: Some(Ref::Self_(SelfRef {})),
+                        source_path: Some("/data/logger_storage".to_string()),
+                        target: Some(Ref::Child(
+                            ChildRef { name: "logger".to_string(), collection: None }
+                        )),
+                        target_path: Some("/data/logger_storage".to_string()),
+                        dependency_type: Some(DependencyType::Strong),
+                    })
+                ]),
+                capabilities: Some(vec![
+                    CapabilityDecl::Storage(StorageDecl {
+                        name: Some("data".to_string()),
+                        source_path: Some("/data/logger_storage".to_string()),
+                        source: Some(Ref::Child(ChildRef {
+                            name: "logger".to_string(),
+                            collection: None,
+                        })),
+                        subdir: None,
+                    }),
+                ]),
+                children: Some(vec![
+                    ChildDecl {
+                        name: Some("logger".to_string()),
+                        url: Some("fuchsia-pkg://fuchsia.com/logger/stable#meta/logger.cm".to_string()),
+                        startup: Some(StartupMode::Lazy),
+                        environment: None,
+                    },
+                ]),
+                ..new_component_decl()
+            },
+            result = Err(ErrorList::new(vec![
+                Error::self_offer_to_backing_child("logger", "/data/logger_storage"),
+                Error::unused_storage("data"),
             ])),
         },
         test_validate_offers_invalid_child => {
@@ -4644,6 +7440,79 @@ mod tests {
                 Error::invalid_runner("RunnerRegistration", "source_name", "dart"),
             ])),
         },
+        test_validate_environment_missing_resolver => {
+            input = {
+                let mut decl = new_component_decl();
+                decl.environments = Some(vec![EnvironmentDecl {
+                    name: Some("a".to_string()),
+                    extends: Some(EnvironmentExtends::None),
+                    runners: None,
+                    resolvers: Some(vec![
+                        ResolverRegistration {
+                            resolver: Some("pkg_resolver".to_string()),
+                            source: Some(Ref::Self_(SelfRef{})),
+                            scheme: Some("fuchsia-pkg".to_string()),
+                        },
+                    ]),
+                    stop_timeout_ms: Some(1234),
+                }]);
+                decl
+            },
+            result = Err(ErrorList::new(vec![
+                Error::invalid_resolver("ResolverRegistration", "resolver", "pkg_resolver"),
+            ])),
+        },
+        test_validate_environment_valid_resolver => {
+            input = {
+                let mut decl = new_component_decl();
+                decl.capabilities = Some(vec![
+                    CapabilityDecl::Resolver(ResolverDecl {
+                        name: Some("pkg_resolver".to_string()),
+                        source_path: Some("/svc/fuchsia.pkg.PackageResolver".to_string()),
+                    }),
+                ]);
+                decl.environments = Some(vec![EnvironmentDecl {
+                    name: Some("a".to_string()),
+                    extends: Some(EnvironmentExtends::None),
+                    runners: None,
+                    resolvers: Some(vec![
+                        ResolverRegistration {
+                            resolver: Some("pkg_resolver".to_string()),
+                            source: Some(Ref::Self_(SelfRef{})),
+                            scheme: Some("fuchsia-pkg".to_string()),
+                        },
+                    ]),
+                    stop_timeout_ms: Some(1234),
+                }]);
+                decl
+            },
+            result = Ok(()),
+        },
+        test_validate_environment_duplicate_name => {
+            input = {
+                let mut decl = new_component_decl();
+                decl.environments = Some(vec![
+                    EnvironmentDecl {
+                        name: Some("a".to_string()),
+                        extends: Some(EnvironmentExtends::Realm),
+                        runners: None,
+                        resolvers: None,
+                        stop_timeout_ms: None,
+                    },
+                    EnvironmentDecl {
+                        name: Some("a".to_string()),
+                        extends: Some(EnvironmentExtends::Realm),
+                        runners: None,
+                        resolvers: None,
+                        stop_timeout_ms: None,
+                    },
+                ]);
+                decl
+            },
+            result = Err(ErrorList::new(vec![
+                Error::duplicate_field("EnvironmentDecl", "name", "a"),
+            ])),
+        },
         test_validate_environment_duplicate_registrations => {
             input = {
                 let mut decl = new_component_decl();
@@ -4966,6 +7835,27 @@ mod tests {
                 Error::invalid_environment("CollectionDecl", "environment", "test_env"),
             ])),
         },
+        test_validate_collections_duplicate_name => {
+            input = {
+                let mut decl = new_component_decl();
+                decl.collections = Some(vec![
+                    CollectionDecl {
+                        name: Some("coll".to_string()),
+                        durability: Some(Durability::Transient),
+                        environment: None,
+                    },
+                    CollectionDecl {
+                        name: Some("coll".to_string()),
+                        durability: Some(Durability::Persistent),
+                        environment: None,
+                    },
+                ]);
+                decl
+            },
+            result = Err(ErrorList::new(vec![
+                Error::duplicate_field("CollectionDecl", "name", "coll"),
+            ])),
+        },
 
         // capabilities
         test_validate_capabilities_empty => {
@@ -0,0 +1,62 @@
+// Copyright 2020 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Ergonomic constructors for `fidl_fuchsia_sys2::Ref` values.
+//!
+//! Manifests and their tests construct `fsys::Ref`s by hand in many places, which is verbose and
+//! easy to typo (e.g. forgetting `collection: None` on a `ChildRef`). These functions are thin,
+//! typo-proof wrappers around the variants of `fsys::Ref`.
+//!
+//! Note: this FIDL's `Ref` has no `Realm` variant -- "realm" was renamed to "parent" before this
+//! enum was defined -- so there's a `parent()` constructor here rather than `realm()`. There's
+//! also no storage-specific `Ref` variant; a storage capability's source is represented the same
+//! way as any other capability's, via `Parent`/`Self_`/`Child`, so there's no separate
+//! `storage()` constructor here either.
+//!
+//! These live in `cm_rust` rather than `cm_fidl_validator`: `cm_rust` already depends on
+//! `cm_fidl_validator`, so adding the reverse dependency (to share these helpers with
+//! `cm_fidl_validator`'s own tests) would be circular. `cm_fidl_validator`'s tests keep
+//! constructing `Ref`s by hand.
+
+use fidl_fuchsia_sys2 as fsys;
+
+pub fn parent() -> fsys::Ref {
+    fsys::Ref::Parent(fsys::ParentRef {})
+}
+
+pub fn self_() -> fsys::Ref {
+    fsys::Ref::Self_(fsys::SelfRef {})
+}
+
+pub fn framework() -> fsys::Ref {
+    fsys::Ref::Framework(fsys::FrameworkRef {})
+}
+
+pub fn child(name: impl Into<String>) -> fsys::Ref {
+    fsys::Ref::Child(fsys::ChildRef { name: name.into(), collection: None })
+}
+
+pub fn collection(name: impl Into<String>) -> fsys::Ref {
+    fsys::Ref::Collection(fsys::CollectionRef { name: name.into() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constructors_produce_expected_variants() {
+        assert_eq!(parent(), fsys::Ref::Parent(fsys::ParentRef {}));
+        assert_eq!(self_(), fsys::Ref::Self_(fsys::SelfRef {}));
+        assert_eq!(framework(), fsys::Ref::Framework(fsys::FrameworkRef {}));
+        assert_eq!(
+            child("logger"),
+            fsys::Ref::Child(fsys::ChildRef { name: "logger".to_string(), collection: None })
+        );
+        assert_eq!(
+            collection("coll"),
+            fsys::Ref::Collection(fsys::CollectionRef { name: "coll".to_string() })
+        );
+    }
+}
@@ -7,14 +7,17 @@ use {
     fidl_fuchsia_sys2 as fsys,
     lazy_static::lazy_static,
     std::collections::HashMap,
+    std::collections::hash_map::DefaultHasher,
     std::convert::{From, TryFrom, TryInto},
     std::fmt,
+    std::hash::{Hash, Hasher},
     std::path::PathBuf,
     std::str::FromStr,
     thiserror::Error,
 };
 
 pub mod data;
+pub mod fsys_ref;
 
 lazy_static! {
     static ref DATA_TYPENAME: CapabilityName = CapabilityName("Data".to_string());
@@ -431,6 +434,50 @@ impl ComponentDecl {
         self.collections.iter().find(|c| c.name == collection_name)
     }
 
+    /// Rewrites the authority (host) of every child's `fuchsia-pkg://` URL that currently points
+    /// at `from_host` to point at `to_host` instead, e.g. when re-homing components to a
+    /// different package repository. URLs using another scheme, or whose authority doesn't match
+    /// `from_host` exactly, are left untouched.
+    ///
+    /// Returns the number of child URLs rewritten.
+    pub fn rewrite_child_urls(&mut self, from_host: &str, to_host: &str) -> usize {
+        const SCHEME: &str = "fuchsia-pkg://";
+        let mut count = 0;
+        for child in &mut self.children {
+            let rest = match child.url.strip_prefix(SCHEME) {
+                Some(rest) => rest,
+                None => continue,
+            };
+            let authority_end = rest.find('/').unwrap_or(rest.len());
+            let (authority, remainder) = rest.split_at(authority_end);
+            if authority != from_host {
+                continue;
+            }
+            child.url = format!("{}{}{}", SCHEME, to_host, remainder);
+            count += 1;
+        }
+        count
+    }
+
+    /// Returns the names of the children that back this component's storage capabilities, i.e.
+    /// the children named as the `source` of a `StorageDecl`. Each name is deduplicated, even if
+    /// multiple storage capabilities share the same source child.
+    pub fn storage_source_children(&self) -> Vec<&str> {
+        let mut children: Vec<&str> = vec![];
+        for capability in &self.capabilities {
+            if let CapabilityDecl::Storage(StorageDecl {
+                source: StorageDirectorySource::Child(child_name),
+                ..
+            }) = capability
+            {
+                if !children.contains(&child_name.as_str()) {
+                    children.push(child_name.as_str());
+                }
+            }
+        }
+        children
+    }
+
     /// Indicates whether the capability specified by `target_name` is exposed to the framework.
     pub fn is_protocol_exposed_to_framework(&self, in_target_name: &CapabilityName) -> bool {
         self.exposes.iter().any(|expose| match expose {
@@ -471,6 +518,375 @@ impl ComponentDecl {
             _ => false,
         })
     }
+
+    /// Returns the `ExposeDecl` that exposes `in_target_name` to the framework, if any. Unlike
+    /// [ComponentDecl::is_protocol_exposed_to_framework], this considers every expose kind that
+    /// carries an [ExposeTarget] (service, protocol, directory, runner, resolver), not just
+    /// protocols.
+    pub fn find_expose_to_framework(&self, in_target_name: &CapabilityName) -> Option<&ExposeDecl> {
+        self.exposes.iter().find(|expose| match expose {
+            ExposeDecl::Service(ExposeServiceDecl { target, target_name, .. })
+            | ExposeDecl::Runner(ExposeRunnerDecl { target, target_name, .. })
+            | ExposeDecl::Resolver(ExposeResolverDecl { target, target_name, .. })
+                if target == &ExposeTarget::Framework =>
+            {
+                target_name == in_target_name
+            }
+            ExposeDecl::Protocol(ExposeProtocolDecl {
+                target,
+                target_path: target_name_or_path,
+                ..
+            })
+            | ExposeDecl::Directory(ExposeDirectoryDecl {
+                target,
+                target_path: target_name_or_path,
+                ..
+            }) if target == &ExposeTarget::Framework => match target_name_or_path {
+                CapabilityNameOrPath::Name(name) => name == in_target_name,
+                CapabilityNameOrPath::Path(path) => {
+                    // TODO(fxbug.dev/56604): Remove this legacy compatibility path
+                    let res: Result<CapabilityPath, _> = format!("/svc/{}", in_target_name).parse();
+                    match res {
+                        Ok(in_target_path) => path == &in_target_path,
+                        Err(_) => false,
+                    }
+                }
+            },
+            _ => false,
+        })
+    }
+
+    /// Returns the `target_path` of every expose in this component's manifest that's reachable
+    /// by a path rather than just a capability name, in declaration order. Of the expose kinds,
+    /// only protocol and directory exposes carry a path-shaped `target_path`
+    /// (`CapabilityNameOrPath::Path`) in this tree's schema -- service, runner, and resolver
+    /// exposes are named capabilities with no path, and protocol/directory exposes that were
+    /// declared by name (`CapabilityNameOrPath::Name`) rather than path are likewise excluded.
+    pub fn exposed_paths(&self) -> Vec<&CapabilityPath> {
+        self.exposed_paths_filtered(None)
+    }
+
+    /// Like [ComponentDecl::exposed_paths], but when `target` is `Some`, only includes exposes
+    /// whose [ExposeTarget] matches it -- e.g. `Some(ExposeTarget::Parent)` to find only the
+    /// paths actually reaching the parent, as opposed to ones exposed to the framework.
+    pub fn exposed_paths_filtered(&self, target: Option<ExposeTarget>) -> Vec<&CapabilityPath> {
+        self.exposes
+            .iter()
+            .filter_map(|expose| match expose {
+                ExposeDecl::Protocol(ExposeProtocolDecl {
+                    target: t,
+                    target_path: CapabilityNameOrPath::Path(path),
+                    ..
+                })
+                | ExposeDecl::Directory(ExposeDirectoryDecl {
+                    target: t,
+                    target_path: CapabilityNameOrPath::Path(path),
+                    ..
+                }) if target.as_ref().map_or(true, |target| target == t) => Some(path),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Buckets every offer in this component's manifest by its [OfferTarget], e.g. for rendering a
+    /// topology graph grouped by destination.
+    pub fn offers_by_target(&self) -> HashMap<OfferTarget, Vec<&OfferDecl>> {
+        let mut offers_by_target = HashMap::new();
+        for offer in &self.offers {
+            let target = match offer {
+                OfferDecl::Service(o) => &o.target,
+                OfferDecl::Protocol(o) => &o.target,
+                OfferDecl::Directory(o) => &o.target,
+                OfferDecl::Storage(o) => &o.target,
+                OfferDecl::Runner(o) => &o.target,
+                OfferDecl::Resolver(o) => &o.target,
+                OfferDecl::Event(o) => &o.target,
+            };
+            offers_by_target.entry(target.clone()).or_insert_with(Vec::new).push(offer);
+        }
+        offers_by_target
+    }
+
+    /// Explains, for every offer in this component's manifest, whether it could be the source for
+    /// `capability` offered to `child_name` (or, if `collection` is given, to that collection
+    /// instead), and if not, why not. Unlike a bare lookup that returns `None` on failure, this
+    /// names the closest near-miss offers, which is useful when logging why a capability route
+    /// failed to resolve.
+    pub fn explain_offer_lookup(
+        &self,
+        capability: &CapabilityNameOrPath,
+        child_name: &str,
+        collection: Option<&str>,
+    ) -> String {
+        if self.offers.is_empty() {
+            return "no offers are declared in this component's manifest".to_string();
+        }
+
+        let wanted_target = match collection {
+            Some(collection) => OfferTarget::Collection(collection.to_string()),
+            None => OfferTarget::Child(child_name.to_string()),
+        };
+
+        self.offers
+            .iter()
+            .map(|offer| Self::explain_one_offer(offer, capability, &wanted_target))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn explain_one_offer(
+        offer: &OfferDecl,
+        capability: &CapabilityNameOrPath,
+        wanted_target: &OfferTarget,
+    ) -> String {
+        let (decl_name, target, offered_capability_matches) = match offer {
+            OfferDecl::Service(o) => (
+                "OfferServiceDecl",
+                &o.target,
+                CapabilityNameOrPath::Name(o.target_name.clone()) == *capability,
+            ),
+            OfferDecl::Protocol(o) => {
+                ("OfferProtocolDecl", &o.target, o.target_path == *capability)
+            }
+            OfferDecl::Directory(o) => {
+                ("OfferDirectoryDecl", &o.target, o.target_path == *capability)
+            }
+            OfferDecl::Storage(o) => (
+                "OfferStorageDecl",
+                &o.target,
+                CapabilityNameOrPath::Name(o.target_name.clone()) == *capability,
+            ),
+            OfferDecl::Runner(o) => (
+                "OfferRunnerDecl",
+                &o.target,
+                CapabilityNameOrPath::Name(o.target_name.clone()) == *capability,
+            ),
+            OfferDecl::Resolver(o) => (
+                "OfferResolverDecl",
+                &o.target,
+                CapabilityNameOrPath::Name(o.target_name.clone()) == *capability,
+            ),
+            OfferDecl::Event(o) => (
+                "OfferEventDecl",
+                &o.target,
+                CapabilityNameOrPath::Name(o.target_name.clone()) == *capability,
+            ),
+        };
+
+        if target != wanted_target {
+            format!("{} targets {:?}, not {:?}", decl_name, target, wanted_target)
+        } else if !offered_capability_matches {
+            format!(
+                "{} targets {:?} as expected, but offers a different capability",
+                decl_name, target
+            )
+        } else {
+            format!("{} targets {:?} and offers the requested capability", decl_name, target)
+        }
+    }
+
+    /// Renders this decl as an indented tree for debugging: children and collections first, then
+    /// uses/exposes/offers, each grouped under its own heading. None of `UseDecl`, `ExposeDecl`,
+    /// or `OfferDecl` implement `Display` in this tree (see [component_decl_hash]), so each line
+    /// is built from a one-line summary of the decl's kind, source, and target instead of
+    /// delegating to a per-decl `Display` impl. This is meant for humans reading test failures and
+    /// logs, not for parsing -- use `{:#?}` if you need the full structure.
+    pub fn to_pretty_string(&self) -> String {
+        let mut out = String::new();
+        out.push_str("ComponentDecl {\n");
+        Self::push_section(&mut out, "children", &self.children, |c| {
+            format!("{} -> {} ({:?})", c.name, c.url, c.startup)
+        });
+        Self::push_section(&mut out, "collections", &self.collections, |c| {
+            format!("{} ({:?})", c.name, c.durability)
+        });
+        Self::push_section(&mut out, "uses", &self.uses, |u| Self::summarize_use(u));
+        Self::push_section(&mut out, "exposes", &self.exposes, |e| Self::summarize_expose(e));
+        Self::push_section(&mut out, "offers", &self.offers, |o| Self::summarize_offer(o));
+        out.push_str("}");
+        out
+    }
+
+    fn push_section<T>(out: &mut String, heading: &str, items: &[T], summarize: impl Fn(&T) -> String) {
+        if items.is_empty() {
+            return;
+        }
+        out.push_str(&format!("  {}:\n", heading));
+        for item in items {
+            out.push_str(&format!("    {}\n", summarize(item)));
+        }
+    }
+
+    fn summarize_use(use_: &UseDecl) -> String {
+        match use_ {
+            UseDecl::Service(u) => {
+                format!("UseServiceDecl {} from {:?}", u.source_name, u.source)
+            }
+            UseDecl::Protocol(u) => {
+                format!("UseProtocolDecl {} from {:?}", u.source_path, u.source)
+            }
+            UseDecl::Directory(u) => {
+                format!("UseDirectoryDecl {} from {:?}", u.source_path, u.source)
+            }
+            UseDecl::Storage(u) => format!("UseStorageDecl {}", u.source_name),
+            UseDecl::Runner(u) => format!("UseRunnerDecl {}", u.source_name),
+            UseDecl::Event(u) => {
+                format!("UseEventDecl {} from {:?}", u.source_name, u.source)
+            }
+            UseDecl::EventStream(u) => format!("UseEventStreamDecl {}", u.target_path),
+        }
+    }
+
+    fn summarize_expose(expose: &ExposeDecl) -> String {
+        match expose {
+            ExposeDecl::Service(e) => {
+                format!("ExposeServiceDecl {} to {:?}", e.target_name, e.target)
+            }
+            ExposeDecl::Protocol(e) => {
+                format!("ExposeProtocolDecl {} to {:?} from {:?}", e.target_path, e.target, e.source)
+            }
+            ExposeDecl::Directory(e) => {
+                format!("ExposeDirectoryDecl {} to {:?} from {:?}", e.target_path, e.target, e.source)
+            }
+            ExposeDecl::Runner(e) => {
+                format!("ExposeRunnerDecl {} to {:?} from {:?}", e.target_name, e.target, e.source)
+            }
+            ExposeDecl::Resolver(e) => {
+                format!("ExposeResolverDecl {} to {:?} from {:?}", e.target_name, e.target, e.source)
+            }
+        }
+    }
+
+    fn summarize_offer(offer: &OfferDecl) -> String {
+        match offer {
+            OfferDecl::Service(o) => {
+                format!("OfferServiceDecl {} to {:?}", o.target_name, o.target)
+            }
+            OfferDecl::Protocol(o) => {
+                format!("OfferProtocolDecl {} to {:?} from {:?}", o.target_path, o.target, o.source)
+            }
+            OfferDecl::Directory(o) => {
+                format!("OfferDirectoryDecl {} to {:?} from {:?}", o.target_path, o.target, o.source)
+            }
+            OfferDecl::Storage(o) => {
+                format!("OfferStorageDecl {} to {:?} from {:?}", o.target_name, o.target, o.source)
+            }
+            OfferDecl::Runner(o) => {
+                format!("OfferRunnerDecl {} to {:?} from {:?}", o.target_name, o.target, o.source)
+            }
+            OfferDecl::Resolver(o) => {
+                format!("OfferResolverDecl {} to {:?} from {:?}", o.target_name, o.target, o.source)
+            }
+            OfferDecl::Event(o) => {
+                format!("OfferEventDecl {} to {:?} from {:?}", o.target_name, o.target, o.source)
+            }
+        }
+    }
+
+    /// Collapses out every `.` path segment (e.g. `/svc/./foo` becomes `/svc/foo`) from every
+    /// capability path in this declaration, then validates the result. Returns the canonicalized
+    /// decl on success.
+    ///
+    /// This exists because `cm_types::Path::validate` (and so `cm_fidl_validator`) accepts `.` as
+    /// an ordinary, valid path segment -- it isn't rejected as a malformed path, so two manifests
+    /// that differ only by a redundant `/./` validate as written rather than being normalized
+    /// first. Callers that want paths compared/routed as if `/./` weren't there should run this
+    /// before validating instead of calling `cm_fidl_validator::validate` directly.
+    pub fn canonicalize_and_validate(
+        mut self,
+    ) -> Result<ComponentDecl, cm_fidl_validator::ErrorList> {
+        fn canonicalize_name_or_path(path: &mut CapabilityNameOrPath) {
+            if let CapabilityNameOrPath::Path(p) = path {
+                *p = p.canonicalize();
+            }
+        }
+
+        for use_ in &mut self.uses {
+            match use_ {
+                UseDecl::Service(u) => u.target_path = u.target_path.canonicalize(),
+                UseDecl::Protocol(u) => {
+                    canonicalize_name_or_path(&mut u.source_path);
+                    u.target_path = u.target_path.canonicalize();
+                }
+                UseDecl::Directory(u) => {
+                    canonicalize_name_or_path(&mut u.source_path);
+                    u.target_path = u.target_path.canonicalize();
+                }
+                UseDecl::Storage(u) => u.target_path = u.target_path.canonicalize(),
+                UseDecl::EventStream(u) => u.target_path = u.target_path.canonicalize(),
+                UseDecl::Runner(_) | UseDecl::Event(_) => {}
+            }
+        }
+        for expose in &mut self.exposes {
+            match expose {
+                ExposeDecl::Protocol(e) => {
+                    canonicalize_name_or_path(&mut e.source_path);
+                    canonicalize_name_or_path(&mut e.target_path);
+                }
+                ExposeDecl::Directory(e) => {
+                    canonicalize_name_or_path(&mut e.source_path);
+                    canonicalize_name_or_path(&mut e.target_path);
+                }
+                ExposeDecl::Service(_) | ExposeDecl::Runner(_) | ExposeDecl::Resolver(_) => {}
+            }
+        }
+        for offer in &mut self.offers {
+            match offer {
+                OfferDecl::Protocol(o) => {
+                    canonicalize_name_or_path(&mut o.source_path);
+                    canonicalize_name_or_path(&mut o.target_path);
+                }
+                OfferDecl::Directory(o) => {
+                    canonicalize_name_or_path(&mut o.source_path);
+                    canonicalize_name_or_path(&mut o.target_path);
+                }
+                OfferDecl::Service(_)
+                | OfferDecl::Storage(_)
+                | OfferDecl::Runner(_)
+                | OfferDecl::Resolver(_)
+                | OfferDecl::Event(_) => {}
+            }
+        }
+        for capability in &mut self.capabilities {
+            match capability {
+                CapabilityDecl::Service(c) => c.source_path = c.source_path.canonicalize(),
+                CapabilityDecl::Protocol(c) => c.source_path = c.source_path.canonicalize(),
+                CapabilityDecl::Directory(c) => c.source_path = c.source_path.canonicalize(),
+                CapabilityDecl::Runner(c) => c.source_path = c.source_path.canonicalize(),
+                CapabilityDecl::Resolver(c) => c.source_path = c.source_path.canonicalize(),
+                CapabilityDecl::Storage(c) => canonicalize_name_or_path(&mut c.source_path),
+            }
+        }
+
+        let fidl_decl: fsys::ComponentDecl = self.clone().native_into_fidl();
+        cm_fidl_validator::validate(&fidl_decl)?;
+        Ok(self)
+    }
+}
+
+/// Computes a hash of `decl` that is stable across reorderings of its `uses`, `exposes`,
+/// `offers`, and `children` lists, since FIDL doesn't guarantee those lists round-trip in a
+/// particular order. Each list is canonicalized by sorting its elements' `Debug` renderings
+/// (none of these types implement `Display`) before they're hashed; every other field is hashed
+/// directly.
+pub fn component_decl_hash(decl: &ComponentDecl) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hash_sorted(&decl.uses, &mut hasher);
+    hash_sorted(&decl.exposes, &mut hasher);
+    hash_sorted(&decl.offers, &mut hasher);
+    hash_sorted(&decl.children, &mut hasher);
+    format!("{:?}", decl.program).hash(&mut hasher);
+    format!("{:?}", decl.facets).hash(&mut hasher);
+    format!("{:?}", decl.capabilities).hash(&mut hasher);
+    format!("{:?}", decl.collections).hash(&mut hasher);
+    format!("{:?}", decl.environments).hash(&mut hasher);
+    hasher.finish()
+}
+
+fn hash_sorted<T: fmt::Debug>(list: &[T], hasher: &mut DefaultHasher) {
+    let mut rendered: Vec<String> = list.iter().map(|item| format!("{:?}", item)).collect();
+    rendered.sort();
+    rendered.hash(hasher);
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -752,12 +1168,20 @@ fidl_translations_identical!(Option<fdata::Dictionary>);
 fidl_translations_identical!(Option<String>);
 
 /// A path to a capability.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+///
+/// `dirname`/`basename` are the parsed components used for comparison and everywhere else in
+/// the codebase; `raw` retains the exact string `try_from`/`from_str` was given, for diagnostics
+/// that want to echo the user's input back verbatim. `raw` is deliberately excluded from
+/// `PartialEq`/`Eq`/`Hash` so paths built from different original strings (e.g. manually
+/// constructed vs. parsed) that resolve to the same dirname/basename keep comparing equal.
+#[derive(Debug, Clone, Default)]
 pub struct CapabilityPath {
     /// The directory containing the last path element, e.g. `/svc/foo` in `/svc/foo/bar`.
     pub dirname: String,
     /// The last path element: e.g. `bar` in `/svc/foo/bar`.
     pub basename: String,
+    /// The original string this path was parsed from, if any.
+    raw: Option<String>,
 }
 
 impl CapabilityPath {
@@ -769,6 +1193,41 @@ impl CapabilityPath {
     pub fn split(&self) -> Vec<String> {
         self.to_string().split("/").map(|s| s.to_string()).filter(|s| !s.is_empty()).collect()
     }
+
+    /// Returns the original string this path was parsed from, if any.
+    pub fn raw(&self) -> Option<&str> {
+        self.raw.as_deref()
+    }
+
+    /// Returns the number of non-empty path components, e.g. 2 for `/svc/foo`.
+    pub fn depth(&self) -> usize {
+        self.split().len()
+    }
+
+    /// Returns the equivalent path with every `.` path segment (e.g. `/svc/./foo`) collapsed out,
+    /// so that e.g. `/svc/./foo` and `/svc/foo` become identical paths. Does not otherwise
+    /// resolve `..` segments, since `cm_types::Path::validate` already rejects empty segments and
+    /// this codebase has no notion of a parent capability path.
+    pub fn canonicalize(&self) -> CapabilityPath {
+        let collapsed =
+            self.to_string().split('/').filter(|part| *part != ".").collect::<Vec<_>>().join("/");
+        collapsed.parse().expect("collapsing \".\" segments out of a valid path can't make it invalid")
+    }
+}
+
+impl PartialEq for CapabilityPath {
+    fn eq(&self, other: &Self) -> bool {
+        self.dirname == other.dirname && self.basename == other.basename
+    }
+}
+
+impl Eq for CapabilityPath {}
+
+impl Hash for CapabilityPath {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.dirname.hash(state);
+        self.basename.hash(state);
+    }
 }
 
 impl FromStr for CapabilityPath {
@@ -781,6 +1240,7 @@ impl FromStr for CapabilityPath {
         Ok(CapabilityPath {
             dirname: if idx == 0 { "/".to_string() } else { path[0..idx].to_string() },
             basename: path[idx + 1..].to_string(),
+            raw: Some(path.to_string()),
         })
     }
 }
@@ -815,6 +1275,13 @@ impl UseDecl {
         }
     }
 
+    /// Alias for `path()`, named for callers that care specifically about the path this use
+    /// installs into the component's incoming namespace (as opposed to, e.g., a capability's
+    /// source path). `Runner` and `Event` uses don't install a namespace entry and return `None`.
+    pub fn target_path(&self) -> Option<&CapabilityPath> {
+        self.path()
+    }
+
     pub fn name(&self) -> Option<&CapabilityName> {
         match self {
             UseDecl::Event(event_decl) => Some(&event_decl.source_name),
@@ -828,6 +1295,76 @@ impl UseDecl {
     }
 }
 
+/// A lossy, kind-tagged summary of a capability's source identifier, shared by the `Use`,
+/// `Expose`, and `Offer` decl families. It's lossy because reconstructing a concrete decl from
+/// one requires fields (e.g. a use's target path, a directory's rights) that don't round-trip
+/// through `Capability`; the `as_use_*` methods below take those as extra arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Capability {
+    Service(CapabilityNameOrPath),
+    Protocol(CapabilityNameOrPath),
+    Directory(CapabilityNameOrPath),
+    Storage(CapabilityName),
+    Runner(CapabilityName),
+    Resolver(CapabilityName),
+}
+
+impl Capability {
+    /// Reconstitutes a `UseServiceDecl` from this capability's source identifier, given the
+    /// `source` and `target_path` that a bare `Capability` doesn't carry. Returns `None` if this
+    /// isn't a `Capability::Service`, or is one identified by a path rather than a name.
+    pub fn as_use_service(
+        &self,
+        source: UseSource,
+        target_path: CapabilityPath,
+    ) -> Option<UseServiceDecl> {
+        match self {
+            Capability::Service(CapabilityNameOrPath::Name(source_name)) => {
+                Some(UseServiceDecl { source, source_name: source_name.clone(), target_path })
+            }
+            _ => None,
+        }
+    }
+
+    /// Reconstitutes a `UseProtocolDecl` from this capability's source identifier, given the
+    /// `source` and `target_path` that a bare `Capability` doesn't carry. Returns `None` if this
+    /// isn't a `Capability::Protocol`.
+    pub fn as_use_protocol(
+        &self,
+        source: UseSource,
+        target_path: CapabilityPath,
+    ) -> Option<UseProtocolDecl> {
+        match self {
+            Capability::Protocol(source_path) => {
+                Some(UseProtocolDecl { source, source_path: source_path.clone(), target_path })
+            }
+            _ => None,
+        }
+    }
+
+    /// Reconstitutes a `UseDirectoryDecl` from this capability's source identifier, given the
+    /// `source`, `target_path`, `rights`, and `subdir` that a bare `Capability` doesn't carry.
+    /// Returns `None` if this isn't a `Capability::Directory`.
+    pub fn as_use_directory(
+        &self,
+        source: UseSource,
+        target_path: CapabilityPath,
+        rights: fio2::Operations,
+        subdir: Option<PathBuf>,
+    ) -> Option<UseDirectoryDecl> {
+        match self {
+            Capability::Directory(source_path) => Some(UseDirectoryDecl {
+                source,
+                source_path: source_path.clone(),
+                target_path,
+                rights,
+                subdir,
+            }),
+            _ => None,
+        }
+    }
+}
+
 /// A named capability.
 ///
 /// Unlike a `CapabilityPath`, a `CapabilityName` doesn't encode any form
@@ -1388,6 +1925,22 @@ impl NativeIntoFidl<Option<fsys::Ref>> for OfferStorageSource {
     }
 }
 
+impl OfferStorageDecl {
+    /// Returns the source this storage offer is routed from.
+    ///
+    /// Unlike the other `Offer*Decl` types, `OfferStorageDecl` isn't an enum over storage kinds --
+    /// it's a single struct with public `source`/`target` fields, so this is just a named
+    /// accessor rather than a helper for matching across variants.
+    pub fn source(&self) -> &OfferStorageSource {
+        &self.source
+    }
+
+    /// Returns the target this storage offer is routed to.
+    pub fn target(&self) -> &OfferTarget {
+        &self.target
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum OfferRunnerSource {
     Parent,
@@ -1514,6 +2067,258 @@ impl NativeIntoFidl<Option<fsys::Ref>> for OfferTarget {
     }
 }
 
+/// A unified, capability-kind-erased view of where a `Use`/`Expose`/`Offer` declaration sources
+/// its capability from. Each decl family has its own source enum (`UseSource`, `ExposeSource`,
+/// `OfferServiceSource`, `OfferStorageSource`, ...) because the set of valid sources differs by
+/// capability kind, but generic routing code that only cares whether the backing component is
+/// the parent, itself, a child, or the framework wants a single type to match on.
+///
+/// This has `Parent` rather than `Realm`, to match the naming the rest of this crate already
+/// uses (`UseSource::Parent`, `OfferEventSource::Parent`, ...) -- `Realm` doesn't appear
+/// elsewhere in cm_rust's source vocabulary. It also has no `Storage` or `Collection` variant:
+/// no decl family's source enum can itself be a storage capability or a collection -- those are
+/// offer/expose *targets* (see `OfferTarget`), not capability sources -- so there's nothing to
+/// map them from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CapabilitySource {
+    Parent,
+    Self_,
+    Framework,
+    Child(String),
+}
+
+impl From<UseSource> for CapabilitySource {
+    fn from(source: UseSource) -> Self {
+        match source {
+            UseSource::Parent => CapabilitySource::Parent,
+            UseSource::Framework => CapabilitySource::Framework,
+        }
+    }
+}
+
+impl From<ExposeSource> for CapabilitySource {
+    fn from(source: ExposeSource) -> Self {
+        match source {
+            ExposeSource::Self_ => CapabilitySource::Self_,
+            ExposeSource::Child(name) => CapabilitySource::Child(name),
+            ExposeSource::Framework => CapabilitySource::Framework,
+        }
+    }
+}
+
+impl From<ExposeServiceSource> for CapabilitySource {
+    fn from(source: ExposeServiceSource) -> Self {
+        match source {
+            ExposeServiceSource::Framework => CapabilitySource::Framework,
+            ExposeServiceSource::Self_ => CapabilitySource::Self_,
+            ExposeServiceSource::Child(name) => CapabilitySource::Child(name),
+        }
+    }
+}
+
+impl From<OfferServiceSource> for CapabilitySource {
+    fn from(source: OfferServiceSource) -> Self {
+        match source {
+            OfferServiceSource::Parent => CapabilitySource::Parent,
+            OfferServiceSource::Self_ => CapabilitySource::Self_,
+            OfferServiceSource::Child(name) => CapabilitySource::Child(name),
+        }
+    }
+}
+
+impl From<OfferDirectorySource> for CapabilitySource {
+    fn from(source: OfferDirectorySource) -> Self {
+        match source {
+            OfferDirectorySource::Parent => CapabilitySource::Parent,
+            OfferDirectorySource::Self_ => CapabilitySource::Self_,
+            OfferDirectorySource::Framework => CapabilitySource::Framework,
+            OfferDirectorySource::Child(name) => CapabilitySource::Child(name),
+        }
+    }
+}
+
+impl From<OfferStorageSource> for CapabilitySource {
+    fn from(source: OfferStorageSource) -> Self {
+        match source {
+            OfferStorageSource::Parent => CapabilitySource::Parent,
+            OfferStorageSource::Self_ => CapabilitySource::Self_,
+        }
+    }
+}
+
+impl From<OfferRunnerSource> for CapabilitySource {
+    fn from(source: OfferRunnerSource) -> Self {
+        match source {
+            OfferRunnerSource::Parent => CapabilitySource::Parent,
+            OfferRunnerSource::Self_ => CapabilitySource::Self_,
+            OfferRunnerSource::Child(name) => CapabilitySource::Child(name),
+        }
+    }
+}
+
+impl From<OfferResolverSource> for CapabilitySource {
+    fn from(source: OfferResolverSource) -> Self {
+        match source {
+            OfferResolverSource::Parent => CapabilitySource::Parent,
+            OfferResolverSource::Self_ => CapabilitySource::Self_,
+            OfferResolverSource::Child(name) => CapabilitySource::Child(name),
+        }
+    }
+}
+
+impl From<OfferEventSource> for CapabilitySource {
+    fn from(source: OfferEventSource) -> Self {
+        match source {
+            OfferEventSource::Framework => CapabilitySource::Framework,
+            OfferEventSource::Parent => CapabilitySource::Parent,
+        }
+    }
+}
+
+impl UseDecl {
+    /// Returns the source this use declares its capability comes from, in the unified
+    /// `CapabilitySource` form. `Storage`, `Runner`, and `EventStream` uses don't carry a source
+    /// ref of their own (a storage use only names which storage capability it wants, and that
+    /// capability's own declaration is what has a source), so they return `None`.
+    pub fn capability_source(&self) -> Option<CapabilitySource> {
+        match self {
+            UseDecl::Service(d) => Some(d.source.clone().into()),
+            UseDecl::Protocol(d) => Some(d.source.clone().into()),
+            UseDecl::Directory(d) => Some(d.source.clone().into()),
+            UseDecl::Event(d) => Some(d.source.clone().into()),
+            UseDecl::Storage(_) | UseDecl::Runner(_) | UseDecl::EventStream(_) => None,
+        }
+    }
+}
+
+impl ExposeDecl {
+    /// Returns the sources this expose declares its capability comes from, in the unified
+    /// `CapabilitySource` form. Plural because `ExposeServiceDecl` alone can aggregate a
+    /// service from more than one source at once; every other variant returns a single-element
+    /// `Vec`.
+    pub fn capability_sources(&self) -> Vec<CapabilitySource> {
+        match self {
+            ExposeDecl::Service(d) => {
+                d.sources.iter().map(|s| s.source.clone().into()).collect()
+            }
+            ExposeDecl::Protocol(d) => vec![d.source.clone().into()],
+            ExposeDecl::Directory(d) => vec![d.source.clone().into()],
+            ExposeDecl::Runner(d) => vec![d.source.clone().into()],
+            ExposeDecl::Resolver(d) => vec![d.source.clone().into()],
+        }
+    }
+}
+
+impl OfferDecl {
+    /// Returns the sources this offer declares its capability comes from, in the unified
+    /// `CapabilitySource` form. Plural because `OfferServiceDecl` alone can aggregate a service
+    /// from more than one source at once; every other variant returns a single-element `Vec`.
+    pub fn capability_sources(&self) -> Vec<CapabilitySource> {
+        match self {
+            OfferDecl::Service(d) => {
+                d.sources.iter().map(|s| s.source.clone().into()).collect()
+            }
+            OfferDecl::Protocol(d) => vec![d.source.clone().into()],
+            OfferDecl::Directory(d) => vec![d.source.clone().into()],
+            OfferDecl::Storage(d) => vec![d.source.clone().into()],
+            OfferDecl::Runner(d) => vec![d.source.clone().into()],
+            OfferDecl::Resolver(d) => vec![d.source.clone().into()],
+            OfferDecl::Event(d) => vec![d.source.clone().into()],
+        }
+    }
+}
+
+impl ComponentDecl {
+    /// Returns the [CapabilitySource] of every offer or expose in this realm's manifest whose
+    /// target matches `use_decl`'s capability, for a `use_decl` sourced from the parent (i.e. one
+    /// of this realm's children `use`s a capability routed through `self`).
+    ///
+    /// This only makes sense for a `use_decl` with `UseSource::Parent`: a use sourced from the
+    /// framework or self has no offer/expose to trace, so those return an empty `Vec`, as do
+    /// `Storage`, `Runner`, and `EventStream` uses, which don't carry a routable capability
+    /// path/name of their own (mirrors [UseDecl::capability_source]).
+    ///
+    /// Matching is purely by capability path/name, since `use_decl` alone doesn't identify which
+    /// child declared it; if more than one offer or expose targets the same path/name (which
+    /// `cm_fidl_validator` would normally reject as a duplicate target), every match is returned.
+    pub fn providers_for_use(&self, use_decl: &UseDecl) -> Vec<CapabilitySource> {
+        match use_decl {
+            UseDecl::Service(UseServiceDecl { source: UseSource::Parent, source_name, .. }) => self
+                .offer_and_expose_service_sources(source_name),
+            UseDecl::Protocol(UseProtocolDecl { source: UseSource::Parent, source_path, .. }) => {
+                self.offer_and_expose_protocol_sources(source_path)
+            }
+            UseDecl::Directory(UseDirectoryDecl { source: UseSource::Parent, source_path, .. }) => {
+                self.offer_and_expose_directory_sources(source_path)
+            }
+            _ => vec![],
+        }
+    }
+
+    fn offer_and_expose_service_sources(&self, name: &CapabilityName) -> Vec<CapabilitySource> {
+        let mut sources = vec![];
+        for offer in &self.offers {
+            if let OfferDecl::Service(OfferServiceDecl { target_name, .. }) = offer {
+                if target_name == name {
+                    sources.extend(offer.capability_sources());
+                }
+            }
+        }
+        for expose in &self.exposes {
+            if let ExposeDecl::Service(ExposeServiceDecl { target_name, .. }) = expose {
+                if target_name == name {
+                    sources.extend(expose.capability_sources());
+                }
+            }
+        }
+        sources
+    }
+
+    fn offer_and_expose_protocol_sources(
+        &self,
+        path: &CapabilityNameOrPath,
+    ) -> Vec<CapabilitySource> {
+        let mut sources = vec![];
+        for offer in &self.offers {
+            if let OfferDecl::Protocol(OfferProtocolDecl { target_path, .. }) = offer {
+                if target_path == path {
+                    sources.extend(offer.capability_sources());
+                }
+            }
+        }
+        for expose in &self.exposes {
+            if let ExposeDecl::Protocol(ExposeProtocolDecl { target_path, .. }) = expose {
+                if target_path == path {
+                    sources.extend(expose.capability_sources());
+                }
+            }
+        }
+        sources
+    }
+
+    fn offer_and_expose_directory_sources(
+        &self,
+        path: &CapabilityNameOrPath,
+    ) -> Vec<CapabilitySource> {
+        let mut sources = vec![];
+        for offer in &self.offers {
+            if let OfferDecl::Directory(OfferDirectoryDecl { target_path, .. }) = offer {
+                if target_path == path {
+                    sources.extend(offer.capability_sources());
+                }
+            }
+        }
+        for expose in &self.exposes {
+            if let ExposeDecl::Directory(ExposeDirectoryDecl { target_path, .. }) = expose {
+                if target_path == path {
+                    sources.extend(expose.capability_sources());
+                }
+            }
+        }
+        sources
+    }
+}
+
 /// Converts the contents of a CM-FIDL declaration and produces the equivalent CM-Rust
 /// struct.
 /// This function applies cm_fidl_validator to check correctness.
@@ -1544,6 +2349,105 @@ pub enum Error {
     },
     #[error("Invalid capability path: {}", raw)]
     InvalidCapabilityPath { raw: String },
+    #[cfg(feature = "host")]
+    #[error("Failed to read \"{}\": {}", path.display(), err)]
+    CmFileRead {
+        path: PathBuf,
+        #[source]
+        err: std::io::Error,
+    },
+    #[cfg(feature = "host")]
+    #[error("Failed to decode \"{}\" as a persistent-FIDL ComponentDecl: {}", path.display(), err)]
+    CmDecode {
+        path: PathBuf,
+        #[source]
+        err: fidl::Error,
+    },
+    #[cfg(feature = "host")]
+    #[error("Failed to decode bytes as a persistent-FIDL ComponentDecl: {}", err)]
+    CmDecodeBytes {
+        #[source]
+        err: fidl::Error,
+    },
+    #[cfg(feature = "host")]
+    #[error("Failed to encode ComponentDecl as persistent FIDL: {}", err)]
+    CmEncode {
+        #[source]
+        err: fidl::Error,
+    },
+}
+
+#[cfg(feature = "host")]
+impl ComponentDecl {
+    /// Reads a `.cm` file at `path`, which holds a component declaration encoded as persistent
+    /// FIDL, and returns the corresponding native [ComponentDecl]. Intended for host-side tools
+    /// (e.g. scrutiny, build-time validators) that don't have a component_manager-style resolver
+    /// to decode these for them.
+    pub fn from_cm_file(path: &std::path::Path) -> Result<ComponentDecl, Error> {
+        let bytes = std::fs::read(path)
+            .map_err(|err| Error::CmFileRead { path: path.to_owned(), err })?;
+        let fidl_decl: fsys::ComponentDecl = fidl::encoding::decode_persistent(&bytes)
+            .map_err(|err| Error::CmDecode { path: path.to_owned(), err })?;
+        ComponentDecl::try_from(fidl_decl)
+    }
+
+    /// Decodes `bytes`, a component declaration encoded as persistent FIDL (e.g. the contents of
+    /// a `.cm` file), into the corresponding native [ComponentDecl]. Complements
+    /// [ComponentDecl::to_cm_bytes]; unlike [ComponentDecl::from_cm_file], this doesn't touch the
+    /// filesystem, for callers that already have the bytes in hand.
+    pub fn from_cm_bytes(bytes: &[u8]) -> Result<ComponentDecl, Error> {
+        let fidl_decl: fsys::ComponentDecl = fidl::encoding::decode_persistent(bytes)
+            .map_err(|err| Error::CmDecodeBytes { err })?;
+        ComponentDecl::try_from(fidl_decl)
+    }
+
+    /// Converts this declaration to a `fsys::ComponentDecl` and encodes it as persistent FIDL,
+    /// suitable for writing out as the contents of a `.cm` file. Complements
+    /// [ComponentDecl::from_cm_file]/[ComponentDecl::from_cm_bytes], which decode it back.
+    pub fn to_cm_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut fidl_decl: fsys::ComponentDecl = self.clone().try_into()?;
+        fidl::encoding::encode_persistent(&mut fidl_decl).map_err(|err| Error::CmEncode { err })
+    }
+}
+
+/// Validates every `*.cm` file directly inside `dir`, for use as a CI gate over a build's output
+/// directory. Returns the set of files that failed, each mapped to its own error.
+///
+/// This returns `cm_rust::Error` rather than `cm_fidl_validator::ErrorList` per file: a `.cm`
+/// file can fail to read or fail to decode before validation ever runs, and those failures
+/// aren't validator errors, so they can't be represented as (or synthesized into) an `ErrorList`
+/// -- `cm_rust::Error`, which `from_cm_file` already returns, is the type that actually covers
+/// everything that can go wrong for one file on this path.
+#[cfg(feature = "host")]
+pub fn validate_directory(dir: &std::path::Path) -> Result<(), HashMap<PathBuf, Error>> {
+    let mut failures = HashMap::new();
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            failures.insert(dir.to_owned(), Error::CmFileRead { path: dir.to_owned(), err });
+            return Err(failures);
+        }
+    };
+    for entry in entries {
+        let path = match entry {
+            Ok(entry) => entry.path(),
+            Err(err) => {
+                failures.insert(dir.to_owned(), Error::CmFileRead { path: dir.to_owned(), err });
+                continue;
+            }
+        };
+        if path.extension().and_then(|ext| ext.to_str()) != Some("cm") {
+            continue;
+        }
+        if let Err(err) = ComponentDecl::from_cm_file(&path) {
+            failures.insert(path, err);
+        }
+    }
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures)
+    }
 }
 
 #[cfg(test)]
@@ -2277,6 +3181,479 @@ mod tests {
         },
     }
 
+    #[test]
+    fn test_use_decl_target_path() {
+        let path: CapabilityPath = "/svc/foo".try_into().unwrap();
+        let cases: Vec<(UseDecl, Option<&CapabilityPath>)> = vec![
+            (
+                UseDecl::Service(UseServiceDecl {
+                    source: UseSource::Parent,
+                    source_name: "foo".into(),
+                    target_path: path.clone(),
+                }),
+                Some(&path),
+            ),
+            (
+                UseDecl::Protocol(UseProtocolDecl {
+                    source: UseSource::Parent,
+                    source_path: "foo".try_into().unwrap(),
+                    target_path: path.clone(),
+                }),
+                Some(&path),
+            ),
+            (
+                UseDecl::Directory(UseDirectoryDecl {
+                    source: UseSource::Parent,
+                    source_path: "foo".try_into().unwrap(),
+                    target_path: path.clone(),
+                    rights: fio2::Operations::Connect,
+                    subdir: None,
+                }),
+                Some(&path),
+            ),
+            (
+                UseDecl::Storage(UseStorageDecl {
+                    source_name: "data".into(),
+                    target_path: path.clone(),
+                }),
+                Some(&path),
+            ),
+            (
+                UseDecl::Runner(UseRunnerDecl { source_name: "elf".into() }),
+                None,
+            ),
+            (
+                UseDecl::Event(UseEventDecl {
+                    source: UseSource::Framework,
+                    source_name: "started".into(),
+                    target_name: "started".into(),
+                    filter: None,
+                }),
+                None,
+            ),
+        ];
+        for (use_, expected) in &cases {
+            assert_eq!(use_.target_path(), *expected);
+            assert_eq!(use_.target_path(), use_.path());
+        }
+    }
+
+    #[test]
+    fn test_component_decl_hash_stable_across_list_order() {
+        let child_a = ChildDecl {
+            name: "a".to_string(),
+            url: "fuchsia-pkg://fuchsia.com/a#meta/a.cm".to_string(),
+            startup: fsys::StartupMode::Lazy,
+            environment: None,
+        };
+        let child_b = ChildDecl {
+            name: "b".to_string(),
+            url: "fuchsia-pkg://fuchsia.com/b#meta/b.cm".to_string(),
+            startup: fsys::StartupMode::Lazy,
+            environment: None,
+        };
+        let use_foo = UseDecl::Runner(UseRunnerDecl { source_name: "foo".into() });
+        let use_bar = UseDecl::Runner(UseRunnerDecl { source_name: "bar".into() });
+
+        let decl1 = ComponentDecl {
+            children: vec![child_a.clone(), child_b.clone()],
+            uses: vec![use_foo.clone(), use_bar.clone()],
+            ..Default::default()
+        };
+        let decl2 = ComponentDecl {
+            children: vec![child_b, child_a],
+            uses: vec![use_bar, use_foo],
+            ..Default::default()
+        };
+        assert_eq!(component_decl_hash(&decl1), component_decl_hash(&decl2));
+
+        let decl3 = ComponentDecl {
+            uses: vec![UseDecl::Runner(UseRunnerDecl { source_name: "baz".into() })],
+            ..Default::default()
+        };
+        assert_ne!(component_decl_hash(&decl1), component_decl_hash(&decl3));
+    }
+
+    #[test]
+    fn test_find_expose_to_framework() {
+        let decl = ComponentDecl {
+            exposes: vec![
+                ExposeDecl::Protocol(ExposeProtocolDecl {
+                    source: ExposeSource::Self_,
+                    source_path: "/svc/to_parent".try_into().unwrap(),
+                    target_path: "/svc/to_parent".try_into().unwrap(),
+                    target: ExposeTarget::Parent,
+                }),
+                ExposeDecl::Protocol(ExposeProtocolDecl {
+                    source: ExposeSource::Self_,
+                    source_path: "/svc/to_framework".try_into().unwrap(),
+                    target_path: "/svc/to_framework".try_into().unwrap(),
+                    target: ExposeTarget::Framework,
+                }),
+                ExposeDecl::Runner(ExposeRunnerDecl {
+                    source: ExposeSource::Self_,
+                    source_name: "elf".try_into().unwrap(),
+                    target: ExposeTarget::Framework,
+                    target_name: "elf".try_into().unwrap(),
+                }),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            decl.find_expose_to_framework(&"to_framework".into()),
+            Some(&decl.exposes[1])
+        );
+        assert_eq!(decl.find_expose_to_framework(&"elf".into()), Some(&decl.exposes[2]));
+        // Exposed to the parent, not the framework, so it shouldn't match.
+        assert_eq!(decl.find_expose_to_framework(&"to_parent".into()), None);
+        assert_eq!(decl.find_expose_to_framework(&"nonexistent".into()), None);
+    }
+
+    #[test]
+    fn test_exposed_paths() {
+        let decl = ComponentDecl {
+            exposes: vec![
+                ExposeDecl::Protocol(ExposeProtocolDecl {
+                    source: ExposeSource::Self_,
+                    source_path: "/svc/logger".try_into().unwrap(),
+                    target_path: "/svc/logger".try_into().unwrap(),
+                    target: ExposeTarget::Parent,
+                }),
+                ExposeDecl::Directory(ExposeDirectoryDecl {
+                    source: ExposeSource::Self_,
+                    source_path: "/data".try_into().unwrap(),
+                    target_path: "/data".try_into().unwrap(),
+                    target: ExposeTarget::Parent,
+                    rights: None,
+                    subdir: None,
+                }),
+                ExposeDecl::Protocol(ExposeProtocolDecl {
+                    source: ExposeSource::Self_,
+                    source_path: "/svc/to_framework".try_into().unwrap(),
+                    target_path: "/svc/to_framework".try_into().unwrap(),
+                    target: ExposeTarget::Framework,
+                }),
+                // Name-based capabilities carry no path, so they're never in the result.
+                ExposeDecl::Runner(ExposeRunnerDecl {
+                    source: ExposeSource::Self_,
+                    source_name: "elf".try_into().unwrap(),
+                    target: ExposeTarget::Parent,
+                    target_name: "elf".try_into().unwrap(),
+                }),
+            ],
+            ..Default::default()
+        };
+
+        let logger_path: CapabilityPath = "/svc/logger".try_into().unwrap();
+        let data_path: CapabilityPath = "/data".try_into().unwrap();
+        let to_framework_path: CapabilityPath = "/svc/to_framework".try_into().unwrap();
+
+        assert_eq!(
+            decl.exposed_paths(),
+            vec![&logger_path, &data_path, &to_framework_path]
+        );
+        assert_eq!(
+            decl.exposed_paths_filtered(Some(ExposeTarget::Parent)),
+            vec![&logger_path, &data_path]
+        );
+        assert_eq!(
+            decl.exposed_paths_filtered(Some(ExposeTarget::Framework)),
+            vec![&to_framework_path]
+        );
+    }
+
+    #[test]
+    fn test_explain_offer_lookup() {
+        let decl = ComponentDecl {
+            offers: vec![
+                // Near-miss: targets the right child, but offers a different protocol.
+                OfferDecl::Protocol(OfferProtocolDecl {
+                    source: OfferServiceSource::Parent,
+                    source_path: "/svc/other".try_into().unwrap(),
+                    target: OfferTarget::Child("logger".to_string()),
+                    target_path: "/svc/other".try_into().unwrap(),
+                    dependency_type: DependencyType::Strong,
+                }),
+                // Near-miss: offers the right protocol, but to a different child.
+                OfferDecl::Protocol(OfferProtocolDecl {
+                    source: OfferServiceSource::Parent,
+                    source_path: "/svc/fuchsia.logger.Log".try_into().unwrap(),
+                    target: OfferTarget::Child("other_child".to_string()),
+                    target_path: "/svc/fuchsia.logger.Log".try_into().unwrap(),
+                    dependency_type: DependencyType::Strong,
+                }),
+                // Exact match.
+                OfferDecl::Protocol(OfferProtocolDecl {
+                    source: OfferServiceSource::Parent,
+                    source_path: "/svc/fuchsia.logger.Log".try_into().unwrap(),
+                    target: OfferTarget::Child("logger".to_string()),
+                    target_path: "/svc/fuchsia.logger.Log".try_into().unwrap(),
+                    dependency_type: DependencyType::Strong,
+                }),
+            ],
+            ..Default::default()
+        };
+
+        let capability: CapabilityNameOrPath = "/svc/fuchsia.logger.Log".try_into().unwrap();
+        let explanation = decl.explain_offer_lookup(&capability, "logger", None);
+
+        let lines: Vec<&str> = explanation.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("offers a different capability"));
+        assert!(lines[1].contains("not"));
+        assert!(lines[2].contains("offers the requested capability"));
+    }
+
+    #[test]
+    fn test_explain_offer_lookup_no_offers() {
+        let decl = ComponentDecl::default();
+        assert_eq!(
+            decl.explain_offer_lookup(&"/svc/foo".try_into().unwrap(), "logger", None),
+            "no offers are declared in this component's manifest"
+        );
+    }
+
+    #[test]
+    fn test_capability_source_from_use_sources() {
+        assert_eq!(CapabilitySource::from(UseSource::Parent), CapabilitySource::Parent);
+        assert_eq!(CapabilitySource::from(UseSource::Framework), CapabilitySource::Framework);
+    }
+
+    #[test]
+    fn test_capability_source_from_expose_sources() {
+        assert_eq!(CapabilitySource::from(ExposeSource::Self_), CapabilitySource::Self_);
+        assert_eq!(CapabilitySource::from(ExposeSource::Framework), CapabilitySource::Framework);
+        assert_eq!(
+            CapabilitySource::from(ExposeSource::Child("logger".to_string())),
+            CapabilitySource::Child("logger".to_string())
+        );
+        assert_eq!(
+            CapabilitySource::from(ExposeServiceSource::Child("logger".to_string())),
+            CapabilitySource::Child("logger".to_string())
+        );
+    }
+
+    #[test]
+    fn test_capability_source_from_offer_sources() {
+        assert_eq!(CapabilitySource::from(OfferServiceSource::Parent), CapabilitySource::Parent);
+        assert_eq!(
+            CapabilitySource::from(OfferDirectorySource::Framework),
+            CapabilitySource::Framework
+        );
+        assert_eq!(CapabilitySource::from(OfferStorageSource::Self_), CapabilitySource::Self_);
+        assert_eq!(
+            CapabilitySource::from(OfferRunnerSource::Child("logger".to_string())),
+            CapabilitySource::Child("logger".to_string())
+        );
+        assert_eq!(
+            CapabilitySource::from(OfferResolverSource::Child("logger".to_string())),
+            CapabilitySource::Child("logger".to_string())
+        );
+        assert_eq!(CapabilitySource::from(OfferEventSource::Parent), CapabilitySource::Parent);
+    }
+
+    #[test]
+    fn test_use_decl_capability_source() {
+        let use_protocol = UseDecl::Protocol(UseProtocolDecl {
+            source: UseSource::Parent,
+            source_path: "/svc/foo".try_into().unwrap(),
+            target_path: "/svc/foo".try_into().unwrap(),
+        });
+        assert_eq!(use_protocol.capability_source(), Some(CapabilitySource::Parent));
+
+        let use_storage = UseDecl::Storage(UseStorageDecl {
+            source_name: "data".into(),
+            target_path: "/data".try_into().unwrap(),
+        });
+        assert_eq!(use_storage.capability_source(), None);
+    }
+
+    #[test]
+    fn test_offer_decl_capability_sources() {
+        let offer_protocol = OfferDecl::Protocol(OfferProtocolDecl {
+            source: OfferServiceSource::Self_,
+            source_path: "/svc/foo".try_into().unwrap(),
+            target: OfferTarget::Child("logger".to_string()),
+            target_path: "/svc/foo".try_into().unwrap(),
+            dependency_type: DependencyType::Strong,
+        });
+        assert_eq!(offer_protocol.capability_sources(), vec![CapabilitySource::Self_]);
+
+        let offer_service = OfferDecl::Service(OfferServiceDecl {
+            sources: vec![
+                ServiceSource { source: OfferServiceSource::Parent, source_name: "a".into() },
+                ServiceSource {
+                    source: OfferServiceSource::Child("logger".to_string()),
+                    source_name: "b".into(),
+                },
+            ],
+            target: OfferTarget::Child("echo".to_string()),
+            target_name: "combined".into(),
+        });
+        assert_eq!(
+            offer_service.capability_sources(),
+            vec![CapabilitySource::Parent, CapabilitySource::Child("logger".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_providers_for_use() {
+        let mut decl = ComponentDecl::default();
+        decl.offers = vec![
+            OfferDecl::Protocol(OfferProtocolDecl {
+                source: OfferServiceSource::Child("a".to_string()),
+                source_path: "/svc/foo".try_into().unwrap(),
+                target: OfferTarget::Child("target".to_string()),
+                target_path: "/svc/foo".try_into().unwrap(),
+                dependency_type: DependencyType::Strong,
+            }),
+            OfferDecl::Protocol(OfferProtocolDecl {
+                source: OfferServiceSource::Self_,
+                source_path: "/svc/bar".try_into().unwrap(),
+                target: OfferTarget::Collection("coll".to_string()),
+                target_path: "/svc/unrelated".try_into().unwrap(),
+                dependency_type: DependencyType::Strong,
+            }),
+        ];
+        decl.exposes = vec![ExposeDecl::Protocol(ExposeProtocolDecl {
+            source: ExposeSource::Child("b".to_string()),
+            source_path: "/svc/foo".try_into().unwrap(),
+            target: ExposeTarget::Parent,
+            target_path: "/svc/foo".try_into().unwrap(),
+        })];
+
+        let use_decl = UseDecl::Protocol(UseProtocolDecl {
+            source: UseSource::Parent,
+            source_path: "/svc/foo".try_into().unwrap(),
+            target_path: "/svc/foo".try_into().unwrap(),
+        });
+        assert_eq!(
+            decl.providers_for_use(&use_decl),
+            vec![
+                CapabilitySource::Child("a".to_string()),
+                CapabilitySource::Child("b".to_string()),
+            ]
+        );
+
+        // A use sourced from the framework has no offer/expose to trace.
+        let use_decl = UseDecl::Protocol(UseProtocolDecl {
+            source: UseSource::Framework,
+            source_path: "/svc/foo".try_into().unwrap(),
+            target_path: "/svc/foo".try_into().unwrap(),
+        });
+        assert_eq!(decl.providers_for_use(&use_decl), vec![]);
+    }
+
+    #[test]
+    fn test_offer_storage_decl_source_and_target() {
+        let offer = OfferStorageDecl {
+            source_name: "cache".try_into().unwrap(),
+            source: OfferStorageSource::Self_,
+            target: OfferTarget::Collection("modular".to_string()),
+            target_name: "cache".try_into().unwrap(),
+        };
+        assert_eq!(offer.source(), &OfferStorageSource::Self_);
+        assert_eq!(offer.target(), &OfferTarget::Collection("modular".to_string()));
+
+        let offer = OfferStorageDecl {
+            source_name: "cache".try_into().unwrap(),
+            source: OfferStorageSource::Parent,
+            target: OfferTarget::Child("logger".to_string()),
+            target_name: "cache".try_into().unwrap(),
+        };
+        assert_eq!(offer.source(), &OfferStorageSource::Parent);
+        assert_eq!(offer.target(), &OfferTarget::Child("logger".to_string()));
+    }
+
+    #[test]
+    fn test_capability_path_raw_ignored_by_eq_and_hash() {
+        use std::collections::hash_map::DefaultHasher;
+
+        let parsed: CapabilityPath = "/svc/foo".try_into().unwrap();
+        let constructed = CapabilityPath {
+            dirname: "/svc".to_string(),
+            basename: "foo".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(parsed.raw(), Some("/svc/foo"));
+        assert_eq!(constructed.raw(), None);
+        assert_eq!(parsed, constructed);
+
+        let mut parsed_hasher = DefaultHasher::new();
+        parsed.hash(&mut parsed_hasher);
+        let mut constructed_hasher = DefaultHasher::new();
+        constructed.hash(&mut constructed_hasher);
+        assert_eq!(parsed_hasher.finish(), constructed_hasher.finish());
+    }
+
+    #[test]
+    fn test_capability_path_depth() {
+        let root: CapabilityPath = "/foo".try_into().unwrap();
+        assert_eq!(root.depth(), 1);
+
+        let nested: CapabilityPath = "/svc/fuchsia.foo.Bar".try_into().unwrap();
+        assert_eq!(nested.depth(), 2);
+
+        let deeply_nested: CapabilityPath = "/data/a/b/c".try_into().unwrap();
+        assert_eq!(deeply_nested.depth(), 4);
+    }
+
+    #[test]
+    fn test_capability_as_use_decl() {
+        let target_path: CapabilityPath = "/svc/foo".try_into().unwrap();
+
+        let service = Capability::Service(CapabilityNameOrPath::Name("foo".into()));
+        assert_eq!(
+            service.as_use_service(UseSource::Parent, target_path.clone()),
+            Some(UseServiceDecl {
+                source: UseSource::Parent,
+                source_name: "foo".into(),
+                target_path: target_path.clone(),
+            })
+        );
+        assert_eq!(service.as_use_protocol(UseSource::Parent, target_path.clone()), None);
+        assert_eq!(
+            service.as_use_directory(
+                UseSource::Parent,
+                target_path.clone(),
+                fio2::Operations::Connect,
+                None
+            ),
+            None
+        );
+
+        let protocol = Capability::Protocol(CapabilityNameOrPath::Path("/svc/bar".try_into().unwrap()));
+        assert_eq!(
+            protocol.as_use_protocol(UseSource::Parent, target_path.clone()),
+            Some(UseProtocolDecl {
+                source: UseSource::Parent,
+                source_path: CapabilityNameOrPath::Path("/svc/bar".try_into().unwrap()),
+                target_path: target_path.clone(),
+            })
+        );
+        assert_eq!(protocol.as_use_service(UseSource::Parent, target_path.clone()), None);
+
+        let directory = Capability::Directory(CapabilityNameOrPath::Path("/data".try_into().unwrap()));
+        assert_eq!(
+            directory.as_use_directory(
+                UseSource::Parent,
+                target_path.clone(),
+                fio2::Operations::Connect,
+                None
+            ),
+            Some(UseDirectoryDecl {
+                source: UseSource::Parent,
+                source_path: CapabilityNameOrPath::Path("/data".try_into().unwrap()),
+                target_path: target_path.clone(),
+                rights: fio2::Operations::Connect,
+                subdir: None,
+            })
+        );
+        assert_eq!(directory.as_use_protocol(UseSource::Parent, target_path.clone()), None);
+    }
+
     test_fidl_into_and_from! {
         fidl_into_and_from_expose_source => {
             input = vec![
@@ -2295,6 +3672,18 @@ mod tests {
             ],
             result_type = ExposeSource,
         },
+        fidl_into_and_from_expose_target => {
+            input = vec![
+                Some(fsys::Ref::Parent(fsys::ParentRef {})),
+                Some(fsys::Ref::Framework(fsys::FrameworkRef {})),
+            ],
+            input_type = Option<fsys::Ref>,
+            result = vec![
+                ExposeTarget::Parent,
+                ExposeTarget::Framework,
+            ],
+            result_type = ExposeTarget,
+        },
         fidl_into_and_from_offer_service_source => {
             input = vec![
                 Some(fsys::Ref::Parent(fsys::ParentRef {})),
@@ -2451,4 +3840,275 @@ mod tests {
             },
         },
     }
+
+    #[test]
+    fn test_rewrite_child_urls() {
+        let child = |name: &str, url: &str| ChildDecl {
+            name: name.to_string(),
+            url: url.to_string(),
+            startup: fsys::StartupMode::Lazy,
+            environment: None,
+        };
+        let mut decl = ComponentDecl {
+            children: vec![
+                child("a", "fuchsia-pkg://fuchsia.com/a#meta/a.cm"),
+                child("b", "fuchsia-pkg://fuchsia.com/b#meta/b.cm"),
+                // Doesn't match `from_host`, so left untouched.
+                child("c", "fuchsia-pkg://other.com/c#meta/c.cm"),
+            ],
+            ..Default::default()
+        };
+
+        let count = decl.rewrite_child_urls("fuchsia.com", "example.com");
+
+        assert_eq!(count, 2);
+        assert_eq!(decl.children[0].url, "fuchsia-pkg://example.com/a#meta/a.cm");
+        assert_eq!(decl.children[1].url, "fuchsia-pkg://example.com/b#meta/b.cm");
+        assert_eq!(decl.children[2].url, "fuchsia-pkg://other.com/c#meta/c.cm");
+    }
+
+    #[test]
+    fn test_storage_source_children() {
+        let storage = |name: &str, source_path: &str, source: StorageDirectorySource| {
+            CapabilityDecl::Storage(StorageDecl {
+                name: name.to_string(),
+                source_path: CapabilityNameOrPath::try_from(source_path).unwrap(),
+                source,
+                subdir: None,
+            })
+        };
+        let decl = ComponentDecl {
+            capabilities: vec![
+                storage("data", "/data", StorageDirectorySource::Child("foo".to_string())),
+                // Shares its source child with "data", so "foo" should only appear once.
+                storage("cache", "/cache", StorageDirectorySource::Child("foo".to_string())),
+                // Sourced from `self`, not a child, so it's excluded.
+                storage("tmp", "/tmp", StorageDirectorySource::Self_),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(decl.storage_source_children(), vec!["foo"]);
+    }
+
+    #[test]
+    fn test_to_pretty_string() {
+        let decl = ComponentDecl {
+            children: vec![ChildDecl {
+                name: "logger".to_string(),
+                url: "fuchsia-pkg://fuchsia.com/logger#meta/logger.cm".to_string(),
+                startup: fsys::StartupMode::Lazy,
+                environment: None,
+            }],
+            uses: vec![UseDecl::Protocol(UseProtocolDecl {
+                source: UseSource::Parent,
+                source_path: "/svc/fuchsia.logger.LogSink".try_into().unwrap(),
+                target_path: "/svc/fuchsia.logger.LogSink".try_into().unwrap(),
+            })],
+            offers: vec![OfferDecl::Protocol(OfferProtocolDecl {
+                source: OfferServiceSource::Self_,
+                source_path: "/svc/fuchsia.logger.LogSink".try_into().unwrap(),
+                target: OfferTarget::Child("logger".to_string()),
+                target_path: "/svc/fuchsia.logger.LogSink".try_into().unwrap(),
+                dependency_type: DependencyType::Strong,
+            })],
+            ..Default::default()
+        };
+
+        let expected = concat!(
+            "ComponentDecl {\n",
+            "  children:\n",
+            "    logger -> fuchsia-pkg://fuchsia.com/logger#meta/logger.cm (Lazy)\n",
+            "  uses:\n",
+            "    UseProtocolDecl /svc/fuchsia.logger.LogSink from Parent\n",
+            "  offers:\n",
+            "    OfferProtocolDecl /svc/fuchsia.logger.LogSink to Child(\"logger\") from Self_\n",
+            "}",
+        );
+        assert_eq!(decl.to_pretty_string(), expected);
+    }
+
+    #[test]
+    fn test_offers_by_target() {
+        let decl = ComponentDecl {
+            offers: vec![
+                OfferDecl::Protocol(OfferProtocolDecl {
+                    source: OfferServiceSource::Self_,
+                    source_path: "/svc/fuchsia.logger.LogSink".try_into().unwrap(),
+                    target: OfferTarget::Child("logger".to_string()),
+                    target_path: "/svc/fuchsia.logger.LogSink".try_into().unwrap(),
+                    dependency_type: DependencyType::Strong,
+                }),
+                OfferDecl::Directory(OfferDirectoryDecl {
+                    source: OfferDirectorySource::Self_,
+                    source_path: "/data/logs".try_into().unwrap(),
+                    target: OfferTarget::Child("logger".to_string()),
+                    target_path: "/data/logs".try_into().unwrap(),
+                    rights: None,
+                    subdir: None,
+                    dependency_type: DependencyType::Strong,
+                }),
+                OfferDecl::Protocol(OfferProtocolDecl {
+                    source: OfferServiceSource::Self_,
+                    source_path: "/svc/fuchsia.logger.LogSink".try_into().unwrap(),
+                    target: OfferTarget::Child("echo".to_string()),
+                    target_path: "/svc/fuchsia.logger.LogSink".try_into().unwrap(),
+                    dependency_type: DependencyType::Strong,
+                }),
+                OfferDecl::Protocol(OfferProtocolDecl {
+                    source: OfferServiceSource::Self_,
+                    source_path: "/svc/fuchsia.logger.LogSink".try_into().unwrap(),
+                    target: OfferTarget::Collection("modular".to_string()),
+                    target_path: "/svc/fuchsia.logger.LogSink".try_into().unwrap(),
+                    dependency_type: DependencyType::Strong,
+                }),
+            ],
+            ..Default::default()
+        };
+
+        let by_target = decl.offers_by_target();
+        assert_eq!(by_target.len(), 3);
+        assert_eq!(by_target[&OfferTarget::Child("logger".to_string())].len(), 2);
+        assert_eq!(by_target[&OfferTarget::Child("echo".to_string())].len(), 1);
+        assert_eq!(by_target[&OfferTarget::Collection("modular".to_string())].len(), 1);
+    }
+
+    // Real `.cm` fixtures are produced by the `cmc` build-time compiler, which isn't available to
+    // this unit test; instead, this builds the fixture's bytes the same way `cmc` would (via
+    // fidl::encoding::encode_persistent), writes them to a temp file, and round-trips that through
+    // `ComponentDecl::from_cm_file()`.
+    #[cfg(feature = "host")]
+    #[test]
+    fn test_from_cm_file() {
+        use std::io::Write;
+
+        let mut fidl_decl = fsys::ComponentDecl {
+            program: None,
+            uses: None,
+            exposes: None,
+            offers: None,
+            capabilities: None,
+            children: None,
+            collections: None,
+            facets: None,
+            environments: None,
+        };
+        let bytes = fidl::encoding::encode_persistent(&mut fidl_decl).expect("encode failed");
+
+        let mut fixture = tempfile::NamedTempFile::new().expect("failed to create temp file");
+        fixture.write_all(&bytes).expect("failed to write fixture");
+
+        let decl = ComponentDecl::from_cm_file(fixture.path()).expect("from_cm_file failed");
+        assert_eq!(decl, ComponentDecl::default());
+    }
+
+    #[cfg(feature = "host")]
+    #[test]
+    fn test_to_cm_bytes_round_trips_through_from_cm_bytes() {
+        let decl = ComponentDecl {
+            uses: vec![UseDecl::Protocol(UseProtocolDecl {
+                source: UseSource::Parent,
+                source_path: "/svc/fuchsia.logger.LogSink".try_into().unwrap(),
+                target_path: "/svc/fuchsia.logger.LogSink".try_into().unwrap(),
+            })],
+            exposes: vec![ExposeDecl::Protocol(ExposeProtocolDecl {
+                source: ExposeSource::Self_,
+                source_path: "/svc/foo".try_into().unwrap(),
+                target_path: "/svc/foo".try_into().unwrap(),
+                target: ExposeTarget::Parent,
+            })],
+            offers: vec![OfferDecl::Protocol(OfferProtocolDecl {
+                source: OfferServiceSource::Self_,
+                source_path: "/svc/bar".try_into().unwrap(),
+                target: OfferTarget::Child("logger".to_string()),
+                target_path: "/svc/bar".try_into().unwrap(),
+                dependency_type: DependencyType::Strong,
+            })],
+            children: vec![ChildDecl {
+                name: "logger".to_string(),
+                url: "fuchsia-pkg://fuchsia.com/logger#meta/logger.cm".to_string(),
+                startup: fsys::StartupMode::Lazy,
+                environment: None,
+            }],
+            collections: vec![CollectionDecl {
+                name: "coll".to_string(),
+                durability: fsys::Durability::Transient,
+                environment: None,
+            }],
+            ..Default::default()
+        };
+
+        let bytes = decl.to_cm_bytes().expect("to_cm_bytes failed");
+        let round_tripped = ComponentDecl::from_cm_bytes(&bytes).expect("from_cm_bytes failed");
+        assert_eq!(decl, round_tripped);
+    }
+
+    #[test]
+    fn test_canonicalize_and_validate_collapses_dot_segments() {
+        let decl = ComponentDecl {
+            uses: vec![UseDecl::Protocol(UseProtocolDecl {
+                source: UseSource::Parent,
+                source_path: "/svc/./foo".try_into().unwrap(),
+                target_path: "/svc/./foo".try_into().unwrap(),
+            })],
+            ..Default::default()
+        };
+
+        let canonicalized =
+            decl.canonicalize_and_validate().expect("canonicalize_and_validate failed");
+        match &canonicalized.uses[0] {
+            UseDecl::Protocol(u) => {
+                assert_eq!(u.source_path, "/svc/foo".try_into().unwrap());
+                assert_eq!(u.target_path, "/svc/foo".try_into().unwrap());
+            }
+            other => panic!("unexpected use decl: {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "host")]
+    #[test]
+    fn test_from_cm_file_missing() {
+        let result = ComponentDecl::from_cm_file(std::path::Path::new("/nonexistent/foo.cm"));
+        match result {
+            Err(Error::CmFileRead { .. }) => {}
+            other => panic!("Expected Error::CmFileRead, got {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "host")]
+    #[test]
+    fn test_validate_directory() {
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+
+        let mut fidl_decl = fsys::ComponentDecl {
+            program: None,
+            uses: None,
+            exposes: None,
+            offers: None,
+            capabilities: None,
+            children: None,
+            collections: None,
+            facets: None,
+            environments: None,
+        };
+        let bytes = fidl::encoding::encode_persistent(&mut fidl_decl).expect("encode failed");
+        std::fs::write(dir.path().join("valid.cm"), &bytes).expect("failed to write fixture");
+
+        // Not valid persistent FIDL, so this fails to decode rather than to validate.
+        std::fs::write(dir.path().join("invalid.cm"), b"not a component declaration")
+            .expect("failed to write fixture");
+
+        // Not a `.cm` file, so `validate_directory` should ignore it rather than fail on it.
+        std::fs::write(dir.path().join("README.md"), b"not a manifest")
+            .expect("failed to write fixture");
+
+        let failures = validate_directory(dir.path()).expect_err("expected validation failures");
+        assert_eq!(failures.len(), 1);
+        match failures.get(&dir.path().join("invalid.cm")) {
+            Some(Error::CmDecode { .. }) => {}
+            other => panic!("Expected Error::CmDecode, got {:?}", other),
+        }
+    }
 }
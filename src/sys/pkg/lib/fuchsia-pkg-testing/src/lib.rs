@@ -23,4 +23,6 @@ mod system_image;
 pub use crate::system_image::SystemImageBuilder;
 
 mod update_package;
-pub use crate::update_package::{make_packages_json, TestUpdatePackage};
+pub use crate::update_package::{
+    make_packages_json, make_packages_json_with_version, TestUpdatePackage,
+};
@@ -74,8 +74,17 @@ impl std::ops::Deref for TestUpdatePackage {
 /// a `packages.json` representing those packages and returns the JSON as a
 /// string.
 pub fn make_packages_json<'a>(urls: impl AsRef<[&'a str]>) -> String {
+    make_packages_json_with_version(urls, json!("1"))
+}
+
+/// Like [`make_packages_json`], but allows the caller to control the `version` field, so tests
+/// can exercise both the integer and string forms (and unsupported versions).
+pub fn make_packages_json_with_version<'a>(
+    urls: impl AsRef<[&'a str]>,
+    version: serde_json::Value,
+) -> String {
     json!({
-      "version": "1",
+      "version": version,
       "content": urls.as_ref(),
     })
     .to_string()
@@ -8,7 +8,11 @@ use {
     fuchsia_zircon::{Status, Vmo},
     futures::prelude::*,
     parking_lot::Mutex,
-    std::{convert::TryInto, sync::Arc},
+    std::{
+        collections::{HashMap, HashSet},
+        convert::TryInto,
+        sync::Arc,
+    },
 };
 
 fn verify_and_read_buffer(buffer: &mut fidl_fuchsia_mem::Buffer) -> Vec<u8> {
@@ -49,7 +53,9 @@ pub struct MockPaverServiceBuilder {
     config_status_hook:
         Option<Box<dyn Fn(&PaverEvent) -> fidl_fuchsia_paver::ConfigurationStatus + Send + Sync>>,
     firmware_hook: Option<Box<dyn Fn(&PaverEvent) -> paver::WriteFirmwareResult + Send + Sync>>,
+    firmware_hook_per_type: HashMap<String, paver::WriteFirmwareResult>,
     read_hook: Option<Box<dyn Fn(&PaverEvent) -> Result<Vec<u8>, Status> + Send + Sync>>,
+    read_asset_responses: HashMap<(paver::Configuration, paver::Asset), Vec<u8>>,
     event_hook: Option<Box<dyn Fn(&PaverEvent) + Send + Sync>>,
     active_config: paver::Configuration,
     current_config: paver::Configuration,
@@ -62,7 +68,9 @@ impl MockPaverServiceBuilder {
             call_hook: None,
             config_status_hook: None,
             firmware_hook: None,
+            firmware_hook_per_type: HashMap::new(),
             read_hook: None,
+            read_asset_responses: HashMap::new(),
             event_hook: None,
             active_config: paver::Configuration::A,
             current_config: paver::Configuration::A,
@@ -94,6 +102,14 @@ impl MockPaverServiceBuilder {
         self
     }
 
+    /// Sets a fixed write-firmware result per firmware type, e.g. to make type "a" succeed while
+    /// type "b" is unsupported. A firmware type not present in `results` falls back to
+    /// [Self::firmware_hook] (or its default, if that wasn't set either).
+    pub fn firmware_hook_per_type(mut self, results: HashMap<String, paver::WriteFirmwareResult>) -> Self {
+        self.firmware_hook_per_type = results;
+        self
+    }
+
     pub fn read_hook<F>(mut self, read_hook: F) -> Self
     where
         F: Fn(&PaverEvent) -> Result<Vec<u8>, Status> + Send + Sync + 'static,
@@ -102,6 +118,20 @@ impl MockPaverServiceBuilder {
         self
     }
 
+    /// Configures `ReadAsset` to return `response` for the given `(configuration, asset)`,
+    /// without having to write a [Self::read_hook] closure. Takes priority over `read_hook` for
+    /// the pairs it configures; any `(configuration, asset)` not given a response here falls back
+    /// to `read_hook` (or its default).
+    pub fn read_asset_response(
+        mut self,
+        configuration: paver::Configuration,
+        asset: paver::Asset,
+        response: Vec<u8>,
+    ) -> Self {
+        self.read_asset_responses.insert((configuration, asset), response);
+        self
+    }
+
     // Provide a callback which will be called for every paver event.
     // Useful for logging or interaction assertions.
     pub fn event_hook<F>(mut self, event_hook: F) -> Self
@@ -140,10 +170,13 @@ impl MockPaverServiceBuilder {
 
         MockPaverService {
             events: Mutex::new(vec![]),
+            written_assets: Mutex::new(HashSet::new()),
             call_hook,
             config_status_hook,
             firmware_hook,
+            firmware_hook_per_type: self.firmware_hook_per_type,
             read_hook,
+            read_asset_responses: self.read_asset_responses,
             event_hook,
             active_config: self.active_config,
             current_config: self.current_config,
@@ -154,11 +187,14 @@ impl MockPaverServiceBuilder {
 
 pub struct MockPaverService {
     events: Mutex<Vec<PaverEvent>>,
+    written_assets: Mutex<HashSet<(paver::Configuration, paver::Asset)>>,
     call_hook: Box<dyn Fn(&PaverEvent) -> Status + Send + Sync>,
     config_status_hook:
         Box<dyn Fn(&PaverEvent) -> fidl_fuchsia_paver::ConfigurationStatus + Send + Sync>,
     firmware_hook: Box<dyn Fn(&PaverEvent) -> paver::WriteFirmwareResult + Send + Sync>,
+    firmware_hook_per_type: HashMap<String, paver::WriteFirmwareResult>,
     read_hook: Box<dyn Fn(&PaverEvent) -> Result<Vec<u8>, Status> + Send + Sync>,
+    read_asset_responses: HashMap<(paver::Configuration, paver::Asset), Vec<u8>>,
     event_hook: Box<dyn Fn(&PaverEvent) + Send + Sync>,
     active_config: paver::Configuration,
     current_config: paver::Configuration,
@@ -170,6 +206,13 @@ impl MockPaverService {
         std::mem::replace(&mut *self.events.lock(), vec![])
     }
 
+    /// Returns the (configuration, asset) pairs written so far, for asserting "these assets were
+    /// written" without caring about order or how many times each was written -- unlike
+    /// `take_events()`, this is not cleared, and accumulates for the life of the mock.
+    pub fn written_assets(&self) -> HashSet<(paver::Configuration, paver::Asset)> {
+        self.written_assets.lock().clone()
+    }
+
     /// Spawns a new task to serve the data sink protocol.
     pub fn spawn_data_sink_service(self: &Arc<Self>) -> paver::DataSinkProxy {
         let (proxy, stream) =
@@ -202,6 +245,9 @@ impl MockPaverService {
 
     fn push_event(self: &Arc<Self>, event: PaverEvent) {
         (*self.event_hook)(&event);
+        if let PaverEvent::WriteAsset { configuration, asset, .. } = &event {
+            self.written_assets.lock().insert((configuration.clone(), asset.clone()));
+        }
         self.events.lock().push(event);
     }
 
@@ -230,8 +276,13 @@ impl MockPaverService {
                     responder,
                 } => {
                     let payload = verify_and_read_buffer(&mut payload);
-                    let event = PaverEvent::WriteFirmware { configuration, firmware_type, payload };
-                    let mut result = (*self.firmware_hook)(&event);
+                    let event =
+                        PaverEvent::WriteFirmware { configuration, firmware_type: firmware_type.clone(), payload };
+                    let mut result = self
+                        .firmware_hook_per_type
+                        .get(&firmware_type)
+                        .cloned()
+                        .unwrap_or_else(|| (*self.firmware_hook)(&event));
                     self.push_event(event);
                     responder.send(&mut result).expect("paver response to send");
                 }
@@ -243,7 +294,12 @@ impl MockPaverService {
                 }
                 paver::DataSinkRequest::ReadAsset { configuration, asset, responder } => {
                     let event = PaverEvent::ReadAsset { configuration, asset };
-                    let mut result = (*self.read_hook)(&event)
+                    let mut result = self
+                        .read_asset_responses
+                        .get(&(configuration, asset))
+                        .cloned()
+                        .map(Ok)
+                        .unwrap_or_else(|| (*self.read_hook)(&event))
                         .map(|payload| {
                             let vmo = Vmo::create(payload.len() as u64).expect("Creating VMO");
                             vmo.write(&payload, 0).expect("writing to VMO");
@@ -388,6 +444,7 @@ pub mod tests {
         super::*,
         fidl_fuchsia_paver as paver,
         fuchsia_zircon::{self as zx, VmoOptions},
+        maplit::hashset,
         matches::assert_matches,
     };
 
@@ -472,6 +529,114 @@ pub mod tests {
         Ok(())
     }
 
+    #[fasync::run_singlethreaded(test)]
+    pub async fn test_written_assets() -> Result<(), Error> {
+        let paver = MockPaverForTest::new(|p| p);
+
+        async fn write_asset(
+            data_sink: &paver::DataSinkProxy,
+            configuration: paver::Configuration,
+            asset: paver::Asset,
+        ) {
+            let data = "hello there".as_bytes();
+            let vmo = Vmo::create_with_opts(VmoOptions::RESIZABLE, data.len() as u64)
+                .expect("Creating VMO");
+            vmo.write(data, 0).expect("writing to VMO");
+            data_sink
+                .write_asset(configuration, asset, &mut Buffer { vmo, size: data.len() as u64 })
+                .await
+                .expect("Writing asset");
+        }
+
+        write_asset(&paver.data_sink, paver::Configuration::B, paver::Asset::Kernel).await;
+        write_asset(&paver.data_sink, paver::Configuration::B, paver::Asset::VerifiedBootMetadata)
+            .await;
+
+        let written = paver.paver.written_assets();
+        assert_eq!(
+            written,
+            hashset! {
+                (paver::Configuration::B, paver::Asset::Kernel),
+                (paver::Configuration::B, paver::Asset::VerifiedBootMetadata),
+            }
+        );
+        assert!(!written.contains(&(paver::Configuration::A, paver::Asset::Kernel)));
+
+        // Unlike take_events(), written_assets() isn't cleared by reading it, and writing the same
+        // asset again doesn't duplicate its entry in the set.
+        write_asset(&paver.data_sink, paver::Configuration::B, paver::Asset::Kernel).await;
+        assert_eq!(paver.paver.written_assets(), written);
+
+        Ok(())
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    pub async fn test_read_asset_response() -> Result<(), Error> {
+        // Simulates an update flow that reads back the current asset to decide whether it
+        // already matches what it's about to write, and skips the write if so.
+        let current_kernel = b"current kernel".to_vec();
+        let paver = MockPaverForTest::new(|p| {
+            p.read_asset_response(
+                paver::Configuration::A,
+                paver::Asset::Kernel,
+                current_kernel.clone(),
+            )
+        });
+
+        let buffer = paver
+            .data_sink
+            .read_asset(paver::Configuration::A, paver::Asset::Kernel)
+            .await
+            .expect("read_asset to send")
+            .expect("read_asset to succeed");
+        assert_eq!(read_mem_buffer(&buffer), current_kernel);
+
+        let new_kernel = "new kernel".as_bytes();
+        if read_mem_buffer(&buffer) != new_kernel {
+            let vmo = Vmo::create_with_opts(VmoOptions::RESIZABLE, new_kernel.len() as u64)
+                .expect("Creating VMO");
+            vmo.write(new_kernel, 0).expect("writing to VMO");
+            paver
+                .data_sink
+                .write_asset(
+                    paver::Configuration::A,
+                    paver::Asset::Kernel,
+                    &mut Buffer { vmo, size: new_kernel.len() as u64 },
+                )
+                .await
+                .expect("Writing asset");
+        }
+
+        assert_eq!(
+            paver.paver.take_events(),
+            vec![
+                PaverEvent::ReadAsset {
+                    configuration: paver::Configuration::A,
+                    asset: paver::Asset::Kernel
+                },
+                PaverEvent::WriteAsset {
+                    configuration: paver::Configuration::A,
+                    asset: paver::Asset::Kernel,
+                    payload: new_kernel.to_vec(),
+                },
+            ]
+        );
+
+        // A (configuration, asset) without a configured response still falls back to the
+        // read_hook default.
+        assert_eq!(
+            paver
+                .data_sink
+                .read_asset(paver::Configuration::B, paver::Asset::Kernel)
+                .await
+                .expect("read_asset to send")
+                .map(|buffer| read_mem_buffer(&buffer)),
+            Ok(vec![])
+        );
+
+        Ok(())
+    }
+
     #[fasync::run_singlethreaded(test)]
     pub async fn test_hook() -> Result<(), Error> {
         let hook = |_: &PaverEvent| zx::Status::NOT_SUPPORTED;
@@ -8,19 +8,30 @@ use {
         AdminProxy, AdminRebootResult, AdminRequest, AdminRequestStream, RebootReason,
     },
     fuchsia_async::{self as fasync, futures::TryFutureExt, futures::TryStreamExt},
-    std::sync::Arc,
+    std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
 };
 
 pub struct MockRebootService {
     call_hook: Box<dyn Fn() -> AdminRebootResult + Send + Sync>,
+    reboot_count: AtomicU32,
 }
 
 impl MockRebootService {
     /// Creates a new MockRebootService with a given callback to run per call to the service.
     /// `call_hook` must return a `Result` for each call, which will be sent to
-    /// the caller as the result of the reboot call.
+    /// the caller as the result of the reboot call. Unlike a real reboot service, this mock
+    /// tolerates and records multiple reboot requests, since a real reboot request doesn't
+    /// actually terminate the caller in these tests.
     pub fn new(call_hook: Box<dyn Fn() -> AdminRebootResult + Send + Sync>) -> Self {
-        Self { call_hook }
+        Self { call_hook, reboot_count: AtomicU32::new(0) }
+    }
+
+    /// Returns the number of reboot requests this mock has served so far.
+    pub fn reboot_count(&self) -> u32 {
+        self.reboot_count.load(Ordering::SeqCst)
     }
 
     /// Serves only the reboot portion of the fuchsia.hardware.power.statecontrol protocol on the
@@ -36,6 +47,7 @@ impl MockRebootService {
                     // of which use RebootReason::SystemUpdate
                     assert_eq!(reason, RebootReason::SystemUpdate);
 
+                    self.reboot_count.fetch_add(1, Ordering::SeqCst);
                     let mut result = (self.call_hook)();
                     responder.send(&mut result)?;
                 }
@@ -70,7 +82,6 @@ mod tests {
     use super::*;
     use fuchsia_async as fasync;
     use fuchsia_zircon as zx;
-    use std::sync::atomic::{AtomicU32, Ordering};
 
     #[fasync::run_singlethreaded(test)]
     async fn test_mock_reboot() {
@@ -118,4 +129,21 @@ mod tests {
             .expect("reboot call succeeded");
         assert_eq!(called.load(Ordering::SeqCst), 1);
     }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn test_mock_reboot_records_repeated_reboots() {
+        let reboot_service = Arc::new(MockRebootService::new(Box::new(|| Ok(()))));
+
+        let reboot_service_clone = Arc::clone(&reboot_service);
+        let proxy = reboot_service_clone.spawn_reboot_service();
+
+        for expected_count in 1..=3 {
+            proxy
+                .reboot(RebootReason::SystemUpdate)
+                .await
+                .expect("made reboot call")
+                .expect("reboot call succeeded");
+            assert_eq!(reboot_service.reboot_count(), expected_count);
+        }
+    }
 }
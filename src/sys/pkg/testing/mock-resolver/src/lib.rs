@@ -18,7 +18,10 @@ use {
         collections::HashMap,
         fs::{self, create_dir},
         path::{Path, PathBuf},
-        sync::Arc,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
     },
     tempfile::TempDir,
 };
@@ -42,6 +45,25 @@ impl TestPackage {
         self
     }
 
+    /// Writes this package's `packages.json`, the list of package URLs the system updater
+    /// resolves as part of an update, in the only format `update-package`'s `packages()`
+    /// actually parses.
+    pub fn with_packages_json<'a>(self, urls: impl AsRef<[&'a str]>) -> Self {
+        let contents =
+            serde_json::json!({ "version": "1", "content": urls.as_ref() }).to_string();
+        self.add_file("packages.json", contents)
+    }
+
+    /// There is no legacy line-delimited `packages` format in this tree -- `update-package`'s
+    /// `packages()` only ever opens `packages.json` -- so this is kept as an alias of
+    /// [TestPackage::with_packages_json]. It exists so callers migrating off the ad-hoc
+    /// `.add_file("packages", ...)` calls scattered through older tests (which, despite the
+    /// filename, already wrote JSON content) have a direct replacement regardless of which
+    /// name they used.
+    pub fn with_packages_list<'a>(self, urls: impl AsRef<[&'a str]>) -> Self {
+        self.with_packages_json(urls)
+    }
+
     fn serve_on(&self, dir_request: ServerEnd<DirectoryMarker>) {
         // Connect to the backing directory which we'll proxy _most_ requests to.
         let (backing_dir_proxy, server_end) =
@@ -151,7 +173,13 @@ enum Expectation {
 /// opened as both a directory and a file.
 pub struct MockResolverService {
     expectations: Mutex<HashMap<String, Expectation>>,
+    // Keyed by the URL without its `?hash=` query parameter. Checked before `expectations` so a
+    // request carrying the wrong hash is rejected regardless of what outcome was registered for
+    // the (correctly-hashed) URL.
+    expected_hashes: Mutex<HashMap<String, String>>,
     resolve_hook: Box<dyn Fn(&str) + Send + Sync>,
+    unexpected_resolve_hook: Mutex<Box<dyn Fn(&str) + Send + Sync>>,
+    strict: AtomicBool,
     packages_dir: tempfile::TempDir,
 }
 
@@ -161,10 +189,27 @@ impl MockResolverService {
         Self {
             packages_dir,
             resolve_hook: resolve_hook.unwrap_or_else(|| Box::new(|_| ())),
+            unexpected_resolve_hook: Mutex::new(Box::new(|_| ())),
+            strict: AtomicBool::new(false),
             expectations: Mutex::new(HashMap::new()),
+            expected_hashes: Mutex::new(HashMap::new()),
         }
     }
 
+    /// When `strict` is true, resolve requests for URLs that were never registered with
+    /// [`MockResolverService::url`]/[`MockResolverService::register_package`] invoke the
+    /// unexpected-resolve hook (see [`MockResolverService::set_unexpected_resolve_hook`]) in
+    /// addition to being failed with `Status::NOT_FOUND` as usual.
+    pub fn set_strict(&self, strict: bool) {
+        self.strict.store(strict, Ordering::SeqCst);
+    }
+
+    /// Sets a hook to be called with the URL of any resolve request that arrives while in strict
+    /// mode for a URL that was never registered. See [`MockResolverService::set_strict`].
+    pub fn set_unexpected_resolve_hook(&self, hook: Box<dyn Fn(&str) + Send + Sync>) {
+        *self.unexpected_resolve_hook.lock() = hook;
+    }
+
     /// Consider using Self::package/Self::url instead to clarify the usage of these 4 str params.
     pub fn register_custom_package(
         &self,
@@ -191,6 +236,32 @@ impl MockResolverService {
         self.url(url).fail(response_status);
     }
 
+    /// Registers `result` for `url`, but first verifies that any resolve request for `url`
+    /// carries a `?hash=` query parameter matching `expected_merkle`. A request whose hash
+    /// doesn't match is failed with `Status::IO_DATA_INTEGRITY`, regardless of `result`, modeling
+    /// the content-address verification pkg_resolver performs for real before ever consulting
+    /// `result`.
+    ///
+    /// `url` must not itself carry a `?hash=` query parameter; the expected merkle is supplied
+    /// separately so it can be checked independently of whatever hash the caller's request
+    /// attaches.
+    pub fn mock_package_result_with_hash(
+        &self,
+        url: impl Into<String>,
+        expected_merkle: impl Into<String>,
+        result: Result<TestPackage, Status>,
+    ) {
+        let url = url.into();
+        let expected_merkle = expected_merkle.into();
+        self.expected_hashes.lock().insert(url.clone(), expected_merkle.clone());
+
+        let hashed_url = format!("{}?hash={}", url, expected_merkle);
+        match result {
+            Ok(pkg) => self.url(hashed_url).resolve(&pkg),
+            Err(status) => self.url(hashed_url).fail(status),
+        }
+    }
+
     /// Registers a package with the given name and merkle root, returning a handle to add files to
     /// the package.
     ///
@@ -272,9 +343,17 @@ impl MockResolverService {
 
         (*self.resolve_hook)(&package_url);
 
-        match self
-            .expectations
-            .lock()
+        if let Some(status) = self.hash_mismatch(&package_url) {
+            responder.send(&mut Err(status.into_raw()))?;
+            return Ok(());
+        }
+
+        let mut expectations = self.expectations.lock();
+        if self.strict.load(Ordering::SeqCst) && !expectations.contains_key(&package_url) {
+            (*self.unexpected_resolve_hook.lock())(&package_url);
+        }
+
+        match expectations
             .get_mut(&package_url)
             .unwrap_or(&mut Expectation::Immediate(Err(Status::NOT_FOUND)))
         {
@@ -292,6 +371,20 @@ impl MockResolverService {
         }
         Ok(())
     }
+
+    /// Returns `Some(Status::IO_DATA_INTEGRITY)` if `package_url` was registered via
+    /// [`MockResolverService::mock_package_result_with_hash`] and its `?hash=` query parameter
+    /// doesn't match the expected merkle. Returns `None` for URLs with no registered expectation,
+    /// leaving them to the usual `expectations` lookup.
+    fn hash_mismatch(&self, package_url: &str) -> Option<Status> {
+        let (base_url, query) = package_url.split_once('?')?;
+        let expected_merkle = self.expected_hashes.lock().get(base_url)?.clone();
+        let actual_merkle = query.split('&').find_map(|param| param.strip_prefix("hash="));
+        if actual_merkle != Some(expected_merkle.as_str()) {
+            return Some(Status::IO_DATA_INTEGRITY);
+        }
+        None
+    }
 }
 
 #[must_use]
@@ -463,4 +556,28 @@ mod tests {
         let first_pkg = first_fut.await.unwrap();
         assert_eq!(read_file(&first_pkg, "meta").await, "fake merkle");
     }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn mock_package_result_with_hash_rejects_mismatched_hash() {
+        let resolver = Arc::new(MockResolverService::new(None));
+        let pkg = resolver.package("update", "correct-merkle");
+        resolver.mock_package_result_with_hash(
+            "fuchsia-pkg://fuchsia.com/update",
+            "correct-merkle",
+            Ok(pkg),
+        );
+
+        let proxy = Arc::clone(&resolver).spawn_resolver_service();
+
+        assert_matches!(
+            do_resolve(&proxy, "fuchsia-pkg://fuchsia.com/update?hash=wrong-merkle").await,
+            Err(Status::IO_DATA_INTEGRITY)
+        );
+
+        let package_dir =
+            do_resolve(&proxy, "fuchsia-pkg://fuchsia.com/update?hash=correct-merkle")
+                .await
+                .unwrap();
+        assert_eq!(read_file(&package_dir, "meta").await, "correct-merkle");
+    }
 }
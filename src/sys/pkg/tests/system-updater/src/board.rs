@@ -12,7 +12,7 @@ async fn validates_board() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("board", "x64")
         .add_file("zbi", "fake zbi")
         .add_file("bootloader", "new bootloader");
@@ -36,7 +36,7 @@ async fn rejects_mismatched_board() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([SYSTEM_IMAGE_URL]))
+        .with_packages_json([SYSTEM_IMAGE_URL])
         .add_file("board", "arm")
         .add_file("zbi", "fake zbi")
         .add_file("bootloader", "new bootloader");
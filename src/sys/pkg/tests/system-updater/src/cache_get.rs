@@ -0,0 +1,80 @@
+// Copyright 2026 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Tests for `MockCacheService`'s handling of `PackageCache.Get`. The system updater doesn't call
+//! `Get` today (it only calls `Sync`), so these drive the mock directly rather than through a
+//! spawned system updater, in anticipation of a future caller that does.
+
+use {
+    super::*,
+    fidl::endpoints::{create_endpoints, create_proxy, create_proxy_and_stream},
+    fidl_fuchsia_pkg::{BlobId, BlobInfo, NeededBlobsMarker, PackageCacheMarker},
+    vfs::{file::pcb::read_only_static, pseudo_directory},
+};
+
+fn spawn_cache_service(
+    cache_service: Arc<MockCacheService>,
+) -> fidl_fuchsia_pkg::PackageCacheProxy {
+    let (proxy, stream) = create_proxy_and_stream::<PackageCacheMarker>().unwrap();
+    fasync::Task::spawn(
+        cache_service
+            .run_cache_service(stream)
+            .unwrap_or_else(|e| panic!("error running cache service: {:?}", e)),
+    )
+    .detach();
+    proxy
+}
+
+#[fasync::run_singlethreaded(test)]
+async fn defaults_to_not_found() {
+    let interactions = Arc::new(Mutex::new(vec![]));
+    let proxy = spawn_cache_service(Arc::new(MockCacheService::new(Arc::clone(&interactions))));
+
+    let (_needed_blobs, needed_blobs_server) = create_endpoints::<NeededBlobsMarker>().unwrap();
+    let result = proxy
+        .get(
+            &mut BlobInfo { blob_id: BlobId { merkle_root: [0xaa; 32] }, length: 0 },
+            &mut std::iter::empty::<&str>(),
+            needed_blobs_server,
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result, Err(Status::NOT_FOUND.into_raw()));
+    assert_eq!(interactions.lock().clone(), vec![CacheGet(hex::encode([0xaa; 32]))]);
+}
+
+#[fasync::run_singlethreaded(test)]
+async fn cache_hit_serves_the_configured_directory() {
+    let interactions = Arc::new(Mutex::new(vec![]));
+    let cache_service = Arc::new(MockCacheService::new(Arc::clone(&interactions)));
+    cache_service
+        .set_get_response(Ok(pseudo_directory! { "file" => read_only_static("hello") }));
+    let proxy = spawn_cache_service(cache_service);
+
+    let (_needed_blobs, needed_blobs_server) = create_endpoints::<NeededBlobsMarker>().unwrap();
+    let (dir_proxy, dir_server) = create_proxy().unwrap();
+    let result = proxy
+        .get(
+            &mut BlobInfo { blob_id: BlobId { merkle_root: [0xbb; 32] }, length: 0 },
+            &mut std::iter::empty::<&str>(),
+            needed_blobs_server,
+            Some(dir_server),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(interactions.lock().clone(), vec![CacheGet(hex::encode([0xbb; 32]))]);
+
+    let file = io_util::directory::open_file(
+        &dir_proxy,
+        "file",
+        fidl_fuchsia_io::OPEN_RIGHT_READABLE,
+    )
+    .await
+    .expect("served directory to contain \"file\"");
+    assert_eq!(io_util::read_file(&file).await.unwrap(), "hello");
+}
@@ -12,7 +12,7 @@ async fn promotes_target_channel_as_current_channel() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake zbi");
 
     env.run_system_updater_oneshot(SystemUpdaterArgs { ..Default::default() }).await.unwrap();
@@ -34,7 +34,7 @@ async fn succeeds_even_if_target_channel_does_not_exist() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake zbi");
 
     env.run_system_updater_oneshot(SystemUpdaterArgs { ..Default::default() }).await.unwrap();
@@ -53,7 +53,7 @@ async fn does_not_promote_target_channel_on_failure() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake zbi");
 
     let result = env.run_system_updater_oneshot(SystemUpdaterArgs { ..Default::default() }).await;
@@ -14,7 +14,7 @@ async fn test_resolve_error_maps_to_cobalt_status_code(
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([pkg_url]))
+        .with_packages_json([pkg_url])
         .add_file("zbi", "fake zbi");
 
     env.resolver.url(pkg_url).fail(status);
@@ -85,7 +85,7 @@ async fn succeeds_even_if_metrics_fail_to_send() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake zbi");
 
     env.run_system_updater_oneshot(SystemUpdaterArgs {
@@ -22,6 +22,58 @@ async fn fails_on_package_resolver_connect_error() {
         .await;
     assert!(result.is_err(), "system updater succeeded when it should fail");
 
+    InteractionMatcher::new()
+        .paver(PaverEvent::QueryCurrentConfiguration)
+        .paver(PaverEvent::ReadAsset {
+            configuration: paver::Configuration::A,
+            asset: paver::Asset::VerifiedBootMetadata,
+        })
+        .paver(PaverEvent::ReadAsset {
+            configuration: paver::Configuration::A,
+            asset: paver::Asset::Kernel,
+        })
+        .paver(PaverEvent::QueryCurrentConfiguration)
+        .paver(PaverEvent::QueryActiveConfiguration)
+        // The connect succeeds, so the system updater only notices the resolver is not there when
+        // it tries to resolve a package
+        .gc()
+        .assert_matches(env.take_interactions());
+}
+
+#[fasync::run_singlethreaded(test)]
+async fn fails_on_update_package_fetch_error() {
+    let env = TestEnv::builder().oneshot(true).build();
+
+    env.resolver
+        .register_package("update", "upd4t3")
+        .with_packages_json([SYSTEM_IMAGE_URL]);
+
+    let system_image_url = SYSTEM_IMAGE_URL;
+    env.resolver.mock_resolve_failure(system_image_url, Status::NOT_FOUND);
+
+    let result = env
+        .run_system_updater_oneshot(SystemUpdaterArgs {
+            initiator: Some(Initiator::User),
+            target: Some("m3rk13"),
+            ..Default::default()
+        })
+        .await;
+    assert!(result.is_err(), "system updater succeeded when it should fail");
+
+    let loggers = env.logger_factory.loggers.lock().clone();
+    assert_eq!(loggers.len(), 1);
+    let logger = loggers.into_iter().next().unwrap();
+    assert_eq!(
+        OtaMetrics::from_events(logger.cobalt_events.lock().clone()),
+        OtaMetrics {
+            initiator: metrics::OtaResultAttemptsMetricDimensionInitiator::UserInitiatedCheck
+                as u32,
+            phase: metrics::OtaResultAttemptsMetricDimensionPhase::PackageDownload as u32,
+            status_code: metrics::OtaResultAttemptsMetricDimensionStatusCode::Error as u32,
+            target: "m3rk13".into(),
+        }
+    );
+
     assert_eq!(
         env.take_interactions(),
         vec![
@@ -36,23 +88,32 @@ async fn fails_on_package_resolver_connect_error() {
             }),
             Paver(PaverEvent::QueryCurrentConfiguration),
             Paver(PaverEvent::QueryActiveConfiguration),
-            // The connect succeeds, so the system updater only notices the resolver is not there when
-            // it tries to resolve a package
-            Gc
+            Gc,
+            PackageResolve(UPDATE_PKG_URL.to_string()),
+            Gc,
+            PackageResolve(system_image_url.to_string()),
         ]
     );
 }
 
 #[fasync::run_singlethreaded(test)]
-async fn fails_on_update_package_fetch_error() {
+async fn fails_on_system_image_hash_mismatch() {
     let env = TestEnv::builder().oneshot(true).build();
 
+    // The update package's packages.json references a system_image URL pinned to a merkle that
+    // doesn't match what the package resolver actually has on file for that package, modeling a
+    // corrupted or tampered update manifest.
+    let tampered_system_image_url =
+        "fuchsia-pkg://fuchsia.com/system_image/0?hash=ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff";
+
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([SYSTEM_IMAGE_URL]));
-
-    let system_image_url = SYSTEM_IMAGE_URL;
-    env.resolver.mock_resolve_failure(system_image_url, Status::NOT_FOUND);
+        .with_packages_json([tampered_system_image_url]);
+    env.resolver.mock_package_result_with_hash(
+        "fuchsia-pkg://fuchsia.com/system_image/0",
+        SYSTEM_IMAGE_HASH,
+        Ok(env.resolver.package("system_image/0", SYSTEM_IMAGE_HASH)),
+    );
 
     let result = env
         .run_system_updater_oneshot(SystemUpdaterArgs {
@@ -94,7 +155,7 @@ async fn fails_on_update_package_fetch_error() {
             Gc,
             PackageResolve(UPDATE_PKG_URL.to_string()),
             Gc,
-            PackageResolve(system_image_url.to_string()),
+            PackageResolve(tampered_system_image_url.to_string()),
         ]
     );
 }
@@ -116,18 +177,15 @@ async fn fails_on_content_package_fetch_error() {
     let pkg5 = env.resolver.package("package5", merkle_str!("dd"));
 
     env.resolver.url("fuchsia-pkg://fuchsia.com/update").resolve(
-        &env.resolver.package("update", UPDATE_HASH).add_file(
-            "packages.json",
-            make_packages_json([
-                SYSTEM_IMAGE_URL,
-                pkg1_url,
-                pkg2_url,
-                pkg3_url,
-                pkg4_url,
-                pkg5_url,
-                pkg6_url,
-            ]),
-        ),
+        &env.resolver.package("update", UPDATE_HASH).with_packages_json([
+            SYSTEM_IMAGE_URL,
+            pkg1_url,
+            pkg2_url,
+            pkg3_url,
+            pkg4_url,
+            pkg5_url,
+            pkg6_url,
+        ]),
     );
     env.resolver
         .url(SYSTEM_IMAGE_URL)
@@ -218,7 +276,7 @@ async fn fails_when_package_cache_sync_fails() {
     env.cache_service.set_sync_response(Err(Status::INTERNAL));
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([SYSTEM_IMAGE_URL]));
+        .with_packages_json([SYSTEM_IMAGE_URL]);
     env.resolver
         .url(SYSTEM_IMAGE_URL)
         .resolve(&env.resolver.package("system_image/0", SYSTEM_IMAGE_HASH));
@@ -255,3 +313,25 @@ async fn fails_when_package_cache_sync_fails() {
         ]
     );
 }
+
+#[fasync::run_singlethreaded(test)]
+#[should_panic(expected = "UnexpectedResolve")]
+async fn strict_resolver_flags_unregistered_resolve_as_unexpected() {
+    let env = TestEnv::builder().oneshot(true).build();
+
+    // Only register the update package; system_image is intentionally left unregistered.
+    env.resolver.set_strict(true);
+    env.resolver
+        .register_package("update", "upd4t3")
+        .with_packages_json([SYSTEM_IMAGE_URL]);
+
+    let _ = env
+        .run_system_updater_oneshot(SystemUpdaterArgs {
+            initiator: Some(Initiator::User),
+            target: Some("m3rk13"),
+            ..Default::default()
+        })
+        .await;
+
+    env.assert_no_unexpected_resolves();
+}
@@ -19,7 +19,7 @@ async fn succeeds_without_writable_data() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake zbi");
 
     env.run_system_updater_oneshot_args(
@@ -117,7 +117,7 @@ async fn writes_history() {
 
     env.resolver
         .register_package("update", UPDATE_HASH)
-        .add_file("packages.json", make_packages_json(["fuchsia-pkg://fuchsia.com/system_image/0?hash=838b5199d12c8ff4ef92bfd9771d2f8781b7b8fd739dd59bcf63f353a1a93f67"]))
+        .with_packages_json(["fuchsia-pkg://fuchsia.com/system_image/0?hash=838b5199d12c8ff4ef92bfd9771d2f8781b7b8fd739dd59bcf63f353a1a93f67"])
         .add_file("zbi", "fake zbi")
         .add_file("fuchsia.vbmeta", "vbmeta")
         .add_file("version", "0.2");
@@ -187,7 +187,7 @@ async fn replaces_bogus_history() {
 
     env.resolver
         .register_package("update", UPDATE_HASH)
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake zbi");
 
     env.run_system_updater_oneshot(SystemUpdaterArgs { start: Some(42), ..Default::default() })
@@ -245,7 +245,7 @@ async fn increments_attempts_counter_on_retry() {
     env.resolver.url("fuchsia-pkg://fuchsia.com/not-found").fail(Status::NOT_FOUND);
     env.resolver
         .register_package("update", UPDATE_HASH)
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake zbi");
 
     let _ = env
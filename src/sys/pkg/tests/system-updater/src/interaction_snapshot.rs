@@ -0,0 +1,46 @@
+// Copyright 2020 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use {super::*, pretty_assertions::assert_eq};
+
+#[fasync::run_singlethreaded(test)]
+async fn snapshot_accumulates_across_runs_without_clearing() {
+    let env = TestEnv::builder().oneshot(true).build();
+
+    env.resolver
+        .register_package("update", "upd4t3")
+        .with_packages_json([])
+        .add_file("zbi", "fake zbi");
+
+    env.run_system_updater_oneshot(SystemUpdaterArgs {
+        initiator: Some(Initiator::User),
+        target: Some("m3rk13"),
+        ..Default::default()
+    })
+    .await
+    .expect("success");
+
+    let after_first = env.snapshot_interactions();
+    assert!(!after_first.is_empty());
+
+    // Unlike `take_interactions`, snapshotting doesn't clear the buffer, so a second snapshot
+    // before anything else happens is identical to the first.
+    assert_eq!(env.snapshot_interactions(), after_first);
+
+    env.run_system_updater_oneshot(SystemUpdaterArgs {
+        initiator: Some(Initiator::User),
+        target: Some("m3rk13"),
+        ..Default::default()
+    })
+    .await
+    .expect("success");
+
+    // The second run's interactions are appended after the first run's, not in place of them.
+    let after_second = env.snapshot_interactions();
+    assert!(after_second.len() > after_first.len());
+    assert_eq!(&after_second[..after_first.len()], &after_first[..]);
+
+    env.reset_interactions();
+    assert_eq!(env.snapshot_interactions(), vec![]);
+}
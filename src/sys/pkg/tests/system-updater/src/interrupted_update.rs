@@ -0,0 +1,135 @@
+// Copyright 2020 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use super::*;
+
+// The number of interactions `updates_the_system` (mode_normal.rs) records up to and including
+// the first `WriteAsset`: QueryCurrentConfiguration, ReadAsset x2, QueryCurrentConfiguration,
+// QueryActiveConfiguration, Gc, PackageResolve(update), Gc, PackageResolve(system_image),
+// BlobfsSync, then WriteAsset.
+const INTERACTIONS_UP_TO_FIRST_WRITE_ASSET: usize = 11;
+
+#[fasync::run_singlethreaded(test)]
+async fn restarts_from_scratch_after_being_killed_mid_update() {
+    let env = TestEnv::builder().oneshot(true).build();
+
+    env.resolver
+        .register_package("update", "upd4t3")
+        .with_packages_json([SYSTEM_IMAGE_URL])
+        .add_file("zbi", "fake zbi");
+    env.resolver
+        .url(SYSTEM_IMAGE_URL)
+        .resolve(&env.resolver.package("system_image/0", SYSTEM_IMAGE_HASH));
+
+    // Kill the updater right as it starts writing the new kernel, modeling a reboot partway
+    // through the update.
+    env.run_system_updater_oneshot_and_kill_after(
+        SystemUpdaterArgs {
+            initiator: Some(Initiator::User),
+            target: Some("m3rk13"),
+            ..Default::default()
+        },
+        INTERACTIONS_UP_TO_FIRST_WRITE_ASSET,
+    )
+    .await;
+
+    let first_run_interactions = env.take_interactions();
+    assert!(
+        first_run_interactions
+            .iter()
+            .any(|i| matches!(i, Paver(PaverEvent::WriteAsset { .. }))),
+        "expected the interrupted run to have started writing an asset, got {:?}",
+        first_run_interactions
+    );
+    assert!(
+        !first_run_interactions.contains(&Reboot),
+        "the interrupted run shouldn't have made it to reboot, got {:?}",
+        first_run_interactions
+    );
+
+    // Run again. Since this updater doesn't persist any partial-write state that would let it
+    // resume mid-asset-write, it's expected to restart the update from scratch rather than try
+    // to resume -- re-querying configuration and re-resolving packages instead of picking up
+    // where it left off.
+    env.run_system_updater_oneshot(SystemUpdaterArgs {
+        initiator: Some(Initiator::User),
+        target: Some("m3rk13"),
+        ..Default::default()
+    })
+    .await
+    .expect("run system updater");
+
+    InteractionMatcher::new()
+        .paver(PaverEvent::QueryCurrentConfiguration)
+        .paver(PaverEvent::ReadAsset {
+            configuration: paver::Configuration::A,
+            asset: paver::Asset::VerifiedBootMetadata,
+        })
+        .paver(PaverEvent::ReadAsset {
+            configuration: paver::Configuration::A,
+            asset: paver::Asset::Kernel,
+        })
+        .paver(PaverEvent::QueryCurrentConfiguration)
+        .paver(PaverEvent::QueryActiveConfiguration)
+        .gc()
+        .resolve(UPDATE_PKG_URL.to_string())
+        .gc()
+        .resolve(SYSTEM_IMAGE_URL.to_string())
+        .sync()
+        .paver(PaverEvent::WriteAsset {
+            configuration: paver::Configuration::B,
+            asset: paver::Asset::Kernel,
+            payload: b"fake zbi".to_vec(),
+        })
+        .paver(PaverEvent::SetConfigurationActive { configuration: paver::Configuration::B })
+        .paver(PaverEvent::DataSinkFlush)
+        .paver(PaverEvent::BootManagerFlush)
+        .reboot()
+        .assert_matches(env.take_interactions());
+}
+
+#[fasync::run_singlethreaded(test)]
+async fn cancelling_mid_update_skips_reboot() {
+    let env = TestEnv::builder().oneshot(true).build();
+
+    env.resolver
+        .register_package("update", "upd4t3")
+        .with_packages_json([SYSTEM_IMAGE_URL])
+        .add_file("zbi", "fake zbi");
+    env.resolver
+        .url(SYSTEM_IMAGE_URL)
+        .resolve(&env.resolver.package("system_image/0", SYSTEM_IMAGE_HASH));
+
+    let mut updater = env.run_system_updater_cancellable(SystemUpdaterArgs {
+        initiator: Some(Initiator::User),
+        target: Some("m3rk13"),
+        ..Default::default()
+    });
+
+    // Cancel right as the updater starts writing the new kernel, modeling a user-initiated
+    // cancellation partway through the update.
+    let interactions = Arc::clone(&env.interactions);
+    loop {
+        if interactions.lock().len() >= INTERACTIONS_UP_TO_FIRST_WRITE_ASSET {
+            let _ = updater.kill();
+            break;
+        }
+        fasync::Timer::new(fasync::Time::after(zx::Duration::from_millis(1))).await;
+    }
+    updater.wait().await;
+
+    let recorded = env.take_interactions();
+    assert!(
+        !recorded.contains(&Reboot),
+        "a cancelled update shouldn't have made it to reboot, got {:?}",
+        recorded
+    );
+    // Cancellation happens as soon as the new kernel is written, before the new configuration is
+    // flipped active or flushed -- pin that DataSinkFlush hasn't run yet at this point.
+    assert!(
+        !recorded.contains(&Paver(PaverEvent::DataSinkFlush)),
+        "expected cancellation before DataSinkFlush, got {:?}",
+        recorded
+    );
+}
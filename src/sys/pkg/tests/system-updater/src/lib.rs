@@ -4,9 +4,13 @@
 
 #![cfg(test)]
 use {
-    self::SystemUpdaterInteraction::{BlobfsSync, Gc, PackageResolve, Paver, Reboot},
+    self::SystemUpdaterInteraction::{
+        BlobfsSync, CacheGet, Gc, PackageResolve, Paver, Reboot, UnexpectedResolve,
+    },
     anyhow::Error,
-    cobalt_sw_delivery_registry as metrics, fidl_fuchsia_paver as paver,
+    cobalt_sw_delivery_registry as metrics,
+    fidl::{endpoints::ServerEnd, Error as FidlError},
+    fidl_fuchsia_paver as paver,
     fidl_fuchsia_pkg::PackageResolverRequestStream,
     fidl_fuchsia_sys::{LauncherProxy, TerminationReason},
     fidl_fuchsia_update_installer::{InstallerMarker, InstallerProxy},
@@ -16,9 +20,8 @@ use {
         client::{App, AppBuilder},
         server::{NestedEnvironment, ServiceFs},
     },
-    fuchsia_pkg_testing::make_packages_json,
-    fuchsia_zircon::Status,
-    futures::prelude::*,
+    fuchsia_zircon::{self as zx, Status},
+    futures::{pin_mut, prelude::*, select},
     mock_paver::{MockPaverService, MockPaverServiceBuilder, PaverEvent},
     mock_reboot::MockRebootService,
     mock_resolver::MockResolverService,
@@ -36,10 +39,13 @@ use {
 };
 
 mod board;
+mod cache_get;
 mod channel;
 mod cobalt_metrics;
 mod fetch_packages;
 mod history;
+mod interaction_snapshot;
+mod interrupted_update;
 mod mode_force_recovery;
 mod mode_normal;
 mod options;
@@ -55,10 +61,12 @@ mod writes_images;
 #[derive(Debug, PartialEq, Clone)]
 enum SystemUpdaterInteraction {
     BlobfsSync,
+    CacheGet(String),
     Gc,
     PackageResolve(String),
     Paver(PaverEvent),
     Reboot,
+    UnexpectedResolve(String),
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
@@ -73,6 +81,10 @@ enum Protocol {
 
 type SystemUpdaterInteractions = Arc<Mutex<Vec<SystemUpdaterInteraction>>>;
 
+/// How long [TestEnv::run_system_updater_oneshot_args] waits for the system updater to exit
+/// before failing the test, rather than hanging forever if a mock service never responds.
+const RUN_SYSTEM_UPDATER_TIMEOUT: zx::Duration = zx::Duration::from_seconds(120);
+
 struct TestEnvBuilder {
     paver_service_builder: MockPaverServiceBuilder,
     blocked_protocols: HashSet<Protocol>,
@@ -167,9 +179,17 @@ impl TestEnvBuilder {
 
         let resolver = {
             let interactions = Arc::clone(&interactions);
-            Arc::new(MockResolverService::new(Some(Box::new(move |resolved_url: &str| {
-                interactions.lock().push(PackageResolve(resolved_url.to_owned()))
-            }))))
+            let resolver =
+                Arc::new(MockResolverService::new(Some(Box::new(move |resolved_url: &str| {
+                    interactions.lock().push(PackageResolve(resolved_url.to_owned()))
+                }))));
+
+            let interactions = Arc::clone(&interactions);
+            resolver.set_unexpected_resolve_hook(Box::new(move |resolved_url: &str| {
+                interactions.lock().push(UnexpectedResolve(resolved_url.to_owned()))
+            }));
+
+            resolver
         };
 
         let reboot_service = {
@@ -299,6 +319,26 @@ struct TestEnv {
     system_updater: Option<App>,
 }
 
+/// A handle to a system updater component spawned via [TestEnv::run_system_updater_cancellable],
+/// which the test can use to kill the component mid-flight instead of running it to completion.
+struct CancellableSystemUpdater {
+    app: App,
+}
+
+impl CancellableSystemUpdater {
+    /// Kills the system updater component. The caller should still [Self::wait] afterwards to
+    /// observe the exit.
+    fn kill(&mut self) -> Result<(), FidlError> {
+        self.app.kill()
+    }
+
+    /// Waits for the system updater component to exit, whether on its own or because it was
+    /// killed via [Self::kill].
+    async fn wait(&mut self) {
+        let _ = self.app.wait().await;
+    }
+}
+
 impl TestEnv {
     fn new() -> Self {
         Self::builder().build()
@@ -316,6 +356,32 @@ impl TestEnv {
         std::mem::replace(&mut *self.interactions.lock(), vec![])
     }
 
+    /// Returns a copy of the interactions recorded so far, without clearing them. Unlike
+    /// `take_interactions`, this can be called multiple times across a single system-updater run
+    /// to observe interactions as they accumulate.
+    fn snapshot_interactions(&self) -> Vec<SystemUpdaterInteraction> {
+        self.interactions.lock().clone()
+    }
+
+    /// Clears the recorded interactions without returning them.
+    fn reset_interactions(&self) {
+        self.interactions.lock().clear();
+    }
+
+    /// Asserts that, while the resolver was in strict mode (see
+    /// `MockResolverService::set_strict`), no resolve requests arrived for URLs that were never
+    /// registered with the resolver.
+    fn assert_no_unexpected_resolves(&self) {
+        let unexpected: Vec<_> = self
+            .interactions
+            .lock()
+            .iter()
+            .filter(|interaction| matches!(interaction, UnexpectedResolve(_)))
+            .cloned()
+            .collect();
+        assert_eq!(unexpected, Vec::<SystemUpdaterInteraction>::new());
+    }
+
     /// Set the name of the board that system-updater is running on.
     fn set_board_name(&self, board: impl AsRef<str>) {
         // Write the "board" file into the build-info directory.
@@ -378,7 +444,7 @@ impl TestEnv {
     ) -> Result<(), fuchsia_component::client::OutputError> {
         let launcher = self.launcher();
 
-        let output = system_updater_app_builder(
+        let output_fut = system_updater_app_builder(
             &self.data_path,
             &self.build_info_path,
             &self.misc_path,
@@ -387,8 +453,21 @@ impl TestEnv {
         )
         .output(launcher)
         .expect("system updater to launch")
-        .await
-        .expect("no errors while waiting for exit");
+        .fuse();
+        pin_mut!(output_fut);
+
+        // A mock service that never responds (e.g. via `MockCacheService::set_response_delay`)
+        // would otherwise hang the system updater, and this test, forever. Fail with a clear
+        // message instead.
+        let output = select! {
+            output = output_fut => output.expect("no errors while waiting for exit"),
+            _ = fasync::Timer::new(fasync::Time::after(RUN_SYSTEM_UPDATER_TIMEOUT)).fuse() => {
+                panic!(
+                    "system updater did not exit within {:?}; a mock service is likely hung",
+                    RUN_SYSTEM_UPDATER_TIMEOUT
+                );
+            }
+        };
 
         if !output.stdout.is_empty() {
             eprintln!("TEST: system updater stdout:\n{}", String::from_utf8_lossy(&output.stdout));
@@ -402,6 +481,64 @@ impl TestEnv {
         output.ok()
     }
 
+    /// Runs the system updater to model a reboot partway through an update: the process is
+    /// killed as soon as `kill_after` interactions have been recorded (e.g. after the first
+    /// `WriteAsset`), rather than being allowed to run to completion. Returns once the process
+    /// has exited, either on its own or because it was killed.
+    async fn run_system_updater_oneshot_and_kill_after<'a>(
+        &self,
+        args: SystemUpdaterArgs<'a>,
+        kill_after: usize,
+    ) {
+        let launcher = self.launcher();
+        let mut app = system_updater_app_builder(
+            &self.data_path,
+            &self.build_info_path,
+            &self.misc_path,
+            args,
+            Default::default(),
+        )
+        .spawn(launcher)
+        .expect("system updater to launch");
+
+        let interactions = Arc::clone(&self.interactions);
+        let wait_fut = app.wait().fuse();
+        pin_mut!(wait_fut);
+        loop {
+            select! {
+                _ = wait_fut => return,
+                _ = fasync::Timer::new(fasync::Time::after(zx::Duration::from_millis(1))).fuse() => {
+                    if interactions.lock().len() >= kill_after {
+                        // The process may have exited on its own in the meantime; either way,
+                        // `wait_fut` above will observe the exit on the next loop iteration.
+                        let _ = app.kill();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Like [TestEnv::run_system_updater_oneshot], but spawns the system updater and returns a
+    /// [CancellableSystemUpdater] handle instead of waiting for it to exit, so the caller can
+    /// cancel it mid-flight (e.g. to pin its cleanup behavior on cancellation) rather than
+    /// running it to completion via `output()`.
+    fn run_system_updater_cancellable<'a>(
+        &self,
+        args: SystemUpdaterArgs<'a>,
+    ) -> CancellableSystemUpdater {
+        let launcher = self.launcher();
+        let app = system_updater_app_builder(
+            &self.data_path,
+            &self.build_info_path,
+            &self.misc_path,
+            args,
+            Default::default(),
+        )
+        .spawn(launcher)
+        .expect("system updater to launch");
+        CancellableSystemUpdater { app }
+    }
+
     /// Opens a connection to the installer fidl service, panicking if the system updater was not
     /// started as a fidl service.
     fn installer_proxy(&self) -> InstallerProxy {
@@ -533,22 +670,48 @@ impl SystemUpdaterArgs<'_> {
 
 struct MockCacheService {
     sync_response: Mutex<Option<Result<(), Status>>>,
+    // Defaults to `Err(Status::NOT_FOUND)`, so tests must opt in to a cache hit.
+    get_response: Mutex<Result<Arc<dyn vfs::directory::entry::DirectoryEntry>, Status>>,
     interactions: SystemUpdaterInteractions,
+    response_delay: Mutex<Option<zx::Duration>>,
 }
 impl MockCacheService {
     fn new(interactions: SystemUpdaterInteractions) -> Self {
-        Self { sync_response: Mutex::new(None), interactions }
+        Self {
+            sync_response: Mutex::new(None),
+            get_response: Mutex::new(Err(Status::NOT_FOUND)),
+            interactions,
+            response_delay: Mutex::new(None),
+        }
     }
 
     fn set_sync_response(&self, response: Result<(), Status>) {
         self.sync_response.lock().replace(response);
     }
 
+    /// Configures the response to the next (and all subsequent) `PackageCache.Get` requests: a
+    /// directory to serve on a cache hit, or a status to fail the request with.
+    fn set_get_response(
+        &self,
+        response: Result<Arc<dyn vfs::directory::entry::DirectoryEntry>, Status>,
+    ) {
+        *self.get_response.lock() = response;
+    }
+
+    /// Delays every response from this mock by `delay`, to simulate a slow PackageCache and
+    /// exercise the system updater's handling of (or tolerance for) a slow dependency.
+    fn set_response_delay(&self, delay: zx::Duration) {
+        self.response_delay.lock().replace(delay);
+    }
+
     async fn run_cache_service(
         self: Arc<Self>,
         mut stream: fidl_fuchsia_pkg::PackageCacheRequestStream,
     ) -> Result<(), Error> {
         while let Some(event) = stream.try_next().await? {
+            if let Some(delay) = *self.response_delay.lock() {
+                fasync::Timer::new(fasync::Time::after(delay)).await;
+            }
             match event {
                 fidl_fuchsia_pkg::PackageCacheRequest::Sync { responder } => {
                     self.interactions.lock().push(BlobfsSync);
@@ -556,6 +719,37 @@ impl MockCacheService {
                         &mut self.sync_response.lock().unwrap_or(Ok(())).map_err(|s| s.into_raw()),
                     )?;
                 }
+                fidl_fuchsia_pkg::PackageCacheRequest::Get {
+                    meta_far_blob,
+                    selectors: _,
+                    needed_blobs,
+                    dir,
+                    responder,
+                } => {
+                    self.interactions
+                        .lock()
+                        .push(CacheGet(hex::encode(meta_far_blob.blob_id.merkle_root)));
+
+                    // This mock never reports any blobs missing, so just drop the iterator
+                    // rather than serving it.
+                    drop(needed_blobs);
+
+                    match &*self.get_response.lock() {
+                        Ok(served_dir) => {
+                            if let Some(dir) = dir {
+                                served_dir.clone().open(
+                                    vfs::execution_scope::ExecutionScope::new(),
+                                    fidl_fuchsia_io::OPEN_RIGHT_READABLE,
+                                    0,
+                                    vfs::path::Path::empty(),
+                                    ServerEnd::new(dir.into_channel()),
+                                );
+                            }
+                            responder.send(&mut Ok(()))?;
+                        }
+                        Err(status) => responder.send(&mut Err(status.into_raw()))?,
+                    }
+                }
                 other => panic!("unsupported PackageCache request: {:?}", other),
             }
         }
@@ -770,6 +964,51 @@ fn resolved_urls(interactions: SystemUpdaterInteractions) -> Vec<String> {
         .collect()
 }
 
+/// A fluent builder for the sequence of interactions the system-updater is expected to have with
+/// external services. Building up the expectation with `.gc()`/`.resolve(url)`/etc. and then
+/// calling `.assert_matches(env.take_interactions())` produces a readable diff (courtesy of
+/// `pretty_assertions`) if the actual sequence doesn't match.
+#[derive(Debug, Default)]
+struct InteractionMatcher {
+    expected: Vec<SystemUpdaterInteraction>,
+}
+
+impl InteractionMatcher {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn gc(mut self) -> Self {
+        self.expected.push(Gc);
+        self
+    }
+
+    fn sync(mut self) -> Self {
+        self.expected.push(BlobfsSync);
+        self
+    }
+
+    fn resolve(mut self, url: impl Into<String>) -> Self {
+        self.expected.push(PackageResolve(url.into()));
+        self
+    }
+
+    fn paver(mut self, event: PaverEvent) -> Self {
+        self.expected.push(Paver(event));
+        self
+    }
+
+    fn reboot(mut self) -> Self {
+        self.expected.push(Reboot);
+        self
+    }
+
+    /// Asserts that `actual` is exactly the expected interaction sequence built up so far.
+    fn assert_matches(self, actual: Vec<SystemUpdaterInteraction>) {
+        assert_eq!(actual, self.expected);
+    }
+}
+
 fn default_options() -> Options {
     Options {
         initiator: fidl_fuchsia_update_installer_ext::Initiator::User,
@@ -25,7 +25,7 @@ async fn writes_recovery_and_force_reboots_into_it() {
     let package_url = SYSTEM_IMAGE_URL;
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([package_url]))
+        .with_packages_json([package_url])
         .add_file("update-mode", &force_recovery_json())
         .add_file("recovery", "the recovery image")
         .add_file("recovery.vbmeta", "the recovery vbmeta");
@@ -98,7 +98,7 @@ async fn reboots_regardless_of_reboot_arg() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages", make_packages_json([]))
+        .with_packages_list([])
         .add_file("update-mode", &force_recovery_json());
 
     env.run_system_updater_oneshot(SystemUpdaterArgs {
@@ -120,7 +120,7 @@ async fn reboots_regardless_of_reboot_controller() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages", make_packages_json([]))
+        .with_packages_list([])
         .add_file("update-mode", &force_recovery_json());
 
     // Start the system update.
@@ -149,7 +149,7 @@ async fn rejects_zbi() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([SYSTEM_IMAGE_URL]))
+        .with_packages_json([SYSTEM_IMAGE_URL])
         .add_file("update-mode", &force_recovery_json())
         .add_file("bootloader", "new bootloader")
         .add_file("zbi", "fake zbi");
@@ -190,7 +190,7 @@ async fn rejects_skip_recovery_flag() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages", make_packages_json([]))
+        .with_packages_list([])
         .add_file("update-mode", &force_recovery_json());
 
     let result = env
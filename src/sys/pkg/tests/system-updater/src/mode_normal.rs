@@ -9,13 +9,40 @@ use {
     pretty_assertions::assert_eq,
 };
 
+#[fasync::run_singlethreaded(test)]
+async fn updates_the_system_through_a_slow_cache_service() {
+    let env = TestEnv::builder().oneshot(true).build();
+
+    // A delay well under `RUN_SYSTEM_UPDATER_TIMEOUT`, so the updater is expected to simply wait
+    // out the slow PackageCache responses rather than time out.
+    env.cache_service.set_response_delay(zx::Duration::from_millis(10));
+
+    env.resolver
+        .register_package("update", "upd4t3")
+        .with_packages_json([SYSTEM_IMAGE_URL])
+        .add_file("zbi", "fake zbi");
+    env.resolver
+        .url(SYSTEM_IMAGE_URL)
+        .resolve(&env.resolver.package("system_image/0", SYSTEM_IMAGE_HASH));
+
+    env.run_system_updater_oneshot(SystemUpdaterArgs {
+        initiator: Some(Initiator::User),
+        target: Some("m3rk13"),
+        ..Default::default()
+    })
+    .await
+    .expect("run system updater");
+
+    assert!(env.take_interactions().contains(&Reboot));
+}
+
 #[fasync::run_singlethreaded(test)]
 async fn updates_the_system() {
     let env = TestEnv::builder().oneshot(true).build();
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([SYSTEM_IMAGE_URL]))
+        .with_packages_json([SYSTEM_IMAGE_URL])
         .add_file("zbi", "fake zbi");
     env.resolver
         .url(SYSTEM_IMAGE_URL)
@@ -81,7 +108,7 @@ async fn requires_zbi() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([SYSTEM_IMAGE_URL]))
+        .with_packages_json([SYSTEM_IMAGE_URL])
         .add_file("bootloader", "new bootloader");
     env.resolver
         .url(SYSTEM_IMAGE_URL)
@@ -125,7 +152,7 @@ async fn updates_the_system_no_oneshot() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([SYSTEM_IMAGE_URL]))
+        .with_packages_json([SYSTEM_IMAGE_URL])
         .add_file("zbi", "fake zbi");
     env.resolver
         .url(SYSTEM_IMAGE_URL)
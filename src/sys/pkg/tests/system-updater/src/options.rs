@@ -10,7 +10,7 @@ async fn uses_custom_update_package() {
 
     env.resolver
         .register_custom_package("another-update/4", "update", "upd4t3r", "fuchsia.com")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake zbi");
 
     env.run_system_updater_oneshot(SystemUpdaterArgs {
@@ -80,7 +80,7 @@ async fn rejects_unknown_flags() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([SYSTEM_IMAGE_URL]))
+        .with_packages_json([SYSTEM_IMAGE_URL])
         .add_file("zbi", "fake zbi");
 
     let result = env
@@ -99,7 +99,7 @@ async fn rejects_extra_args() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([SYSTEM_IMAGE_URL]))
+        .with_packages_json([SYSTEM_IMAGE_URL])
         .add_file("zbi", "fake zbi");
 
     let result = env
@@ -119,7 +119,7 @@ async fn does_not_reboot_if_requested_not_to_reboot() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake zbi");
 
     env.run_system_updater_oneshot(SystemUpdaterArgs {
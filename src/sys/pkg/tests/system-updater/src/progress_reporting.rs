@@ -24,7 +24,7 @@ async fn progress_reporting_fetch_multiple_packages() {
     let update_pkg = env
         .resolver
         .package("update", UPDATE_HASH)
-        .add_file("packages.json", make_packages_json([pkg1_url, pkg2_url, pkg3_url]));
+        .with_packages_json([pkg1_url, pkg2_url, pkg3_url]);
     let pkg1 = env.resolver.package("package1", merkle_str!("aa"));
     let pkg2 = env.resolver.package("package2", merkle_str!("bb"));
     let pkg3 = env.resolver.package("package3", merkle_str!("cc"));
@@ -113,7 +113,7 @@ async fn monitor_connects_to_existing_attempt() {
     let update_pkg = env
         .resolver
         .package("update", UPDATE_HASH)
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake zbi");
 
     // Block the update pkg resolve to ensure the update attempt is still in
@@ -155,7 +155,7 @@ async fn succeed_additional_start_requests_when_compatible() {
     let update_pkg = env
         .resolver
         .package("update", UPDATE_HASH)
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake zbi");
 
     // Block the update pkg resolve to ensure the update attempt is still in
@@ -203,7 +203,7 @@ async fn fail_additional_start_requests_when_not_compatible() {
 
     env.resolver
         .package("update", UPDATE_HASH)
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake zbi");
 
     // Block the update pkg resolve to ensure the update attempt is still in
@@ -14,7 +14,7 @@ async fn reboot_controller_detach_causes_deferred_reboot() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake zbi");
 
     // Start the system update.
@@ -45,7 +45,7 @@ async fn reboot_controller_unblock_causes_reboot() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake zbi");
 
     // Start the system update.
@@ -76,7 +76,7 @@ async fn reboot_controller_dropped_causes_reboot() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake zbi");
 
     // Start the system update.
@@ -2,7 +2,10 @@
 // Use of this source code is governed by a BSD-style license that can be
 // found in the LICENSE file.
 
-use {super::*, pretty_assertions::assert_eq};
+use {
+    super::*, fuchsia_pkg_testing::make_packages_json_with_version, pretty_assertions::assert_eq,
+    serde_json::json,
+};
 
 #[fasync::run_singlethreaded(test)]
 async fn rejects_invalid_package_name() {
@@ -12,7 +15,7 @@ async fn rejects_invalid_package_name() {
     // validate the update package.
     env.resolver
         .register_custom_package("not_update", "not_update", "upd4t3", "fuchsia.com")
-        .add_file("packages.json", make_packages_json([SYSTEM_IMAGE_URL]))
+        .with_packages_json([SYSTEM_IMAGE_URL])
         .add_file("zbi", "fake zbi")
         .add_file("zedboot", "new recovery");
 
@@ -100,3 +103,135 @@ async fn fails_if_package_unavailable() {
         ]
     );
 }
+
+#[fasync::run_singlethreaded(test)]
+async fn accepts_packages_json_string_version() {
+    let env = TestEnv::builder().oneshot(true).build();
+
+    env.resolver
+        .register_package("update", "upd4t3")
+        .add_file(
+            "packages.json",
+            make_packages_json_with_version([SYSTEM_IMAGE_URL], json!("1")),
+        )
+        .add_file("zbi", "fake zbi");
+    env.resolver
+        .url(SYSTEM_IMAGE_URL)
+        .resolve(&env.resolver.package("system_image/0", SYSTEM_IMAGE_HASH));
+
+    let result = env
+        .run_system_updater_oneshot(SystemUpdaterArgs {
+            initiator: Some(Initiator::User),
+            target: Some("m3rk13"),
+            ..Default::default()
+        })
+        .await;
+    assert!(result.is_ok(), "system updater failed: {:?}", result);
+
+    InteractionMatcher::new()
+        .paver(PaverEvent::QueryCurrentConfiguration)
+        .paver(PaverEvent::ReadAsset {
+            configuration: paver::Configuration::A,
+            asset: paver::Asset::VerifiedBootMetadata,
+        })
+        .paver(PaverEvent::ReadAsset {
+            configuration: paver::Configuration::A,
+            asset: paver::Asset::Kernel,
+        })
+        .paver(PaverEvent::QueryCurrentConfiguration)
+        .paver(PaverEvent::QueryActiveConfiguration)
+        .gc()
+        .resolve(UPDATE_PKG_URL)
+        .gc()
+        .resolve(SYSTEM_IMAGE_URL)
+        .sync()
+        .paver(PaverEvent::WriteAsset {
+            configuration: paver::Configuration::B,
+            asset: paver::Asset::Kernel,
+            payload: b"fake zbi".to_vec(),
+        })
+        .paver(PaverEvent::SetConfigurationActive { configuration: paver::Configuration::B })
+        .paver(PaverEvent::DataSinkFlush)
+        .paver(PaverEvent::BootManagerFlush)
+        .reboot()
+        .assert_matches(env.take_interactions());
+}
+
+#[fasync::run_singlethreaded(test)]
+async fn rejects_unsupported_packages_json_version() {
+    let env = TestEnv::builder().oneshot(true).build();
+
+    env.resolver.register_package("update", "upd4t3").add_file(
+        "packages.json",
+        make_packages_json_with_version([SYSTEM_IMAGE_URL], json!("2")),
+    );
+
+    let result = env
+        .run_system_updater_oneshot(SystemUpdaterArgs {
+            initiator: Some(Initiator::User),
+            target: Some("m3rk13"),
+            ..Default::default()
+        })
+        .await;
+    assert!(result.is_err(), "system updater succeeded when it should fail");
+
+    // The update package's own packages.json fails to parse, so we should never have resolved
+    // the packages it would have listed.
+    assert_eq!(resolved_urls(env.interactions.clone()), vec![UPDATE_PKG_URL.to_string()]);
+}
+
+#[fasync::run_singlethreaded(test)]
+async fn with_packages_json_resolves_listed_urls() {
+    let env = TestEnv::builder().oneshot(true).build();
+
+    env.resolver
+        .register_package("update", "upd4t3")
+        .with_packages_json([SYSTEM_IMAGE_URL])
+        .add_file("zbi", "fake zbi");
+    env.resolver
+        .url(SYSTEM_IMAGE_URL)
+        .resolve(&env.resolver.package("system_image/0", SYSTEM_IMAGE_HASH));
+
+    let result = env
+        .run_system_updater_oneshot(SystemUpdaterArgs {
+            initiator: Some(Initiator::User),
+            target: Some("m3rk13"),
+            ..Default::default()
+        })
+        .await;
+    assert!(result.is_ok(), "system updater failed: {:?}", result);
+
+    assert_eq!(
+        resolved_urls(env.interactions.clone()),
+        vec![UPDATE_PKG_URL.to_string(), SYSTEM_IMAGE_URL.to_string()]
+    );
+}
+
+#[fasync::run_singlethreaded(test)]
+async fn with_packages_list_resolves_listed_urls() {
+    let env = TestEnv::builder().oneshot(true).build();
+
+    // `with_packages_list` is an alias of `with_packages_json` (see its doc comment), since this
+    // tree has no separate line-delimited `packages` format -- assert it resolves the same urls.
+    env.resolver
+        .register_package("update", "upd4t3")
+        .with_packages_list([SYSTEM_IMAGE_URL])
+        .add_file("zbi", "fake zbi");
+    env.resolver
+        .url(SYSTEM_IMAGE_URL)
+        .resolve(&env.resolver.package("system_image/0", SYSTEM_IMAGE_HASH));
+
+    let result = env
+        .run_system_updater_oneshot(SystemUpdaterArgs {
+            initiator: Some(Initiator::User),
+            target: Some("m3rk13"),
+            ..Default::default()
+        })
+        .await;
+    assert!(result.is_ok(), "system updater failed: {:?}", result);
+
+    assert_eq!(
+        resolved_urls(env.interactions.clone()),
+        vec![UPDATE_PKG_URL.to_string(), SYSTEM_IMAGE_URL.to_string()]
+    );
+}
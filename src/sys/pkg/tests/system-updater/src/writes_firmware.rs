@@ -10,7 +10,7 @@ async fn writes_bootloader() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake zbi")
         .add_file("bootloader", "new bootloader");
 
@@ -64,7 +64,7 @@ async fn writes_firmware() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake zbi")
         .add_file("firmware", "fake firmware");
 
@@ -118,7 +118,7 @@ async fn writes_multiple_firmware_types() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake zbi")
         .add_file("firmware_a", "fake firmware A")
         .add_file("firmware_b", "fake firmware B");
@@ -197,7 +197,7 @@ async fn skips_unsupported_firmware_type() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake zbi")
         .add_file("firmware", "fake firmware");
 
@@ -246,6 +246,88 @@ async fn skips_unsupported_firmware_type() {
     );
 }
 
+#[fasync::run_singlethreaded(test)]
+async fn skips_unsupported_firmware_type_per_type() {
+    let env = TestEnv::builder()
+        .paver_service(|builder| {
+            let mut per_type = std::collections::HashMap::new();
+            per_type.insert("b".to_string(), paver::WriteFirmwareResult::Unsupported(true));
+            builder.firmware_hook_per_type(per_type)
+        })
+        .oneshot(true)
+        .build();
+
+    env.resolver
+        .register_package("update", "upd4t3")
+        .with_packages_json([])
+        .add_file("zbi", "fake zbi")
+        .add_file("firmware_a", "fake firmware A")
+        .add_file("firmware_b", "fake firmware B");
+
+    // Update should still succeed even though firmware type "b" is unsupported -- only that
+    // type's write is skipped, not the rest of the update.
+    env.run_system_updater_oneshot(SystemUpdaterArgs {
+        initiator: Some(Initiator::User),
+        target: Some("m3rk13"),
+        ..Default::default()
+    })
+    .await
+    .expect("success");
+
+    let mut interactions = env.take_interactions();
+    // The order of files listed from a directory isn't guaranteed so the firmware could be
+    // written in either order. Sort by type string so we can easily validate contents.
+    interactions[9..11].sort_by_key(|event| {
+        if let Paver(PaverEvent::WriteFirmware { configuration: _, firmware_type, payload: _ }) =
+            event
+        {
+            return firmware_type.clone();
+        } else {
+            panic!("Not a WriteFirmware event: {:?}", event);
+        }
+    });
+
+    assert_eq!(
+        interactions,
+        vec![
+            Paver(PaverEvent::QueryCurrentConfiguration),
+            Paver(PaverEvent::ReadAsset {
+                configuration: paver::Configuration::A,
+                asset: paver::Asset::VerifiedBootMetadata
+            }),
+            Paver(PaverEvent::ReadAsset {
+                configuration: paver::Configuration::A,
+                asset: paver::Asset::Kernel
+            }),
+            Paver(PaverEvent::QueryCurrentConfiguration),
+            Paver(PaverEvent::QueryActiveConfiguration),
+            Gc,
+            PackageResolve(UPDATE_PKG_URL.to_string()),
+            Gc,
+            BlobfsSync,
+            Paver(PaverEvent::WriteFirmware {
+                configuration: paver::Configuration::B,
+                firmware_type: "a".to_string(),
+                payload: b"fake firmware A".to_vec()
+            }),
+            Paver(PaverEvent::WriteFirmware {
+                configuration: paver::Configuration::B,
+                firmware_type: "b".to_string(),
+                payload: b"fake firmware B".to_vec()
+            }),
+            Paver(PaverEvent::WriteAsset {
+                configuration: paver::Configuration::B,
+                asset: paver::Asset::Kernel,
+                payload: b"fake zbi".to_vec(),
+            }),
+            Paver(PaverEvent::SetConfigurationActive { configuration: paver::Configuration::B }),
+            Paver(PaverEvent::DataSinkFlush),
+            Paver(PaverEvent::BootManagerFlush),
+            Reboot,
+        ]
+    );
+}
+
 #[fasync::run_singlethreaded(test)]
 async fn fails_on_firmware_write_error() {
     let env = TestEnv::builder()
@@ -258,7 +340,7 @@ async fn fails_on_firmware_write_error() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake zbi")
         .add_file("firmware", "fake firmware");
 
@@ -10,7 +10,7 @@ async fn fails_on_paver_connect_error() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake_zbi");
 
     let result = env
@@ -50,7 +50,7 @@ async fn fails_on_image_write_error() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake_zbi");
 
     let result = env
@@ -109,7 +109,7 @@ async fn skip_recovery_does_not_write_recovery_or_vbmeta() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake zbi")
         .add_file("zedboot", "new recovery")
         .add_file("recovery.vbmeta", "new recovery vbmeta");
@@ -123,8 +123,21 @@ async fn skip_recovery_does_not_write_recovery_or_vbmeta() {
     .await
     .expect("success");
 
+    let interactions = env.take_interactions();
+
+    // The recovery kernel and recovery vbmeta are coupled: skipping one without the other would
+    // leave the recovery slot with mismatched kernel/vbmeta, so assert neither is written.
+    assert!(
+        !interactions.iter().any(|i| matches!(
+            i,
+            Paver(PaverEvent::WriteAsset { configuration: paver::Configuration::Recovery, .. })
+        )),
+        "expected no writes to the recovery configuration, got {:?}",
+        interactions
+    );
+
     assert_eq!(
-        env.take_interactions(),
+        interactions,
         vec![
             Paver(PaverEvent::QueryCurrentConfiguration),
             Paver(PaverEvent::ReadAsset {
@@ -154,6 +167,54 @@ async fn skip_recovery_does_not_write_recovery_or_vbmeta() {
     );
 }
 
+#[fasync::run_singlethreaded(test)]
+async fn writes_recovery_vbmeta_when_not_skipped() {
+    let env = TestEnv::builder().oneshot(true).build();
+
+    env.resolver
+        .register_package("update", "upd4t3")
+        .with_packages_json([])
+        .add_file("zbi", "fake zbi")
+        .add_file("zedboot", "new recovery")
+        .add_file("recovery.vbmeta", "new recovery vbmeta");
+
+    env.run_system_updater_oneshot(SystemUpdaterArgs {
+        initiator: Some(Initiator::User),
+        target: Some("m3rk13"),
+        skip_recovery: Some(false),
+        ..Default::default()
+    })
+    .await
+    .expect("success");
+
+    let interactions = env.take_interactions();
+
+    assert!(
+        interactions.iter().any(|i| matches!(
+            i,
+            Paver(PaverEvent::WriteAsset {
+                configuration: paver::Configuration::Recovery,
+                asset: paver::Asset::VerifiedBootMetadata,
+                ..
+            })
+        )),
+        "expected recovery.vbmeta to be written when skip_recovery is false, got {:?}",
+        interactions
+    );
+    assert!(
+        interactions.iter().any(|i| matches!(
+            i,
+            Paver(PaverEvent::WriteAsset {
+                configuration: paver::Configuration::Recovery,
+                asset: paver::Asset::Kernel,
+                ..
+            })
+        )),
+        "expected the recovery kernel to be written when skip_recovery is false, got {:?}",
+        interactions
+    );
+}
+
 #[fasync::run_singlethreaded(test)]
 async fn writes_to_both_configs_if_abr_not_supported() {
     let env = TestEnv::builder()
@@ -163,7 +224,7 @@ async fn writes_to_both_configs_if_abr_not_supported() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake_zbi");
 
     env.run_system_updater_oneshot(SystemUpdaterArgs {
@@ -233,7 +294,7 @@ async fn updates_even_if_cant_set_active_partition_healthy() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake_zbi");
 
     env.run_system_updater_oneshot(SystemUpdaterArgs {
@@ -409,7 +470,7 @@ async fn writes_recovery_called_legacy_zedboot() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake zbi")
         .add_file("zedboot", "new recovery");
 
@@ -464,7 +525,7 @@ async fn writes_recovery() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake zbi")
         .add_file("recovery", "new recovery");
 
@@ -518,7 +579,7 @@ async fn writes_recovery_vbmeta() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake zbi")
         .add_file("zedboot", "new recovery")
         .add_file("recovery.vbmeta", "new recovery vbmeta");
@@ -578,7 +639,7 @@ async fn writes_fuchsia_vbmeta() {
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake zbi")
         .add_file("fuchsia.vbmeta", "fake zbi vbmeta");
 
@@ -650,7 +711,7 @@ async fn update_with_custom_config_status(
 
     env.resolver
         .register_package("update", "upd4t3")
-        .add_file("packages.json", make_packages_json([]))
+        .with_packages_json([])
         .add_file("zbi", "fake_zbi");
 
     env.run_system_updater_oneshot(SystemUpdaterArgs {